@@ -0,0 +1,150 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use crate::buffer::lru_k_replacer::check_frame_id;
+use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use crate::include::common::config::{AccessType, FrameId};
+
+// (last access, frame id), the least recently used frame of a list is its first entry
+type ListKey = (usize, FrameId);
+
+struct ArcNode {
+    frequent: bool, // in T2, otherwise in T1
+    last_access: usize,
+    is_evictable: bool,
+}
+
+impl ArcNode {
+    fn key(&self, frame_id: FrameId) -> ListKey {
+        (self.last_access, frame_id)
+    }
+}
+
+struct ArcReplacerState {
+    node_store: HashMap<FrameId, ArcNode>,
+    t1: BTreeSet<ListKey>, // evictable frames seen once
+    t2: BTreeSet<ListKey>, // evictable frames seen at least twice
+    t1_len: usize,         // every frame in T1, evictable or not
+    target_t1: usize,      // adaptive target size of T1 ("p" in the ARC paper)
+    current_timestamp: usize,
+}
+
+impl ArcReplacerState {
+    fn list(&mut self, frequent: bool) -> &mut BTreeSet<ListKey> {
+        if frequent {
+            &mut self.t2
+        } else {
+            &mut self.t1
+        }
+    }
+}
+
+/// Adaptive replacement: frames live in a recency list (T1) until their second
+/// access moves them to a frequency list (T2). Victims come from T1 while it is
+/// larger than the adaptive target, otherwise from T2, LRU within each list.
+///
+/// The replacer only sees frame ids, which the buffer pool reuses right after
+/// eviction, so there are no ghost lists. Instead the target grows when a T1
+/// frame proves to be reused and shrinks on hits in T2.
+pub struct ArcReplacerImpl {
+    latch: Mutex<ArcReplacerState>,
+    replacer_size: usize,
+}
+
+impl LRUKReplacer for ArcReplacerImpl {
+    // k is unused, ARC always promotes on the second access
+    fn new(num_frames: usize, _k: usize) -> Self {
+        ArcReplacerImpl {
+            latch: Mutex::new(ArcReplacerState {
+                node_store: HashMap::new(),
+                t1: BTreeSet::new(),
+                t2: BTreeSet::new(),
+                t1_len: 0,
+                target_t1: 0,
+                current_timestamp: 0,
+            }),
+            replacer_size: num_frames,
+        }
+    }
+
+    fn evict(&self) -> Option<FrameId> {
+        let mut state = self.latch.lock().unwrap();
+        let from_t1 = !state.t1.is_empty() && (state.t1_len > state.target_t1 || state.t2.is_empty());
+        let (_, victim) = state.list(!from_t1).pop_first()?;
+        state.node_store.remove(&victim);
+        if from_t1 {
+            state.t1_len -= 1;
+        }
+        Some(victim)
+    }
+
+    // Scans register a frame in T1 but never promote it or adapt the target
+    fn record_access(&self, frame_id: FrameId, access_type: AccessType) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        let timestamp = state.current_timestamp;
+        state.current_timestamp += 1;
+        let Some(node) = state.node_store.get_mut(&frame_id) else {
+            state.node_store.insert(frame_id, ArcNode {
+                frequent: false,
+                last_access: timestamp,
+                is_evictable: false,
+            });
+            state.t1_len += 1;
+            return;
+        };
+        if matches!(access_type, AccessType::Scan) {
+            return;
+        }
+        let (old_key, was_frequent) = (node.key(frame_id), node.frequent);
+        node.frequent = true;
+        node.last_access = timestamp;
+        let (is_evictable, new_key) = (node.is_evictable, node.key(frame_id));
+        if was_frequent {
+            state.target_t1 = state.target_t1.saturating_sub(1);
+        } else {
+            state.t1_len -= 1;
+            state.target_t1 = (state.target_t1 + 1).min(self.replacer_size);
+        }
+        if is_evictable {
+            state.list(was_frequent).remove(&old_key);
+            state.t2.insert(new_key);
+        }
+    }
+
+    fn set_evictable(&self, frame_id: FrameId, set_evictable: bool) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(node) = state.node_store.get_mut(&frame_id) {
+            if node.is_evictable != set_evictable {
+                node.is_evictable = set_evictable;
+                let (frequent, key) = (node.frequent, node.key(frame_id));
+                if set_evictable {
+                    state.list(frequent).insert(key);
+                } else {
+                    state.list(frequent).remove(&key);
+                }
+            }
+        }
+    }
+
+    fn remove(&self, frame_id: FrameId) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut state = self.latch.lock().unwrap();
+        if let Some(node) = state.node_store.remove(&frame_id) {
+            if node.is_evictable {
+                state.list(node.frequent).remove(&node.key(frame_id));
+            }
+            if !node.frequent {
+                state.t1_len -= 1;
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        let state = self.latch.lock().unwrap();
+        state.t1.len() + state.t2.len()
+    }
+}
@@ -1,23 +1,43 @@
 use crate::include::buffer::bufferpool_manager::{FrameHeaderImpl,BufferPoolManagerImpl};
-use crate::include::buffer::lru_k_replacer::LRUKReplacer;
 use crate::include::common::config::{PAGE_SIZE,PageId,FrameId, AccessType};
-use crate::include::storage::page::page::Page;
+use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use crate::include::storage::disk::disk_scheduler::{DiskRequest, DiskSchedulerTrait};
 use crate::storage::disk::disk_scheduler::DiskScheduler;
-use std::collections::{HashMap, LinkedList};
-use std::sync::atomic::{AtomicUsize, Ordering, AtomicI32};
+use std::collections::{HashMap, HashSet, LinkedList};
+use std::ops::{Deref, DerefMut};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use super::lru_k_replacer::LRUKReplacerImpl;
 
 use crate::storage::page::page_guard::{ReadPageGuard,WritePageGuard};
 
+/// The bytes of one page. Pages are only read and written through their
+/// little-endian encoders, so the bytes need no particular alignment.
+#[derive(Debug)]
+pub struct FrameData([u8; PAGE_SIZE]);
+
+impl Deref for FrameData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for FrameData {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
 #[derive(Debug)]
 pub struct FrameHeader {
     frame_id: FrameId,
     page_id: Mutex<Option<PageId>>, // There can be a frame with no page in it. 
-    rwlatch: RwLock<()>,
+    rwlatch: RwLock<FrameData>, // the page bytes, only reachable through the latch
     pin_count: AtomicUsize,
     is_dirty: Mutex<bool>,
-    data: Mutex<[u8; PAGE_SIZE]>,
 }
 
 impl FrameHeader {
@@ -25,36 +45,20 @@ impl FrameHeader {
         FrameHeader {
             frame_id,
             page_id: Mutex::new(None),
-            rwlatch: RwLock::new(()),
+            rwlatch: RwLock::new(FrameData([0; PAGE_SIZE])),
             pin_count: AtomicUsize::new(0),
             is_dirty: Mutex::new(false),
-            data: Mutex::new([0; PAGE_SIZE]),
-
         }
 
     }
 }
 impl FrameHeaderImpl for FrameHeader {
 
-    fn get_data(&self) -> &[u8] {
-        let _gaurd = self.rwlatch.read().unwrap();
-        let data = self.data.lock().unwrap();
-        unsafe { &*(&*data as *const [u8; PAGE_SIZE]) }
-    }
-
-    fn get_data_mut(&self) -> &mut  [u8] {
-        let _guard = self.rwlatch.write().unwrap();
-        let mut data = self.data.lock().unwrap();
-        *self.is_dirty.lock().unwrap()= true;
-        unsafe { &mut *(&mut *data as *mut [u8; PAGE_SIZE]) }
-    }
-
     fn reset(&self) {
-        let _guard = self.rwlatch.write().unwrap();
+        self.rwlatch.write().unwrap().0 = [0; PAGE_SIZE];
         *self.page_id.lock().unwrap() = None;
         self.pin_count.store(0,Ordering::SeqCst);
         *self.is_dirty.lock().unwrap()= false;
-        *self.data.lock().unwrap()= [0; PAGE_SIZE];
     }
 
     fn get_frame_id(&self) -> FrameId {
@@ -65,7 +69,7 @@ impl FrameHeaderImpl for FrameHeader {
         *self.page_id.lock().unwrap()
     }
 
-    fn set_page_id(&mut self, page_id: PageId)-> PageId {
+    fn set_page_id(&self, page_id: PageId)-> PageId {
         *self.page_id.lock().unwrap() = Some(page_id);
         page_id
     } 
@@ -90,32 +94,44 @@ impl FrameHeaderImpl for FrameHeader {
         *self.is_dirty.lock().unwrap() = is_dirty
     }
 
-    fn read_latch(&self) -> RwLockReadGuard<()> {
-        self.rwlatch.read().unwrap()        
+    fn read_latch(&self) -> RwLockReadGuard<'_, FrameData> {
+        self.rwlatch.read().unwrap()
     }
 
-    fn write_latch(&self) -> RwLockWriteGuard<()> {
+    fn write_latch(&self) -> RwLockWriteGuard<'_, FrameData> {
         self.rwlatch.write().unwrap()
     }
 
 }
 
+/// Handle to a buffer pool. Clones are cheap and all share the same frames,
+/// page table and free list, so page guards can keep the pool alive.
+#[derive(Clone)]
 pub struct BufferPoolManager {
+    inner: Arc<BufferPoolManagerInner>,
+}
+
+pub struct BufferPoolManagerInner {
     num_frames: usize,
-    next_page_id: AtomicI32,
     bpm_latch: Arc<Mutex<()>>,
     frames: Vec<Arc<FrameHeader>>,
     pub page_table: Mutex<HashMap<PageId, FrameId>>, 
+    // Pages a fetch is reading into or writing back from a frame, without the bpm latch
+    in_flight: Mutex<HashMap<PageId, FrameId>>,
+    // Pages to delete once their last pin is dropped
+    pending_deletes: Mutex<HashSet<PageId>>,
+    leaked_pages: AtomicUsize,
     free_frames: Mutex<LinkedList<FrameId>>,
-    replacer: Arc<LRUKReplacerImpl>,
+    replacer: Arc<dyn LRUKReplacer>,
     disk_scheduler: Arc<DiskScheduler>,
+    fsync_on_flush: bool,
 }
 
 impl BufferPoolManager {
     pub fn new(
         num_frames: usize,
         disk_manager: Arc<DiskScheduler>,
-        lru_k_replacer: Arc<LRUKReplacerImpl>,
+        lru_k_replacer: Arc<dyn LRUKReplacer>,
     ) -> Self {
         let mut frames = Vec::with_capacity(num_frames);
         let mut free_frames = LinkedList::new();
@@ -123,38 +139,221 @@ impl BufferPoolManager {
             frames.push(Arc::new(FrameHeader::new(i as FrameId)));
             free_frames.push_back(i as FrameId);
         }
-        BufferPoolManager {
+        let inner = BufferPoolManagerInner {
             num_frames,
-            next_page_id: AtomicI32::new(0),
             bpm_latch: Arc::new(Mutex::new(())),
             frames,
             page_table: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            pending_deletes: Mutex::new(HashSet::new()),
+            leaked_pages: AtomicUsize::new(0),
             free_frames: Mutex::new(free_frames),
             replacer: lru_k_replacer,
             disk_scheduler: disk_manager,
+            fsync_on_flush: false,
+        };
+        BufferPoolManager { inner: Arc::new(inner) }
+    }
+
+    /// When enabled, every flush also fsyncs the db file before the page is marked clean.
+    pub fn with_fsync_on_flush(mut self, fsync_on_flush: bool) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("with_fsync_on_flush must be called before the buffer pool is shared")
+            .fsync_on_flush = fsync_on_flush;
+        self
+    }
+
+    /// Returns a pinned frame holding `page_id`.
+    /// On a miss the frame comes from the free list, or else the replacer picks a victim
+    /// which is written back if dirty, and the page is read in from disk.
+    /// Returns None if every frame is pinned or the page can't be read.
+    fn fetch_frame(&self, page_id: PageId, access_type: AccessType) -> Option<(FrameId, Arc<FrameHeader>)> {
+        loop {
+            let latch = self.bpm_latch.lock().unwrap();
+            let mut page_table = self.page_table.lock().unwrap();
+            if let Some(&frame_id) = page_table.get(&page_id) {
+                let frame = self.frames[frame_id as usize].clone();
+                self.pin_frame(&frame, access_type);
+                return Some((frame_id, frame));
+            }
+            // Another fetch is still moving the page in or out of a frame. It holds the
+            // frame's latch until it's done, so wait for that and look again.
+            let in_flight = self.in_flight.lock().unwrap().get(&page_id).copied();
+            if let Some(frame_id) = in_flight {
+                drop(page_table);
+                drop(latch);
+                drop(self.frames[frame_id as usize].read_latch());
+                continue;
+            }
+
+            // if not create a new frame
+            let free_frame_id = self.free_frames.lock().unwrap().pop_front();
+            let (frame_id, write_back) = match free_frame_id {
+                Some(frame_id) => (frame_id, None),
+                None => {
+                    let frame_id = self.replacer.evict()?;
+                    let frame = &self.frames[frame_id as usize];
+                    let old_page_id = frame.get_page_id();
+                    if let Some(old_page_id) = old_page_id {
+                        page_table.remove(&old_page_id);
+                    }
+                    (frame_id, old_page_id.filter(|_| frame.is_dirty()))
+                }
+            };
+            // The frame is unpinned, so at most an unsafe flush holds its latch for a moment
+            let frame = self.frames[frame_id as usize].clone();
+            let mut data = frame.write_latch();
+            {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                in_flight.insert(page_id, frame_id);
+                if let Some(old_page_id) = write_back {
+                    in_flight.insert(old_page_id, frame_id);
+                }
+            }
+            drop(page_table);
+            drop(latch);
+
+            // The frame is in neither the page table, the free list nor the replacer,
+            // so nobody else gets to it while the I/O runs without the bpm latch
+            if let Some(old_page_id) = write_back {
+                if self.write_page_data(old_page_id, &data).is_err() {
+                    // Keep the dirty page resident rather than lose its contents
+                    let _latch = self.bpm_latch.lock().unwrap();
+                    let mut page_table = self.page_table.lock().unwrap();
+                    page_table.insert(old_page_id, frame_id);
+                    {
+                        let mut in_flight = self.in_flight.lock().unwrap();
+                        in_flight.remove(&page_id);
+                        in_flight.remove(&old_page_id);
+                    }
+                    self.replacer.record_access(frame_id, AccessType::Unknown);
+                    self.replacer.set_evictable(frame_id, true);
+                    drop(data);
+                    self.delete_if_pending(&mut page_table, old_page_id);
+                    return None;
+                }
+            }
+            let loaded = self.read_in(&mut data, page_id);
+
+            let _latch = self.bpm_latch.lock().unwrap();
+            let mut page_table = self.page_table.lock().unwrap();
+            {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                in_flight.remove(&page_id);
+                if let Some(old_page_id) = write_back {
+                    in_flight.remove(&old_page_id);
+                }
+            }
+            // the written back page may have been deleted meanwhile
+            if let Some(old_page_id) = write_back {
+                self.delete_if_pending(&mut page_table, old_page_id);
+            }
+            frame.set_is_dirty(false);
+            if loaded.is_err() {
+                // Nothing was loaded, the frame goes back to the free list
+                data.fill(0);
+                *frame.page_id.lock().unwrap() = None;
+                self.free_frames.lock().unwrap().push_back(frame_id);
+                self.delete_if_pending(&mut page_table, page_id);
+                return None;
+            }
+            frame.set_page_id(page_id);
+            page_table.insert(page_id, frame_id);
+            self.pin_frame(&frame, access_type);
+            drop(data);
+            return Some((frame_id, frame));
         }
     }
 
-    fn fetch_frame(&self, page_id: PageId) -> Option<(FrameId, Arc<FrameHeader>)> {
-        let mut page_table = self.page_table.lock().unwrap();
-        if let Some(&frame_id) = page_table.get(&page_id) {
-            // print the page table
-            //println!("page table {:?}", page_table);
-            //println!("frame {:?}", self.frames[frame_id as usize].clone());
-            return Some((frame_id, self.frames[frame_id as usize].clone()));
+    // Caller must hold bpm_latch, so the frame can't be evicted between the
+    // replacer update and the pin count increment
+    fn pin_frame(&self, frame: &FrameHeader, access_type: AccessType) {
+        frame.increment_pin_count();
+        self.replacer.record_access(frame.get_frame_id(), access_type);
+        self.replacer.set_evictable(frame.get_frame_id(), false);
+    }
+
+    /// Drops one pin, the frame becomes evictable once nobody holds it.
+    /// Page guards release their latch before calling this.
+    pub(crate) fn unpin_frame(&self, frame: &FrameHeader) {
+        // Taken so a concurrent fetch can't pin the frame between the
+        // pin count hitting zero and the frame becoming evictable
+        let _latch = self.bpm_latch.lock().unwrap();
+        frame.decrement_pin_count();
+        if frame.get_pin_count() == 0 {
+            self.replacer.set_evictable(frame.get_frame_id(), true);
+            if let Some(page_id) = frame.get_page_id() {
+                self.delete_if_pending(&mut self.page_table.lock().unwrap(), page_id);
+            }
         }
-        //println!("page table {:?}", page_table);
-        // if not create a new frame
-        let mut free_frames = self.free_frames.lock().unwrap(); 
-        if let Some(frame_id) = free_frames.pop_front() {
-            page_table.insert(page_id, frame_id);
-            return Some((frame_id, self.frames[frame_id as usize].clone()));
-        
-        // placeholder: use a replacer to evict a frame???
+    }
 
+    // Caller must hold bpm_latch. Deletes the page if a deferred delete waits for
+    // it, now that nobody pins it anymore.
+    fn delete_if_pending(&self, page_table: &mut HashMap<PageId, FrameId>, page_id: PageId) {
+        if self.pending_deletes.lock().unwrap().remove(&page_id) && !self.delete_unpinned_page(page_table, page_id) {
+            self.leaked_pages.fetch_add(1, Ordering::SeqCst);
         }
+    }
 
-        None
+    // Caller must hold bpm_latch and have checked that nobody pins the page.
+    // Returns false if the disk manager fails to free it.
+    fn delete_unpinned_page(&self, page_table: &mut HashMap<PageId, FrameId>, page_id: PageId) -> bool {
+        if self.disk_scheduler.deallocate_page(page_id).is_err() {
+            return false
+        }
+        if let Some(frame_id) = page_table.remove(&page_id) {
+            self.replacer.remove(frame_id);
+            let mut free_frames = self.free_frames.lock().unwrap();
+            free_frames.push_back(frame_id);
+            self.frames[frame_id as usize].reset();
+        }
+        true
+    }
+
+    /// Writes page bytes through the disk scheduler and blocks until the write completed
+    /// (and was synced, if enabled).
+    pub(crate) fn write_page_data(&self, page_id: PageId, data: &[u8]) -> io::Result<()> {
+        let (tx, rx) = channel();
+        self.disk_scheduler.schedule(DiskRequest {
+            page_id,
+            is_write: true,
+            data: Arc::new(Mutex::new(data.to_vec())),
+            is_done: tx,
+        });
+        rx.recv().unwrap_or_else(|_| Err(io::Error::other("Disk scheduler dropped the request")))?;
+        if self.fsync_on_flush {
+            self.disk_scheduler.sync()?;
+        }
+        Ok(())
+    }
+
+    // Writes a frame under its read latch, so nobody can dirty it again between the
+    // write and clearing the dirty flag. Blocks while a write guard holds the page.
+    fn write_frame(&self, frame: &FrameHeader, page_id: PageId) -> io::Result<()> {
+        let data = frame.read_latch();
+        self.write_page_data(page_id, &data)?;
+        frame.set_is_dirty(false);
+        Ok(())
+    }
+
+    // Synchronously read a page from disk into a frame's bytes.
+    // A page that was never written is past the end of the file and reads as zeroes.
+    fn read_in(&self, frame_data: &mut [u8], page_id: PageId) -> io::Result<()> {
+        let (tx, rx) = channel();
+        let data = Arc::new(Mutex::new(vec![0u8; PAGE_SIZE]));
+        self.disk_scheduler.schedule(DiskRequest {
+            page_id,
+            is_write: false,
+            data: data.clone(),
+            is_done: tx,
+        });
+        match rx.recv().unwrap_or_else(|_| Err(io::Error::other("Disk scheduler dropped the request"))) {
+            Ok(()) => frame_data.copy_from_slice(&data.lock().unwrap()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => frame_data.fill(0),
+            Err(e) => return Err(e),
+        }
+        Ok(())
     }
 }
 
@@ -163,30 +362,57 @@ impl BufferPoolManagerImpl for BufferPoolManager {
         self.num_frames
     }
     /// Allocates a newpage (default) on disk and in-memory. 
-    fn new_page(&self) -> PageId {
-        // create a new pageid
-        let page_id = self.next_page_id.fetch_add(1, Ordering::SeqCst);
-        // Check if we can create a new frame
-        page_id
+    /// Page ids come from the disk manager, so freed pages are reused and
+    /// the sequence continues where it left off after a restart.
+    /// Fails if the disk manager can't grow the file or its free list is corrupted.
+    fn new_page(&self) -> io::Result<PageId> {
+        self.disk_scheduler.allocate_page()
     }
 
+    /// Removes a page from the buffer pool and returns its space on disk.
+    /// Returns false if the page is still pinned or was not allocated.
     fn delete_page(&self, page_id: PageId) -> bool {
+        let _latch = self.bpm_latch.lock().unwrap();
         let mut page_table = self.page_table.lock().unwrap(); 
-        if let Some(frame_id) = page_table.remove(&page_id) {
-            let frame = &self.frames[frame_id as usize];
-            let mut free_frames = self.free_frames.lock().unwrap();
-            free_frames.push_back(frame_id);
-            frame.reset();
-            true
-        } else {
-            false
+        // a page on its way in or out of a frame counts as pinned
+        if self.in_flight.lock().unwrap().contains_key(&page_id) {
+            return false
         }
+        if let Some(&frame_id) = page_table.get(&page_id) {
+            if self.frames[frame_id as usize].get_pin_count() > 0 {
+                return false
+            }
+        }
+        self.delete_unpinned_page(&mut page_table, page_id)
+    }
+
+    /// Like delete_page, but a pinned page is deleted by whoever drops its last pin.
+    fn delete_page_deferred(&self, page_id: PageId) {
+        let _latch = self.bpm_latch.lock().unwrap();
+        let mut page_table = self.page_table.lock().unwrap();
+        let pinned = match page_table.get(&page_id) {
+            Some(&frame_id) => self.frames[frame_id as usize].get_pin_count() > 0,
+            None => false,
+        };
+        // a page on its way into a frame gets pinned as soon as it's there
+        if pinned || self.in_flight.lock().unwrap().contains_key(&page_id) {
+            self.pending_deletes.lock().unwrap().insert(page_id);
+        } else if !self.delete_unpinned_page(&mut page_table, page_id) {
+            self.leaked_pages.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn pending_delete_count(&self) -> usize {
+        self.pending_deletes.lock().unwrap().len()
+    }
+
+    fn leaked_page_count(&self) -> usize {
+        self.leaked_pages.load(Ordering::SeqCst)
     }
 
     fn checked_read_page(&self, page_id: PageId, access_type: AccessType) -> Option<ReadPageGuard> {
-        if let Some((frame_id, frame)) = self.fetch_frame(page_id) {
-            //&self.replacer.record_access(frame_id, access_type);
-            Some(ReadPageGuard::new(page_id,frame_id, frame, self.replacer.clone(), self.bpm_latch.clone(), self.disk_scheduler.clone(), Arc::new(self.clone()),))
+        if let Some((frame_id, frame)) = self.fetch_frame(page_id, access_type) {
+            Some(ReadPageGuard::new(page_id, frame_id, frame, self.clone()))
         } else {
             None
         }
@@ -200,8 +426,8 @@ impl BufferPoolManagerImpl for BufferPoolManager {
     }
 
     fn checked_write_page(&self, page_id: PageId, access_type: AccessType) -> Option<WritePageGuard> {
-        if let Some((frame_id, frame)) = self.fetch_frame(page_id) {
-            Some(WritePageGuard::new(page_id,frame_id ,frame, self.replacer.clone(), self.bpm_latch.clone(), self.disk_scheduler.clone(), Arc::new(self.clone()),))
+        if let Some((frame_id, frame)) = self.fetch_frame(page_id, access_type) {
+            Some(WritePageGuard::new(page_id, frame_id, frame, self.clone()))
         } else {
             None
         }
@@ -212,35 +438,61 @@ impl BufferPoolManagerImpl for BufferPoolManager {
         })
     }
 
-    fn flush_page_unsafe(&self, page_id: PageId) -> bool {
-        let page_table = self.page_table.lock().unwrap();
-        if let Some(&frame_id) = page_table.get(&page_id) {
-            let frame = &self.frames[frame_id as usize];
-            // Placeholder: Flush frame data through diskscedhuler
-            frame.set_is_dirty(false);
-            true
-        } else {
-            false
+    /// Writes the page to disk whether or not it is dirty, without pinning it.
+    /// The caller must make sure the page can't be evicted meanwhile.
+    /// Returns Ok(false) if the page is not in the buffer pool.
+    fn flush_page_unsafe(&self, page_id: PageId) -> io::Result<bool> {
+        let frame_id = self.page_table.lock().unwrap().get(&page_id).copied();
+        match frame_id {
+            Some(frame_id) => {
+                self.write_frame(&self.frames[frame_id as usize], page_id)?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
-    fn flush_page(&self, page_id: PageId) -> bool {
-        let _guard = self.bpm_latch.lock().unwrap();
-        self.flush_page_unsafe(page_id)
+    /// Like flush_page_unsafe, but pins the page for the duration of the write.
+    /// Waits for any write guard on the page to be dropped.
+    fn flush_page(&self, page_id: PageId) -> io::Result<bool> {
+        let frame = {
+            let _latch = self.bpm_latch.lock().unwrap();
+            let frame_id = self.page_table.lock().unwrap().get(&page_id).copied();
+            match frame_id {
+                Some(frame_id) => {
+                    let frame = self.frames[frame_id as usize].clone();
+                    frame.increment_pin_count();
+                    self.replacer.set_evictable(frame_id, false);
+                    frame
+                }
+                None => return Ok(false),
+            }
+        };
+        // The page latch is only taken once bpm_latch is released
+        let result = self.write_frame(&frame, page_id);
+        self.unpin_frame(&frame);
+        result.map(|()| true)
     }
 
-    fn flush_all_pages_unsafe(&self) {
-        let page_table = self.page_table.lock().unwrap();
-        for (&page_id, &frame_id) in page_table.iter() {
+    /// Writes every dirty page to disk, stopping at the first failed write.
+    fn flush_all_pages_unsafe(&self) -> io::Result<()> {
+        let resident: Vec<_> = self.page_table.lock().unwrap().iter().map(|(&page_id, &frame_id)| (page_id, frame_id)).collect();
+        for (page_id, frame_id) in resident {
             let frame = &self.frames[frame_id as usize];
-            // Placeholder: Flush frame data via disk_scheduler
-            frame.set_is_dirty(false);
+            if frame.is_dirty() {
+                self.write_frame(frame, page_id)?;
+            }
         }
+        Ok(())
     }
 
-    fn flush_all_pages(&self) {
-        let _guard = self.bpm_latch.lock().unwrap();
-        self.flush_all_pages_unsafe();
+    /// Writes every resident page to disk, pinning one page at a time.
+    fn flush_all_pages(&self) -> io::Result<()> {
+        let resident: Vec<_> = self.page_table.lock().unwrap().keys().copied().collect();
+        for page_id in resident {
+            self.flush_page(page_id)?;
+        }
+        Ok(())
     }
 
     fn get_pin_count(&self, page_id: PageId) -> Option<usize> {
@@ -252,18 +504,10 @@ impl BufferPoolManagerImpl for BufferPoolManager {
 
 }
 
-// Required for Arc<BufferPoolManager>
-impl Clone for BufferPoolManager {
-    fn clone(&self) -> Self {
-        BufferPoolManager {
-            num_frames: self.num_frames,
-            next_page_id: AtomicI32::new(self.next_page_id.load(Ordering::SeqCst)),
-            bpm_latch: self.bpm_latch.clone(),
-            frames: self.frames.clone(),
-            page_table: Mutex::new(self.page_table.lock().unwrap().clone()),
-            free_frames: Mutex::new(self.free_frames.lock().unwrap().clone()),
-            replacer: self.replacer.clone(),
-            disk_scheduler: self.disk_scheduler.clone(),
-        }
+impl Deref for BufferPoolManager {
+    type Target = BufferPoolManagerInner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
     }
-}
\ No newline at end of file
+}
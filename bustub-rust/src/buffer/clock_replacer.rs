@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+
+use crate::buffer::lru_k_replacer::check_frame_id;
+use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use crate::include::common::config::{AccessType, FrameId};
+
+#[derive(Clone, Copy)]
+struct ClockEntry {
+    referenced: bool,
+    is_evictable: bool,
+}
+
+struct ClockReplacerState {
+    entries: Vec<Option<ClockEntry>>, // indexed by frame id, None if the frame is not tracked
+    hand: usize,
+    curr_size: usize,
+}
+
+/// Second chance replacement: the hand sweeps over the frames in id order,
+/// clearing reference bits and evicting the first evictable frame without one.
+pub struct ClockReplacerImpl {
+    latch: Mutex<ClockReplacerState>,
+    replacer_size: usize,
+}
+
+impl LRUKReplacer for ClockReplacerImpl {
+    // k is unused, a single reference bit is kept per frame
+    fn new(num_frames: usize, _k: usize) -> Self {
+        ClockReplacerImpl {
+            latch: Mutex::new(ClockReplacerState {
+                entries: vec![None; num_frames],
+                hand: 0,
+                curr_size: 0,
+            }),
+            replacer_size: num_frames,
+        }
+    }
+
+    fn evict(&self) -> Option<FrameId> {
+        let mut state = self.latch.lock().unwrap();
+        if state.curr_size == 0 {
+            return None;
+        }
+        // Two full turns are enough: the first one clears every reference bit
+        for _ in 0..2 * self.replacer_size {
+            let frame_id = state.hand;
+            state.hand = (state.hand + 1) % self.replacer_size;
+            if let Some(entry) = state.entries[frame_id].as_mut() {
+                if !entry.is_evictable {
+                    continue;
+                }
+                if entry.referenced {
+                    entry.referenced = false;
+                } else {
+                    state.entries[frame_id] = None;
+                    state.curr_size -= 1;
+                    return Some(frame_id as FrameId);
+                }
+            }
+        }
+        None
+    }
+
+    // A scan registers the frame but never gives it a second chance
+    fn record_access(&self, frame_id: FrameId, access_type: AccessType) {
+        check_frame_id(frame_id, self.replacer_size);
+        let referenced = !matches!(access_type, AccessType::Scan);
+        let mut state = self.latch.lock().unwrap();
+        match state.entries[frame_id as usize].as_mut() {
+            Some(entry) => entry.referenced |= referenced,
+            None => {
+                state.entries[frame_id as usize] = Some(ClockEntry { referenced, is_evictable: false });
+            }
+        }
+    }
+
+    fn set_evictable(&self, frame_id: FrameId, set_evictable: bool) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(entry) = state.entries[frame_id as usize].as_mut() {
+            if entry.is_evictable != set_evictable {
+                entry.is_evictable = set_evictable;
+                if set_evictable {
+                    state.curr_size += 1;
+                } else {
+                    state.curr_size -= 1;
+                }
+            }
+        }
+    }
+
+    fn remove(&self, frame_id: FrameId) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut state = self.latch.lock().unwrap();
+        if let Some(entry) = state.entries[frame_id as usize].take() {
+            if entry.is_evictable {
+                state.curr_size -= 1;
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.latch.lock().unwrap().curr_size
+    }
+}
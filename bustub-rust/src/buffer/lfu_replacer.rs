@@ -0,0 +1,110 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use crate::buffer::lru_k_replacer::check_frame_id;
+use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use crate::include::common::config::{AccessType, FrameId};
+
+// (access count, last access, frame id), the victim is the first entry
+type LfuKey = (usize, usize, FrameId);
+
+struct LfuNode {
+    access_count: usize,
+    last_access: usize,
+    is_evictable: bool,
+}
+
+impl LfuNode {
+    fn key(&self, frame_id: FrameId) -> LfuKey {
+        (self.access_count, self.last_access, frame_id)
+    }
+}
+
+struct LfuReplacerState {
+    node_store: HashMap<FrameId, LfuNode>,
+    evictable: BTreeSet<LfuKey>,
+    current_timestamp: usize,
+}
+
+/// Least frequently used: evicts the frame with the fewest recorded accesses,
+/// ties are broken by the least recent access.
+pub struct LfuReplacerImpl {
+    latch: Mutex<LfuReplacerState>,
+    replacer_size: usize,
+}
+
+impl LRUKReplacer for LfuReplacerImpl {
+    // k is unused, the whole access count is kept per frame
+    fn new(num_frames: usize, _k: usize) -> Self {
+        LfuReplacerImpl {
+            latch: Mutex::new(LfuReplacerState {
+                node_store: HashMap::new(),
+                evictable: BTreeSet::new(),
+                current_timestamp: 0,
+            }),
+            replacer_size: num_frames,
+        }
+    }
+
+    fn evict(&self) -> Option<FrameId> {
+        let mut state = self.latch.lock().unwrap();
+        let (_, _, victim) = state.evictable.pop_first()?;
+        state.node_store.remove(&victim);
+        Some(victim)
+    }
+
+    // Scans only register a frame, they don't count towards its frequency
+    fn record_access(&self, frame_id: FrameId, access_type: AccessType) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        let timestamp = state.current_timestamp;
+        let node = state.node_store.entry(frame_id).or_insert(LfuNode {
+            access_count: 0,
+            last_access: timestamp,
+            is_evictable: false,
+        });
+        if matches!(access_type, AccessType::Scan) && node.access_count > 0 {
+            return;
+        }
+        if node.is_evictable {
+            state.evictable.remove(&node.key(frame_id));
+        }
+        node.access_count += 1;
+        node.last_access = timestamp;
+        if node.is_evictable {
+            state.evictable.insert(node.key(frame_id));
+        }
+        state.current_timestamp += 1;
+    }
+
+    fn set_evictable(&self, frame_id: FrameId, set_evictable: bool) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(node) = state.node_store.get_mut(&frame_id) {
+            if node.is_evictable != set_evictable {
+                node.is_evictable = set_evictable;
+                if set_evictable {
+                    state.evictable.insert(node.key(frame_id));
+                } else {
+                    state.evictable.remove(&node.key(frame_id));
+                }
+            }
+        }
+    }
+
+    fn remove(&self, frame_id: FrameId) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut state = self.latch.lock().unwrap();
+        if let Some(node) = state.node_store.remove(&frame_id) {
+            if node.is_evictable {
+                state.evictable.remove(&node.key(frame_id));
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.latch.lock().unwrap().evictable.len()
+    }
+}
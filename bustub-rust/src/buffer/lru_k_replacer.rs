@@ -1,121 +1,150 @@
-use rand::Rng;
-use std::collections::{HashMap, LinkedList};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::sync::Mutex;
-use std::usize;
 
-use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use std::sync::Arc;
+
+use crate::buffer::arc_replacer::ArcReplacerImpl;
+use crate::buffer::clock_replacer::ClockReplacerImpl;
+use crate::buffer::lfu_replacer::LfuReplacerImpl;
+use crate::buffer::two_q_replacer::TwoQReplacerImpl;
+use crate::include::buffer::lru_k_replacer::{LRUKReplacer, ReplacerPolicy};
 use crate::include::common::config::{AccessType, FrameId};
-use std::cell::RefCell;
+
+// Orders evictable frames so the victim is always the first entry:
+// (has k accesses, timestamp of the kth most recent access, frame id).
+// Frames with fewer than k accesses (+inf k-distance) sort first, by earliest access.
+type EvictionKey = (bool, usize, FrameId);
 
 pub struct LRUKNode {
-    history: LinkedList<usize>,
+    history: VecDeque<usize>,
     k_: usize,
     frame_id: FrameId,
     is_evictable: bool,
 }
 
+impl LRUKNode {
+    fn eviction_key(&self) -> EvictionKey {
+        (self.history.len() >= self.k_, *self.history.front().unwrap(), self.frame_id)
+    }
+}
+
+// Everything the replacer mutates, kept under one lock
+struct LRUKReplacerState {
+    node_store_: HashMap<FrameId, LRUKNode>,
+    evictable_: BTreeSet<EvictionKey>, // one entry per evictable node
+    current_timestamp_: usize,
+}
+
 pub struct LRUKReplacerImpl {
-    node_store_: RefCell<HashMap<FrameId, LRUKNode>>,
-    curr_size_: RefCell<usize>,
+    latch_: Mutex<LRUKReplacerState>,
     replacer_size_: usize,
     k_: usize,
-    latch_: Mutex<()>,
-    current_timestamp_: RefCell<usize>,
+}
+
+// Frame ids handed to a replacer must belong to the buffer pool it was sized for
+pub(crate) fn check_frame_id(frame_id: FrameId, replacer_size: usize) {
+    assert!(
+        frame_id >= 0 && (frame_id as usize) < replacer_size,
+        "Invalid frame id {} for a replacer of size {}", frame_id, replacer_size
+    );
+}
+
+impl ReplacerPolicy {
+    /// Builds a replacer for a buffer pool with `num_frames` frames.
+    pub fn build(self, num_frames: usize) -> Arc<dyn LRUKReplacer> {
+        match self {
+            ReplacerPolicy::LruK(k) => Arc::new(LRUKReplacerImpl::new(num_frames, k)),
+            ReplacerPolicy::Clock => Arc::new(ClockReplacerImpl::new(num_frames, 0)),
+            ReplacerPolicy::TwoQ => Arc::new(TwoQReplacerImpl::new(num_frames, 2)),
+            ReplacerPolicy::Arc => Arc::new(ArcReplacerImpl::new(num_frames, 0)),
+            ReplacerPolicy::Lfu => Arc::new(LfuReplacerImpl::new(num_frames, 0)),
+        }
+    }
 }
 
 impl LRUKReplacer for LRUKReplacerImpl {
     fn new(num_frames: usize, k: usize) -> Self {
+        // every access has to enter the history, eviction keys read its front
+        assert!(k >= 1, "LRU-K needs k of at least 1, got {}", k);
         LRUKReplacerImpl {
-            node_store_: RefCell::new(HashMap::new()),
-            curr_size_: RefCell::new(0),
+            latch_: Mutex::new(LRUKReplacerState {
+                node_store_: HashMap::new(),
+                evictable_: BTreeSet::new(),
+                current_timestamp_: 0,
+            }),
             replacer_size_: num_frames,
             k_: k,
-            latch_: Mutex::new(()),
-            current_timestamp_: RefCell::new(0),
         }
     }
 
+    /// Evicts the frame with the largest backward k-distance.
+    /// Frames with fewer than k recorded accesses have +inf distance and go first,
+    /// ties between them are broken by their earliest access (classic LRU).
     fn evict(&self) -> Option<FrameId> {
-        let _gaurd = self.latch_.lock().unwrap();
-        // let random_frame_id = rand::thread_rng().gen_range(0..self.replacer_size_) as FrameId;
-        // Some(random_frame_id)
-
-        let mut min_frame_id: Option<FrameId> = None;
-        let mut min_kth_time: usize = usize::MAX;
-        for (&frame_id, node) in self.node_store_.borrow().iter() {
-            if node.is_evictable {
-                if let Some(&kth_time) = node.history.front() {
-                    if min_frame_id.is_none() || kth_time < min_kth_time {
-                        min_frame_id = Some(frame_id);
-                        min_kth_time = kth_time;
-                    }
-                }
-            }
-
-            // frame_id is None {
-            //     frame_id = frame_id
-            //     let min_global_time = node.history.front();
-            // } else {
-            //     min_curr_time = MIN(min_global_time, node.history.front());
-            //     if min_curr_time < min_global_time {
-            //         min_frame_id = frame_id;
-            //         min_global_time = min_curr_time;
-            //     }
-
-            // }
-        }
-        min_frame_id
+        let mut state = self.latch_.lock().unwrap();
+        let (_, _, victim) = state.evictable_.pop_first()?;
+        state.node_store_.remove(&victim);
+        Some(victim)
     }
 
+    /// Scan accesses only register a frame the first time it is seen,
+    /// they never add to an existing history so a sequential scan can't
+    /// make its pages look hot.
     fn record_access(&self, frame_id: FrameId, access_type: AccessType) {
-        // TODO:
-        let _gaurd = self.latch_.lock().unwrap();
-        let mut node_store = self.node_store_.borrow_mut();
-        let mut timestamp = self.current_timestamp_.borrow_mut(); 
-        let node = node_store.entry(frame_id).or_insert(LRUKNode {
-            history: LinkedList::new(),
+        check_frame_id(frame_id, self.replacer_size_);
+        let mut guard = self.latch_.lock().unwrap();
+        let state = &mut *guard;
+        let timestamp = state.current_timestamp_;
+        let node = state.node_store_.entry(frame_id).or_insert(LRUKNode {
+            history: VecDeque::with_capacity(self.k_),
             k_: self.k_,
-            frame_id: frame_id,
+            frame_id,
             is_evictable: false,
         });
-        node.history.push_back(*timestamp);
+        if matches!(access_type, AccessType::Scan) && !node.history.is_empty() {
+            return;
+        }
+        // The key changes with the history, so re-insert evictable nodes
+        if node.is_evictable {
+            state.evictable_.remove(&node.eviction_key());
+        }
+        node.history.push_back(timestamp);
         if node.history.len() > node.k_ {
             node.history.pop_front();
         }
-        *timestamp += 1;
+        if node.is_evictable {
+            state.evictable_.insert(node.eviction_key());
+        }
+        state.current_timestamp_ += 1;
     }
 
     fn set_evictable(&self, frame_id: FrameId, set_evictable: bool) {
-        // get the LRUK node from the map
-        let _gaurd = self.latch_.lock().unwrap();
-        let mut node_store = self.node_store_.borrow_mut();
-        let mut curr_size = self.curr_size_.borrow_mut();
-        if let Some(node) = node_store.get_mut(&frame_id) {
+        check_frame_id(frame_id, self.replacer_size_);
+        let mut guard = self.latch_.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(node) = state.node_store_.get_mut(&frame_id) {
             if node.is_evictable != set_evictable {
                 node.is_evictable = set_evictable;
                 if set_evictable {
-                    *curr_size += 1;
+                    state.evictable_.insert(node.eviction_key());
                 } else {
-                    *curr_size -= 1;
+                    state.evictable_.remove(&node.eviction_key());
                 }
             }
         }
     }
 
     fn remove(&self, frame_id: FrameId) {
-        // Here I think, we might need to deal with memory cleanup or deleting the map for the frame ID
-        let _gaurd = self.latch_.lock().unwrap();
-        let mut node_store = self.node_store_.borrow_mut();
-        let mut curr_size = self.curr_size_.borrow_mut();
-        if let Some(node) = node_store.remove(&frame_id) {
+        check_frame_id(frame_id, self.replacer_size_);
+        let mut state = self.latch_.lock().unwrap();
+        if let Some(node) = state.node_store_.remove(&frame_id) {
             if node.is_evictable {
-                *curr_size -= 1
+                state.evictable_.remove(&node.eviction_key());
             }
         }
     }
 
     fn size(&self) -> usize {
-        let _gaurd = self.latch_.lock().unwrap();
-        *self.curr_size_.borrow()
+        self.latch_.lock().unwrap().evictable_.len()
     }
 }
@@ -1,2 +1,6 @@
 pub mod lru_k_replacer;
-pub mod bufferpool_manager;
\ No newline at end of file
+pub mod clock_replacer;
+pub mod two_q_replacer;
+pub mod arc_replacer;
+pub mod lfu_replacer;
+pub mod bufferpool_manager;
@@ -0,0 +1,154 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use crate::buffer::lru_k_replacer::check_frame_id;
+use crate::include::buffer::lru_k_replacer::LRUKReplacer;
+use crate::include::common::config::{AccessType, FrameId};
+
+// (queue position, frame id), the front of each queue is its first entry
+type QueueKey = (usize, FrameId);
+
+struct TwoQNode {
+    hot: bool,           // in Am, otherwise still in A1
+    access_count: usize,
+    queued_at: usize,    // A1: time the frame entered, Am: last access
+    is_evictable: bool,
+}
+
+impl TwoQNode {
+    fn key(&self, frame_id: FrameId) -> QueueKey {
+        (self.queued_at, frame_id)
+    }
+}
+
+struct TwoQReplacerState {
+    node_store: HashMap<FrameId, TwoQNode>,
+    a1: BTreeSet<QueueKey>, // evictable frames of the FIFO queue
+    am: BTreeSet<QueueKey>, // evictable frames of the LRU queue
+    a1_len: usize,          // every frame in A1, evictable or not
+    current_timestamp: usize,
+}
+
+impl TwoQReplacerState {
+    fn queue(&mut self, hot: bool) -> &mut BTreeSet<QueueKey> {
+        if hot {
+            &mut self.am
+        } else {
+            &mut self.a1
+        }
+    }
+}
+
+/// Simplified 2Q: newly seen frames enter the A1 FIFO queue and move to the
+/// Am LRU queue once they have been accessed k times. A1 is evicted first
+/// while it holds more than a quarter of the pool, so one-off pages
+/// can't push out the hot set.
+pub struct TwoQReplacerImpl {
+    latch: Mutex<TwoQReplacerState>,
+    replacer_size: usize,
+    k: usize,
+    a1_target: usize,
+}
+
+impl LRUKReplacer for TwoQReplacerImpl {
+    // k is the number of accesses that promotes a frame to Am
+    fn new(num_frames: usize, k: usize) -> Self {
+        TwoQReplacerImpl {
+            latch: Mutex::new(TwoQReplacerState {
+                node_store: HashMap::new(),
+                a1: BTreeSet::new(),
+                am: BTreeSet::new(),
+                a1_len: 0,
+                current_timestamp: 0,
+            }),
+            replacer_size: num_frames,
+            k: k.max(1),
+            a1_target: (num_frames / 4).max(1),
+        }
+    }
+
+    fn evict(&self) -> Option<FrameId> {
+        let mut state = self.latch.lock().unwrap();
+        let from_a1 = !state.a1.is_empty() && (state.a1_len > self.a1_target || state.am.is_empty());
+        let (_, victim) = state.queue(!from_a1).pop_first()?;
+        state.node_store.remove(&victim);
+        if from_a1 {
+            state.a1_len -= 1;
+        }
+        Some(victim)
+    }
+
+    // Scans never promote a frame or refresh its position
+    fn record_access(&self, frame_id: FrameId, access_type: AccessType) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        let timestamp = state.current_timestamp;
+        state.current_timestamp += 1;
+        let Some(node) = state.node_store.get_mut(&frame_id) else {
+            state.node_store.insert(frame_id, TwoQNode {
+                hot: self.k == 1,
+                access_count: 1,
+                queued_at: timestamp,
+                is_evictable: false,
+            });
+            if self.k > 1 {
+                state.a1_len += 1;
+            }
+            return;
+        };
+        if matches!(access_type, AccessType::Scan) {
+            return;
+        }
+        let old_key = node.key(frame_id);
+        let was_hot = node.hot;
+        node.access_count += 1;
+        if node.hot || node.access_count >= self.k {
+            node.hot = true;
+            node.queued_at = timestamp;
+        }
+        let (is_evictable, hot, new_key) = (node.is_evictable, node.hot, node.key(frame_id));
+        if !was_hot && hot {
+            state.a1_len -= 1;
+        }
+        if is_evictable {
+            state.queue(was_hot).remove(&old_key);
+            state.queue(hot).insert(new_key);
+        }
+    }
+
+    fn set_evictable(&self, frame_id: FrameId, set_evictable: bool) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut guard = self.latch.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(node) = state.node_store.get_mut(&frame_id) {
+            if node.is_evictable != set_evictable {
+                node.is_evictable = set_evictable;
+                let (hot, key) = (node.hot, node.key(frame_id));
+                if set_evictable {
+                    state.queue(hot).insert(key);
+                } else {
+                    state.queue(hot).remove(&key);
+                }
+            }
+        }
+    }
+
+    fn remove(&self, frame_id: FrameId) {
+        check_frame_id(frame_id, self.replacer_size);
+        let mut state = self.latch.lock().unwrap();
+        if let Some(node) = state.node_store.remove(&frame_id) {
+            if node.is_evictable {
+                state.queue(node.hot).remove(&node.key(frame_id));
+            }
+            if !node.hot {
+                state.a1_len -= 1;
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        let state = self.latch.lock().unwrap();
+        state.a1.len() + state.am.len()
+    }
+}
@@ -1,14 +1,11 @@
 use crate::include::common::config::{AccessType, FrameId, PageId};
-use std::any::Any;
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::io;
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
 
+use crate::buffer::bufferpool_manager::FrameData;
 use crate::storage::page::page_guard::{ReadPageGuard, WritePageGuard};
 
 pub trait FrameHeaderImpl {
-    // Return a read-only view of the frame's raw memory as bytes.  
-    fn get_data(&self) -> &[u8];
-    // Return a mutable view of the frame's raw memory, so the content can be updated.
-    fn get_data_mut(&self) -> &mut [u8];
     // Clear or reset the frame's content
     fn reset(&self);
     fn get_frame_id(&self) -> FrameId;
@@ -16,7 +13,7 @@ pub trait FrameHeaderImpl {
     // return the page_id that the frame is currently holding
     fn get_page_id(&self) -> Option<PageId>;
     // Set the new page_id that the frame has to hold
-    fn set_page_id(&mut self, page_id: PageId) -> PageId;
+    fn set_page_id(&self, page_id: PageId) -> PageId;
     // get the current pin count of the frame
     fn get_pin_count(&self) -> usize;
     // increment pin count
@@ -27,23 +24,30 @@ pub trait FrameHeaderImpl {
     fn is_dirty(&self) -> bool;
     // True if the frame has been recently modified and needs to be written back to disk. 
     fn set_is_dirty(&self, is_dirty: bool);
-    // Acquire a read latch to safely read the frame concurrently. 
-    fn read_latch(&self) -> RwLockReadGuard<()>;
-    // Acquire a write latch (exclusive access) to modify the frame without reference. 
-    fn write_latch(&self) -> RwLockWriteGuard<()>;
+    // Acquire a read latch to safely read the frame's bytes concurrently.
+    fn read_latch(&self) -> RwLockReadGuard<'_, FrameData>;
+    // Acquire a write latch (exclusive access) to modify the frame's bytes.
+    fn write_latch(&self) -> RwLockWriteGuard<'_, FrameData>;
 }
 
 pub trait BufferPoolManagerImpl {
     fn size(&self)-> usize; 
-    fn new_page(&self) -> PageId;
+    fn new_page(&self) -> io::Result<PageId>;
     fn delete_page(&self, page_id: PageId) -> bool;
+    // Deletes the page now, or once the last pin is dropped if it is pinned.
+    // A page the disk manager fails to free is counted by leaked_page_count.
+    fn delete_page_deferred(&self, page_id: PageId);
+    // Pages waiting for their last pin to be dropped before they are deleted
+    fn pending_delete_count(&self) -> usize;
+    // Pages a deferred delete failed to return to the disk manager
+    fn leaked_page_count(&self) -> usize;
     fn checked_write_page(&self, page_id: PageId, access_type: AccessType) -> Option<WritePageGuard>;
     fn checked_read_page(&self, page_id: PageId, access_type: AccessType) -> Option<ReadPageGuard>;
     fn write_page(&self, page_id: PageId, access_type: AccessType) -> WritePageGuard;
     fn read_page(&self, page_id: PageId, access_type: AccessType) -> ReadPageGuard;
-    fn flush_page_unsafe(&self, page_id: PageId) -> bool;
-    fn flush_page(&self, page_id: PageId) -> bool;
-    fn flush_all_pages_unsafe(&self);
-    fn flush_all_pages(&self);
+    fn flush_page_unsafe(&self, page_id: PageId) -> io::Result<bool>;
+    fn flush_page(&self, page_id: PageId) -> io::Result<bool>;
+    fn flush_all_pages_unsafe(&self) -> io::Result<()>;
+    fn flush_all_pages(&self) -> io::Result<()>;
     fn get_pin_count(&self, page_id: PageId) -> Option<usize>;
 }
\ No newline at end of file
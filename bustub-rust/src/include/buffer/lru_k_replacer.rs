@@ -1,10 +1,24 @@
 use crate::include::common::config::{AccessType, FrameId};
 
-pub trait LRUKReplacer {
-    fn new(num_frames: usize, k: usize) -> Self;
+/// Contract shared by every replacement policy the buffer pool can use.
+/// Frames start out non-evictable when first accessed, `size` counts the
+/// evictable ones and `evict` forgets the victim's history.
+/// Frame ids outside `0..num_frames` are a caller bug and panic.
+pub trait LRUKReplacer: Send + Sync {
+    fn new(num_frames: usize, k: usize) -> Self where Self: Sized;
     fn evict(&self) -> Option<FrameId>;
     fn record_access(&self, frame_id: FrameId, access_type: AccessType);
     fn set_evictable(&self, frame_id: FrameId, set_evictable: bool);
     fn remove(&self, frame_id: FrameId);
     fn size(&self) -> usize;
 }
+
+/// Replacement policies that can be picked when building a buffer pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplacerPolicy {
+    LruK(usize), // k, at least 1
+    Clock,
+    TwoQ,
+    Arc,
+    Lfu,
+}
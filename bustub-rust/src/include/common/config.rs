@@ -12,11 +12,14 @@ pub enum AccessType {
 }
 
 #[derive(Debug, PartialEq)]
+#[allow(non_camel_case_types)]
+// Encoded as a u16 in the B+ tree page header
 //enum class IndexPageType { INVALID_INDEX_PAGE = 0, LEAF_PAGE, INTERNAL_PAGE };
 pub enum IndexPageType {
     INVALID_INDEX_PAGE = 0,
     LEAF_PAGE = 1,
     INTERNAL_PAGE = 2,
+    HEADER_PAGE = 3,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -2,7 +2,8 @@ use std::fmt;
 use crate::include::common::config::{PageId, INVALID_PAGE_ID};
 
 /// Represents a Record ID (RID), identifying a tuple's location in a heap page.
-#[derive(Clone, Copy, PartialEq, Debug)]
+// Rids order by page, then by slot
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Rid {
     page_id: PageId,
     slot_num: u32, // The position of the tuple within that Page's tuple array. 
@@ -41,17 +42,13 @@ impl Rid {
         self.page_id = page_id;
         self.slot_num = slot_num;
     }
-
-    /// Returns a string representation of the RID (e.g., "(page_id, slot_num)").
-    pub fn to_string(&self) -> String {
-        format!("({}, {})", self.page_id, self.slot_num)
-    }
 }
 
 // Implement Display for std::fmt::Display (equivalent to C++ operator<<)
+// String representation of the RID (e.g., "(page_id, slot_num)").
 impl fmt::Display for Rid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "({}, {})", self.page_id, self.slot_num)
     }
 }
 
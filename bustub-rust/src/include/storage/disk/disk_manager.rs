@@ -1,2 +1,20 @@
-use std::fs::{File, OpenOptions};
-use std::sync::{Arc, Mutex};
+use crate::include::common::config::PageId;
+
+// Page 0 of every db file is reserved for the disk header, data pages start at 1
+pub const DISK_HEADER_PAGE_ID: PageId = 0;
+pub const FIRST_DATA_PAGE_ID: PageId = 1;
+
+pub const DISK_HEADER_MAGIC: u32 = 0x4255_5354; // "BUST"
+pub const DISK_HEADER_VERSION: u32 = 1;
+// Marker written at the start of a deallocated page, followed by the next free page id
+pub const FREE_PAGE_MAGIC: u32 = 0x4652_4545; // "FREE"
+
+/// In-memory copy of the disk header page.
+/// Freed pages form a singly linked list threaded through the pages themselves,
+/// the header only stores the head of that list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskHeader {
+    pub next_page_id: PageId,   // high-water mark: first page id never handed out
+    pub free_list_head: PageId, // most recently freed page or INVALID_PAGE_ID
+    pub free_page_count: u32,
+}
@@ -1,7 +1,7 @@
 use crate::include::common::config::PageId;
 //use crate::storage::disk::disk_manager::DiskManager;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread;
+use std::io;
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
@@ -9,12 +9,20 @@ pub struct DiskRequest {
     pub page_id: PageId,
     pub is_write: bool,
     pub data: Arc<Mutex<Vec<u8>>>,
-    // callback used to signal the request issuer when the request has been completed
-    pub is_done: Sender<bool>,
+    // callback used to signal the request issuer when the request has been completed,
+    // carrying the result of the disk I/O
+    pub is_done: Sender<io::Result<()>>,
 }
 
 pub trait DiskSchedulerTrait {
     fn schedule(&self, disk_request: DiskRequest);
     fn start_worker_thread(&mut self);
-    fn deallocate_page(&self);
+    // Allocate a page on disk, reusing freed pages first
+    fn allocate_page(&self) -> io::Result<PageId>;
+    // Return a page to the free list
+    fn deallocate_page(&self, page_id: PageId) -> io::Result<()>;
+    // Grow the database file so it can hold at least `pages` pages
+    fn increase_disk_space(&self, pages: usize) -> io::Result<()>;
+    // Flush everything written so far to stable storage
+    fn sync(&self) -> io::Result<()>;
 }
@@ -1,39 +1,124 @@
-use crate::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
 use crate::buffer::bufferpool_manager::BufferPoolManager;
 use crate::include::common::config::{PageId, ValueType};
+use crate::include::common::rid::Rid;
 use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
-use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageTrait};
-use std::collections::HashMap;
+use crate::include::storage::page::b_plus_tree_page::BplusTreePageTrait;
+use crate::include::storage::index::generic_key::OrdComparator;
+use crate::include::storage::index::index_iterator::IndexIterator;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
 
-pub struct BplusTree<'a> {
+// Whether an index allows several entries with the same key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexMode {
+    // One entry per key, like a primary key
+    #[default]
+    Unique,
+    // Entries with equal keys are ordered by their rid, like a secondary index
+    NonUnique,
+}
+
+// How a tree of variable-length keys shortens the keys it stores, trees of
+// fixed-width keys never compress them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyCompression {
+    #[default]
+    Off,
+    // Separators copied up from a leaf split are cut to the shortest key that still
+    // tells the two leaves apart (suffix truncation), and leaves store the prefix
+    // their keys share once (prefix compression)
+    PrefixSuffix,
+}
+
+// Returned by insert when a unique index already has the key, or a non-unique
+// index the same (key, rid) entry. Holds the rid stored for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateKey {
+    pub existing: Rid,
+}
+
+// A broken invariant found by BplusTree::check_integrity. Indexes are entry
+// (key) slots of the page, the first slot is 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityViolation {
+    // The page can't be read as a tree page at all, nothing below it is checked
+    Corrupted { page_id: PageId, reason: String },
+    // The page id stored in the page isn't the one its parent points at
+    PageIdMismatch { page_id: PageId, stored: PageId },
+    // More than one parent (or the same parent twice) points at the page
+    ReachedTwice { page_id: PageId },
+    // The entry at index isn't greater than the one before it
+    KeysOutOfOrder { page_id: PageId, index: usize },
+    // The entry at index falls outside the range the separators above give the page
+    KeyOutOfRange { page_id: PageId, index: usize },
+    // A page other than the root holds less than get_min_size() entries, or the root
+    // none. Slotted pages other than the root are checked by ByteUnderflow instead.
+    Underflow { page_id: PageId, size: i32, min_size: i32 },
+    // A slotted page other than the root holds less than get_min_size() entries and
+    // takes less than min_used bytes
+    ByteUnderflow { page_id: PageId, used_bytes: usize, min_used: usize },
+    // The page holds more entries than its max size
+    Overflow { page_id: PageId, size: i32, max_size: i32 },
+    // The leaf sits at another depth than the first leaf, the root is at depth 0
+    UnevenLeafDepth { page_id: PageId, depth: usize, expected: usize },
+    // The next page id of the leaf isn't the leaf that follows it in key order
+    BrokenLeafChain { page_id: PageId, next_page_id: PageId, expected: PageId },
+}
+
+// Page counts and key bytes of a tree, gathered by BplusTree::stats
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TreeStats {
+    // Levels of pages, 0 for an empty tree and 1 for a root leaf
+    pub height: usize,
+    pub leaf_pages: usize,
+    pub internal_pages: usize,
+    pub entries: usize,
+    // Child pointers of all internal pages
+    pub children: usize,
+    // Key bytes the leaves store, a shared prefix counts once
+    pub leaf_key_bytes: usize,
+    // Key bytes of all separators in internal pages
+    pub separator_key_bytes: usize,
+    // Key bytes prefix compressed leaves don't store
+    pub prefix_bytes_saved: usize,
+}
+
+// Keys are ordered by the comparator, integer keys by default. Every operation
+// takes &self and latches pages as it goes, so a tree can be shared by threads.
+pub struct BplusTree<K = KeyType, C = OrdComparator> {
     pub index_name: String,
-    pub bpm: &'a BufferPoolManager,
+    pub bpm: BufferPoolManager,
     pub log: Vec<String>,
     pub leaf_max_size: i32,
     pub internal_max_size: i32,
     pub header_page_id: PageId,
-    pub parent_map: HashMap<PageId, PageId>,
-
+    pub comparator: C,
+    pub mode: IndexMode,
+    pub compression: KeyCompression,
+    pub(crate) key_type: PhantomData<K>,
 }
 
-pub trait BplusTreeImpl {
-    // Returns true if this B+ tree has no keys and values. 
-    fn is_empty(&mut self) -> bool;
-    // Insert a key-value pair into this B+tree
-    fn insert(&mut self, key:KeyType, value:ValueType) -> bool;
-    // Remove a key and its value from this B+tree
-    fn remove(&mut self, key: KeyType);
-    // Return the value associated with a given key
-    fn get_value(&self, key: KeyType, );
+pub trait BplusTreeImpl<K = KeyType> {
+    // Returns true if this B+ tree has no keys and values.
+    fn is_empty(&self) -> bool;
+    // Insert a key-value pair into this B+tree, fails on a duplicate (see DuplicateKey)
+    fn insert(&self, key:K, value:ValueType) -> Result<(), DuplicateKey>;
+    // Remove the key's entry, if there is one. A non-unique index only removes the
+    // entry pointing at rid, a unique one ignores rid.
+    fn remove(&self, key: K, rid: Rid);
+    // Return the values associated with a given key ordered by rid, at most one in a unique index
+    fn get_value(&self, key: K) -> Vec<Rid>;
     // Return the pageid of the root node
-    fn get_root_page_id(&mut self) -> PageId;
-    // Index Iterator
-    fn begin(&self); // Index Iterator Type?????
+    fn get_root_page_id(&self) -> PageId;
+    // Iterator over every entry in key order
+    fn begin(&self) -> IndexIterator<'_, K>;
+    // Iterator starting at the first key >= key
+    fn begin_at(&self, key: K) -> IndexIterator<'_, K>;
+    // Iterator over the keys in range, any mix of inclusive, exclusive and open bounds
+    fn range<R: RangeBounds<K>>(&self, range: R) -> IndexIterator<'_, K>;
+    // Iterator past the last entry, every finished iterator compares equal to it
+    fn end(&self) -> IndexIterator<'_, K>;
     fn is_safe_to_insert(&self, page: &dyn BplusTreePageTrait)-> bool;
     //fn split_leaf(&self, leaf_page: &mut BplusTreeLeafPage) -> (LeafPageGuard, i64);
 
 }
-
-pub struct IndexIterator {
-
-}
\ No newline at end of file
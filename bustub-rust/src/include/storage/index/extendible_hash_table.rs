@@ -0,0 +1,37 @@
+use crate::buffer::bufferpool_manager::BufferPoolManager;
+use crate::include::common::config::PageId;
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::{DefaultKeyHasher, OrdComparator};
+use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
+use std::io;
+use std::marker::PhantomData;
+
+// A disk-backed extendible hash table for equality-only unique indexes. A header
+// page routes the high bits of a key's hash to a directory page, the directory
+// routes the low bits to a bucket page. Full buckets split and double the
+// directory when they have to, empty buckets merge with their split image and the
+// directory halves again once no bucket needs all of its bits.
+pub struct ExtendibleHashTable<K = KeyType, C = OrdComparator, H = DefaultKeyHasher> {
+    pub index_name: String,
+    pub bpm: BufferPoolManager,
+    pub header_page_id: PageId,
+    // Tells keys with the same hash apart
+    pub comparator: C,
+    pub hasher: H,
+    // Set up the directories the table creates, split buckets keep the max size of
+    // the bucket they split from
+    pub directory_max_depth: u32,
+    pub bucket_max_size: u32,
+    pub(crate) key_type: PhantomData<K>,
+}
+
+pub trait ExtendibleHashTableImpl<K = KeyType> {
+    // Insert the key pointing at rid. Fails with InvalidInput holding a DuplicateKey
+    // if the table already has the key, and with StorageFull if its bucket is full
+    // and can't split because the directory is at its max depth.
+    fn insert(&self, key: K, rid: Rid) -> io::Result<()>;
+    // Remove the entry of the key pointing at rid, false if there is none
+    fn remove(&self, key: K, rid: Rid) -> bool;
+    // The rid stored for the key, at most one
+    fn get_value(&self, key: K) -> Vec<Rid>;
+}
@@ -0,0 +1,96 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+/// A key the B+ tree pages can store. Slot counts are derived from SIZE.
+pub trait IndexKey: Copy + Debug + PartialEq + 'static {
+    /// Bytes the key takes in a page, the most it may take for variable-length keys
+    const SIZE: usize;
+    /// Variable-length keys are stored in slotted pages, which fill up by bytes
+    /// rather than by a fixed number of SIZE byte slots
+    const VARIABLE_LENGTH: bool = false;
+    /// Bytes `encode` writes for this key
+    fn encoded_len(&self) -> usize {
+        Self::SIZE
+    }
+    /// Writes the key into `buf`, which is exactly encoded_len bytes long
+    fn encode(&self, buf: &mut [u8]);
+    /// Reads a key written by `encode`
+    fn decode(buf: &[u8]) -> Self;
+    /// Leading bytes the encodings of this key and `other` share, which prefix
+    /// compressed leaves store once. Only variable-length keys share any.
+    fn common_prefix_len(&self, _other: &Self) -> usize {
+        0
+    }
+    /// A key k with lower < k <= upper in byte order, as short as the key type
+    /// allows. Separators cut down to it still tell the same two pages apart.
+    fn shortest_separator(_lower: &Self, upper: &Self) -> Self {
+        *upper
+    }
+}
+
+/// Orders the keys of a tree. The comparator is part of the index definition,
+/// the same key bytes may sort differently in another index.
+pub trait KeyComparator<K> {
+    fn compare(&self, lhs: &K, rhs: &K) -> Ordering;
+}
+
+/// Orders keys by their `Ord` implementation, the default for integer keys
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrdComparator;
+
+/// Hashes the keys of a hash index. The hash picks the pages a key is stored in,
+/// so a reopened index has to hash with the same function.
+pub trait KeyHasher<K> {
+    fn hash(&self, key: &K) -> u32;
+}
+
+/// Hashes the encoded key bytes with 32 bit FNV-1a and mixes the result with the
+/// MurmurHash3 finalizer, so the low bits and the high bits both spread well
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultKeyHasher;
+
+/// An opaque key of N bytes, N is one of 4, 8, 16, 32 or 64. The bytes are built
+/// from column values by `KeySchema::key` and only the `GenericComparator` of the
+/// same schema knows how to order them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GenericKey<const N: usize> {
+    pub(crate) data: [u8; N],
+}
+
+/// A variable-length key of at most N bytes, like a VARCHAR(N) column. Pages only
+/// store the bytes the key has. Keys order byte-wise, which is code point order for
+/// UTF-8 strings, so a tree of them uses the OrdComparator.
+#[derive(Clone, Copy)]
+pub struct VarcharKey<const N: usize> {
+    pub(crate) len: u16,
+    // Zero past len
+    pub(crate) data: [u8; N],
+}
+
+/// Type of a key column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyColumn {
+    Integer,        // i32, 4 bytes
+    BigInt,         // i64, 8 bytes
+    Varchar(usize), // at most this many bytes of UTF-8, zero padded
+}
+
+/// Value of a key column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyValue {
+    Integer(i32),
+    BigInt(i64),
+    Varchar(String),
+}
+
+/// The columns of a (possibly composite) key, stored back to back in the key bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySchema {
+    pub(crate) columns: Vec<KeyColumn>,
+}
+
+/// Orders generic keys column by column: integers numerically, strings byte-wise
+#[derive(Debug, Clone)]
+pub struct GenericComparator<const N: usize> {
+    pub(crate) schema: KeySchema,
+}
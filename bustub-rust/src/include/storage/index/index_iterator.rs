@@ -0,0 +1,18 @@
+use crate::buffer::bufferpool_manager::BufferPoolManager;
+use crate::include::storage::index::generic_key::KeyComparator;
+use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
+use crate::include::storage::page::b_plus_tree_leaf_page::BplusTreeLeafPage;
+use crate::storage::page::page_guard::ReadPageGuard;
+use std::ops::Bound;
+
+/// Walks the leaf chain in key order, yielding (key, rid) pairs. Only the
+/// current leaf is read latched between calls, the next one is latched before
+/// it is released so a concurrent merge can't free the page being moved to.
+pub struct IndexIterator<'a, K = KeyType> {
+    pub(crate) bpm: &'a BufferPoolManager,
+    pub(crate) comparator: &'a dyn KeyComparator<K>,
+    // The latched leaf and its decoded entries, None once the iterator is exhausted
+    pub(crate) leaf: Option<(ReadPageGuard, Box<BplusTreeLeafPage<K>>)>,
+    pub(crate) index: usize,
+    pub(crate) upper: Bound<K>,
+}
@@ -1 +1,4 @@
-pub mod b_plus_tree;
\ No newline at end of file
+pub mod b_plus_tree;
+pub mod generic_key;
+pub mod index_iterator;
+pub mod extendible_hash_table;
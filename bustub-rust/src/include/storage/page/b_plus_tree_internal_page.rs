@@ -1,36 +1,65 @@
-
 use crate::include::common::config::{PageId, PAGE_SIZE};
-use crate::include::storage::page::b_plus_tree_page::BplusTreePage;
-const INTERNAL_PAGE_HEADER_SIZE: usize = 12;
-// INTERNAL_PAGE_SLOT_CNT
-const KEY_SIZE: usize = 8; // 8BYTES
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::KeyComparator;
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BPLUS_TREE_PAGE_HEADER_SIZE};
+use std::io;
 const VALUE_SIZE: usize = 4; // 4BYTES
-const PAIR_SIZE: usize = KEY_SIZE + VALUE_SIZE;
-pub const INTERNAL_PAGE_SLOT_CNT: usize = (PAGE_SIZE - INTERNAL_PAGE_HEADER_SIZE ) / PAIR_SIZE;
 
+// Number of (key, child) slots that fit an internal page with keys of key_size bytes
+pub const fn internal_page_slot_cnt(key_size: usize) -> usize {
+    (PAGE_SIZE - BPLUS_TREE_PAGE_HEADER_SIZE) / (key_size + VALUE_SIZE)
+}
+
+// INTERNAL_PAGE_SLOT_CNT for the default 8 byte keys
+pub const INTERNAL_PAGE_SLOT_CNT: usize = internal_page_slot_cnt(8);
 
+
+// The default key type, see generic_key for the others
 pub type KeyType = i64;
 
-#[repr(C)]
 #[derive(Debug, PartialEq)]
-pub struct BplusTreeInternalPage {
+pub struct BplusTreeInternalPage<K = KeyType> {
     pub base_page: BplusTreePage,
-    pub key_array: [i64; INTERNAL_PAGE_SLOT_CNT],
-    pub page_id_array: [PageId; INTERNAL_PAGE_SLOT_CNT],
+    // All hold internal_page_slot_cnt(K::SIZE) slots
+    pub key_array: Vec<K>,
+    pub page_id_array: Vec<PageId>,
+    // The rid of the entry each separator was copied from, only stored when
+    // separator_rids is set. Non-unique indexes order equal keys by rid.
+    pub rid_array: Vec<Rid>,
+    pub separator_rids: bool,
 }
-pub trait BplusTreeInternalPageImpl {
+pub trait BplusTreeInternalPageImpl<K> {
     fn new(max_size: i32, page_id: PageId) -> Self;
-    /// returns the key at the specified index. 
-    fn key_at(&self, index: i32) -> KeyType;
+    /// returns the key at the specified index.
+    fn key_at(&self, index: i32) -> K;
     /// Sets the key at the specified index
-    fn set_key_at(&mut self, index: i32, key: KeyType);
-    fn set_page_id_at(&mut self, index: i32, page_id: PageId); 
+    fn set_key_at(&mut self, index: i32, key: K);
+    fn set_page_id_at(&mut self, index: i32, page_id: PageId);
     /// returns the index of the page id
     fn value_index(&self, value: PageId) -> i32;
     /// returns the child page id at the specfied index
     fn page_id_value_at(&self, index: i32) -> PageId;
+    /// returns the index of the child whose subtree covers the key
+    fn lookup_index<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> i32;
+    /// returns the child page whose subtree covers the key
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> PageId;
+    /// like lookup_index, but separators equal to the key are ordered by their rid
+    /// when the page has separator rids. No rid routes before every entry of the key.
+    fn lookup_entry_index<C: KeyComparator<K> + ?Sized>(&self, key: K, rid: Option<Rid>, comparator: &C) -> i32;
+    /// removes the child at index (> 0) and the separator key left of it
+    fn remove_at(&mut self, index: i32);
     //fn index_value_at(&self, index:i32) -> i32;
+    /// the separator keys of the page, like (5,9)
     fn to_string(&self) -> String;
     fn is_leaf(&self) -> bool;
+    /// Writes the page in its on-disk format, `page` must be PAGE_SIZE bytes.
+    /// The size + 1 child page ids (i32) start at offset 32, followed by
+    /// a slot for every key after the full array of child ids. Pages with
+    /// separator rids have fewer slots and store the rids after the keys.
+    /// Slotted pages keep child 0 at offset 32 and the slots after it, each entry
+    /// (key, rid if any, child right of the key) ends at the end of the page.
+    fn encode(&self, page: &mut [u8]);
+    /// Reads a page written by `encode`, fails on corrupted or non-internal pages
+    /// and on pages holding keys of another width
+    fn decode(page: &[u8]) -> io::Result<Self> where Self: Sized;
 }
-
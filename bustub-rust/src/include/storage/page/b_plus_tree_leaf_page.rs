@@ -1,35 +1,61 @@
-
-
 use crate::include::common::config::{PageId, ValueType, PAGE_SIZE};
 use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
+use crate::include::storage::index::generic_key::KeyComparator;
 use crate::include::common::rid::Rid;
-use crate::include::storage::page::b_plus_tree_page::BplusTreePage;
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BPLUS_TREE_PAGE_HEADER_SIZE};
+use std::io;
 
-pub const LEAF_PAGE_HEADER_SIZE: usize = 16;
+// Entries start right after the shared page header. The header once counted only
+// the 16 bytes of the base page and left out next_page_id and the padding up to
+// the 8 byte aligned keys, so a full leaf of 255 slots ran past the end of its
+// frame. Slot counts are derived from this size and not hard coded.
+pub const LEAF_PAGE_HEADER_SIZE: usize = BPLUS_TREE_PAGE_HEADER_SIZE;
+pub(crate) const RID_SIZE: usize = 8; // page id i32, slot u32
 
-// INTERNAL_PAGE_SLOT_CNT
-const KEY_SIZE: usize = 8; // 8BYTES
-const VALUE_SIZE: usize = 4; // 4BYTES
-const PAIR_SIZE: usize = KEY_SIZE + VALUE_SIZE;
-pub const LEAF_PAGE_SLOT_CNT: usize =
-    (PAGE_SIZE - LEAF_PAGE_HEADER_SIZE) / (KEY_SIZE+ std::mem::size_of::<Rid>());
+// Number of (key, rid) entries that fit a leaf page with keys of key_size bytes
+pub const fn leaf_page_slot_cnt(key_size: usize) -> usize {
+    (PAGE_SIZE - LEAF_PAGE_HEADER_SIZE) / (key_size + RID_SIZE)
+}
+
+// LEAF_PAGE_SLOT_CNT for the default 8 byte keys
+pub const LEAF_PAGE_SLOT_CNT: usize = leaf_page_slot_cnt(8);
 
-#[repr(C)]
 #[derive(Debug, PartialEq)]
-pub struct BplusTreeLeafPage {
+pub struct BplusTreeLeafPage<K = KeyType> {
     pub base_page: BplusTreePage,
     pub next_page_id: PageId,
-    pub key_array: [KeyType; LEAF_PAGE_SLOT_CNT],
-    pub rid_array: [Rid; LEAF_PAGE_SLOT_CNT],
+    // Both hold leaf_page_slot_cnt(K::SIZE) slots
+    pub key_array: Vec<K>,
+    pub rid_array: Vec<Rid>,
+    // Slotted leaves store the prefix all their keys share once when set
+    pub prefix_compression: bool,
 }
 
-pub trait BplusTreeLeafPageImpl {
+pub trait BplusTreeLeafPageImpl<K> {
     fn new(max_size: i32, page_id: PageId) -> Self;
     fn get_next_page_id(&self) -> PageId;
     fn set_next_page_id(&mut self, page_id: PageId);
-    fn key_at(&self, index: i32) -> KeyType;
+    fn key_at(&self, index: i32) -> K;
     fn to_string(&self)-> String;
-    fn insert(&mut self, index: i32, key: KeyType, value: ValueType)-> bool;
+    fn insert(&mut self, index: i32, key: K, value: ValueType)-> bool;
     fn is_leaf(&self) -> bool;
-    fn find_insert_position(&self, key: KeyType) -> i32;
-}
\ No newline at end of file
+    fn find_insert_position<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> i32;
+    /// Returns the rid stored for the key, if the key is in this leaf
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> Option<Rid>;
+    /// Removes the key and its rid, false if the key is not in this leaf
+    fn remove<C: KeyComparator<K> + ?Sized>(&mut self, key: K, comparator: &C) -> bool;
+    /// Binary searches entries ordered by key, then rid. Ok with the index of the
+    /// (key, rid) entry, Err with the index it would be inserted at.
+    fn search_entry<C: KeyComparator<K> + ?Sized>(&self, key: K, rid: Rid, comparator: &C) -> Result<i32, i32>;
+    /// Removes the entry at index
+    fn remove_at(&mut self, index: i32);
+    /// Writes the page in its on-disk format, `page` must be PAGE_SIZE bytes.
+    /// Entries are (key, rid page id i32, rid slot u32) from offset 32, in slotted
+    /// pages the slots start there and the entries end at the end of the page.
+    /// Prefix compressed pages put the shared prefix (length u16, bytes) at 32,
+    /// the slots follow it and the entries only hold the rest of their key.
+    fn encode(&self, page: &mut [u8]);
+    /// Reads a page written by `encode`, fails on corrupted or non-leaf pages
+    /// and on pages holding keys of another width
+    fn decode(page: &[u8]) -> io::Result<Self> where Self: Sized;
+}
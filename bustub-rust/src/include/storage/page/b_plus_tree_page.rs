@@ -1,5 +1,34 @@
-use crate::include::common::config::{IndexPageType, PageId};
+use crate::include::common::config::{IndexPageType, PageId, PAGE_SIZE};
 
+// On-disk layout shared by leaf and internal pages, every integer is little-endian:
+//    0  magic         u32
+//    4  version       u16
+//    6  page type     u16
+//    8  checksum      u32, CRC-32 of the whole page with this field zeroed
+//   12  size          i32
+//   16  max size      i32
+//   20  page id       i32
+//   24  next page id  i32, INVALID_PAGE_ID on internal pages
+//   28  key size      u16, bytes per key in the entries
+//   30  flags         u16, bit 0 set on internal pages whose separators carry rids,
+//                     bit 1 on slotted pages, bit 2 on prefix compressed leaves
+//   32  entries, see the leaf and internal page encoders
+//
+// The tree's header page shares this header with size and max size 0, its
+// root page id follows at 32.
+//
+// Pages of variable-length keys are slotted: a slot directory of (offset u16,
+// key length u16) pairs grows from the header and the entries it points at grow
+// from the end of the page, each holding only the bytes its key has.
+pub const BPLUS_TREE_PAGE_MAGIC: u32 = 0x4250_5447; // "GTPB" on disk
+pub const BPLUS_TREE_PAGE_VERSION: u16 = 2;
+pub const BPLUS_TREE_PAGE_HEADER_SIZE: usize = 32;
+pub const SLOT_SIZE: usize = 4;
+
+// Keys of slotted pages take at most this many bytes, so at least eight of the
+// largest entries (an internal one with a rid) fit a page. Split and merged pages
+// then always have room to spare.
+pub const MAX_SLOTTED_KEY_SIZE: usize = (PAGE_SIZE - BPLUS_TREE_PAGE_HEADER_SIZE - 4) / 8 - 16;
 
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -25,4 +54,9 @@ pub trait BplusTreePageTrait {
     fn is_leaf(&self) -> bool;
     fn max_size(&self) -> i32;
     fn get_size(&self) -> i32;
+    fn min_size(&self) -> i32;
+    // Bytes the page takes in the slotted format, how full a page of variable-length keys is
+    fn used_bytes(&self) -> usize;
+    // The most bytes taking one more entry can add to used_bytes
+    fn max_entry_bytes(&self) -> usize;
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use crate::include::common::config::{PageId, PAGE_SIZE};
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::KeyComparator;
+use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
+use crate::include::storage::page::b_plus_tree_leaf_page::RID_SIZE;
+use std::io;
+
+// Page type of hash table bucket pages, see HTABLE_PAGE_MAGIC
+pub const HTABLE_BUCKET_PAGE_TYPE: u16 = 3;
+pub const HTABLE_BUCKET_PAGE_HEADER_SIZE: usize = 24;
+
+// Number of (key, rid) entries that fit a bucket page with keys of key_size
+// bytes. Every entry stores the length of its key too.
+pub const fn bucket_page_slot_cnt(key_size: usize) -> usize {
+    (PAGE_SIZE - HTABLE_BUCKET_PAGE_HEADER_SIZE) / (2 + key_size + RID_SIZE)
+}
+
+// The entries of one bucket of an extendible hash table, in no particular order
+#[derive(Debug, PartialEq)]
+pub struct ExtendibleHTableBucketPage<K = KeyType> {
+    pub page_id: PageId,
+    pub max_size: u32,
+    pub entries: Vec<(K, Rid)>,
+}
+
+pub trait ExtendibleHTableBucketPageImpl<K> {
+    fn new(max_size: u32, page_id: PageId) -> Self;
+    // Returns the rid stored for the key, if the key is in this bucket
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: &K, comparator: &C) -> Option<Rid>;
+    // Adds the entry, false if the bucket is full. Keys aren't checked for duplicates.
+    fn insert(&mut self, key: K, rid: Rid) -> bool;
+    // Removes the entry of the key pointing at rid, false if there is none
+    fn remove<C: KeyComparator<K> + ?Sized>(&mut self, key: &K, rid: Rid, comparator: &C) -> bool;
+    fn size(&self) -> u32;
+    fn is_full(&self) -> bool;
+    fn is_empty(&self) -> bool;
+    // Writes the page in its on-disk format, `page` must be PAGE_SIZE bytes. Every
+    // integer is little-endian:
+    //    0  index page header  magic, version, page type and checksum
+    //   12  size               u32
+    //   16  max size           u32
+    //   20  key size           u16, bytes per key slot
+    //   24  entries            (key length u16, key padded to key size, rid page id i32, rid slot u32)
+    fn encode(&self, page: &mut [u8]);
+    // Reads a page written by `encode`, fails on corrupted or non-bucket pages and
+    // on pages holding keys of another width
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> where Self: Sized;
+}
@@ -0,0 +1,62 @@
+use crate::include::common::config::PageId;
+use std::io;
+
+// Page type of hash table directory pages, see HTABLE_PAGE_MAGIC
+pub const HTABLE_DIRECTORY_PAGE_TYPE: u16 = 2;
+// A directory grows to at most 2^9 slots
+pub const HTABLE_DIRECTORY_MAX_DEPTH: u32 = 9;
+pub const HTABLE_DIRECTORY_ARRAY_SIZE: usize = 1 << HTABLE_DIRECTORY_MAX_DEPTH;
+
+// Maps the low global_depth bits of a hash to a bucket page. A bucket of local
+// depth d holds the keys whose low d bits match its slots, the 2^(global_depth - d)
+// slots sharing those bits all point at it.
+#[derive(Debug, PartialEq)]
+pub struct ExtendibleHTableDirectoryPage {
+    pub page_type: u16,
+    pub max_depth: u32,
+    pub global_depth: u32,
+    // Only the first 2^global_depth slots are in use
+    pub local_depths: [u8; HTABLE_DIRECTORY_ARRAY_SIZE],
+    pub bucket_page_ids: [PageId; HTABLE_DIRECTORY_ARRAY_SIZE],
+}
+
+pub trait ExtendibleHTableDirectoryPageImpl {
+    // Slot of the bucket the hash belongs to
+    fn hash_to_bucket_index(&self, hash: u32) -> usize;
+    fn get_bucket_page_id(&self, bucket_index: usize) -> PageId;
+    fn set_bucket_page_id(&mut self, bucket_index: usize, page_id: PageId);
+    // The slot the bucket at bucket_index was split from, or splits into
+    fn get_split_image_index(&self, bucket_index: usize) -> usize;
+    fn get_global_depth(&self) -> u32;
+    fn get_max_depth(&self) -> u32;
+    // The low global_depth bits set
+    fn get_global_depth_mask(&self) -> u32;
+    // The low bits the keys of the bucket at bucket_index share
+    fn get_local_depth_mask(&self, bucket_index: usize) -> u32;
+    fn get_local_depth(&self, bucket_index: usize) -> u32;
+    fn set_local_depth(&mut self, bucket_index: usize, local_depth: u32);
+    // Doubles the directory, the new upper half mirrors the lower half
+    fn incr_global_depth(&mut self);
+    // Halves the directory, only valid when can_shrink
+    fn decr_global_depth(&mut self);
+    // True when every bucket has a local depth below the global depth
+    fn can_shrink(&self) -> bool;
+    // Slots in use, 2^global_depth
+    fn size(&self) -> usize;
+    // Slots the directory can grow to, 2^max_depth
+    fn max_size(&self) -> usize;
+    // Fails unless the depths are in range and every bucket of local depth d is
+    // pointed at by exactly the 2^(global_depth - d) slots sharing its low d bits
+    fn verify_integrity(&self) -> io::Result<()>;
+    // Writes the page in its on-disk format, `page` must be PAGE_SIZE bytes. Every
+    // integer is little-endian:
+    //    0  index page header  magic, version, page type and checksum
+    //   12  max depth          u32
+    //   16  global depth       u32
+    //   20  local depths       u8 each, all HTABLE_DIRECTORY_ARRAY_SIZE of them
+    //  532  bucket page ids    i32 each, all HTABLE_DIRECTORY_ARRAY_SIZE of them
+    fn encode(&self, page: &mut [u8]);
+    // Reads a page written by `encode`, fails on corrupted or non-directory pages and
+    // on depths out of range. The slots are checked by verify_integrity.
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> where Self: Sized;
+}
@@ -0,0 +1,38 @@
+use crate::include::common::config::PageId;
+use std::io;
+
+// Every hash table page starts with the header shared by all index pages: this
+// magic, the version, its page type and a checksum of the page
+pub const HTABLE_PAGE_MAGIC: u32 = 0x4C42_5448; // "HTBL" on disk
+pub const HTABLE_PAGE_VERSION: u16 = 1;
+pub const HTABLE_HEADER_PAGE_TYPE: u16 = 1;
+// The header routes hashes to at most 2^9 directories
+pub const HTABLE_HEADER_MAX_DEPTH: u32 = 9;
+pub const HTABLE_HEADER_ARRAY_SIZE: usize = 1 << HTABLE_HEADER_MAX_DEPTH;
+
+// The first page of an extendible hash table. The top max_depth bits of a hash
+// pick the directory of the key, directories are created on first use.
+#[derive(Debug, PartialEq)]
+pub struct ExtendibleHTableHeaderPage {
+    pub page_type: u16,
+    pub max_depth: u32,
+    // INVALID_PAGE_ID until a key routed to the directory is inserted
+    pub directory_page_ids: [PageId; HTABLE_HEADER_ARRAY_SIZE],
+}
+
+pub trait ExtendibleHTableHeaderPageImpl {
+    // Index of the directory the hash belongs to
+    fn hash_to_directory_index(&self, hash: u32) -> usize;
+    fn get_directory_page_id(&self, directory_index: usize) -> PageId;
+    fn set_directory_page_id(&mut self, directory_index: usize, page_id: PageId);
+    // Number of directories the header can route to, 2^max_depth
+    fn max_size(&self) -> usize;
+    // Writes the page in its on-disk format, `page` must be PAGE_SIZE bytes. Every
+    // integer is little-endian:
+    //    0  index page header   magic, version, page type and checksum
+    //   12  max depth           u32
+    //   16  directory page ids  i32 each, all HTABLE_HEADER_ARRAY_SIZE of them
+    fn encode(&self, page: &mut [u8]);
+    // Reads a page written by `encode`, fails on corrupted or non-header pages
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> where Self: Sized;
+}
@@ -1,5 +1,9 @@
 pub mod page_guard;
+#[allow(clippy::module_inception)]
 pub mod page;
 pub mod b_plus_tree_page;
 pub mod b_plus_tree_internal_page;
-pub mod b_plus_tree_leaf_page;
\ No newline at end of file
+pub mod b_plus_tree_leaf_page;
+pub mod extendible_htable_header_page;
+pub mod extendible_htable_directory_page;
+pub mod extendible_htable_bucket_page;
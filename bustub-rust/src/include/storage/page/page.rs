@@ -1,5 +1,3 @@
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard}; 
-
 pub struct Page {
     
 }
\ No newline at end of file
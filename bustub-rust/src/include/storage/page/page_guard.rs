@@ -1,6 +1,5 @@
-use std::sync::{Arc, RwLock}; 
-use std::ops::{Deref, DerefMut}; 
-use crate::include::common::config::{AccessType, FrameId, PageId};
+use crate::include::common::config::{FrameId, PageId};
+use std::io;
 
 
 pub trait PageguardImpl{
@@ -12,12 +11,14 @@ pub trait PageguardImpl{
 pub trait ReadPageGuardImpl: PageguardImpl{
     fn as_ref(&self) -> &[u8]; 
     fn is_dirty(&self) -> bool;
-    fn flush(&self);
+    // Blocks until a dirty page is on disk, the page stays dirty if the write fails
+    fn flush(&self) -> io::Result<()>;
 }
 
 pub trait WritePageGuardImpl: PageguardImpl{
     fn as_ref(&self) -> &[u8];
     fn as_mut(&mut self) -> &mut [u8]; 
     fn is_dirty(&self) -> bool;
-    fn flush(&self);
+    // Blocks until a dirty page is on disk, the page stays dirty if the write fails
+    fn flush(&self) -> io::Result<()>;
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
 pub mod buffer;
+pub mod common;
 pub mod include;
 pub mod storage;
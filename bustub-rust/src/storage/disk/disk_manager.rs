@@ -1,61 +1,279 @@
-use crate::include::common::config::PageId;
-//use crate::include::storage::disk::disk_manager::DiskManager;
-use std::fs::{File, OpenOptions};
-use std::io::{self, Read, Seek, SeekFrom, Write};
-use std::sync::{Arc, Mutex}; 
-use std::path::Path;
+use crate::include::common::config::{PageId, INVALID_PAGE_ID, PAGE_SIZE};
+use crate::include::storage::disk::disk_manager::{
+    DiskHeader, DISK_HEADER_MAGIC, DISK_HEADER_PAGE_ID, DISK_HEADER_VERSION, FIRST_DATA_PAGE_ID, FREE_PAGE_MAGIC,
+};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::fs::FileExt;
+use std::sync::{Arc, Mutex};
 
+// Size of one record in the legacy append-only log: (page_id, page bytes)
+const APPEND_LOG_ENTRY_SIZE: usize = 4 + PAGE_SIZE;
+
+/// Page file where page N lives at byte offset `N * PAGE_SIZE`.
+/// Reads and writes are positional (pread/pwrite), so concurrent requests
+/// for different pages never contend on a shared file cursor.
+/// Page 0 holds the disk header which tracks allocation across restarts.
 #[derive(Clone)]
 pub struct DiskManager {
     pub db_file: String,        // file path eg- "test.db"
-    pub file: Arc<Mutex<File>>, // Open file handle
+    pub file: Arc<File>,        // Open file handle
+    header: Arc<Mutex<DiskHeader>>, // also serializes allocation and file growth
 }
 
+impl DiskHeader {
+    fn new(next_page_id: PageId) -> Self {
+        DiskHeader {
+            next_page_id,
+            free_list_head: INVALID_PAGE_ID,
+            free_page_count: 0,
+        }
+    }
+
+    fn to_page(self) -> [u8; PAGE_SIZE] {
+        let mut page = [0u8; PAGE_SIZE];
+        page[0..4].copy_from_slice(&DISK_HEADER_MAGIC.to_le_bytes());
+        page[4..8].copy_from_slice(&DISK_HEADER_VERSION.to_le_bytes());
+        page[8..12].copy_from_slice(&self.next_page_id.to_le_bytes());
+        page[12..16].copy_from_slice(&self.free_list_head.to_le_bytes());
+        page[16..20].copy_from_slice(&self.free_page_count.to_le_bytes());
+        page
+    }
+
+    // None if the page does not carry the header magic
+    fn from_page(page: &[u8]) -> io::Result<Option<Self>> {
+        let read_u32 = |pos: usize| u32::from_le_bytes(page[pos..pos + 4].try_into().unwrap());
+        if read_u32(0) != DISK_HEADER_MAGIC {
+            return Ok(None);
+        }
+        if read_u32(4) != DISK_HEADER_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unsupported db file version {}", read_u32(4))));
+        }
+        Ok(Some(DiskHeader {
+            next_page_id: read_u32(8) as PageId,
+            free_list_head: read_u32(12) as PageId,
+            free_page_count: read_u32(16),
+        }))
+    }
+}
 
 impl DiskManager {
+    /// Opens (or creates) a db file and recovers its allocation state from the header page.
+    /// A file without a header is adopted only if page 0 is unused (all zeroes),
+    /// every existing page is then treated as allocated.
     pub fn new(db_file: &str) -> io::Result<Self> {
         let file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
+            .truncate(false)
             .open(db_file)?;
-        Ok(Self {
+        let dm = Self {
             db_file: db_file.to_string(),
-            file: Arc::new(Mutex::new(file)),
-        })
+            file: Arc::new(file),
+            header: Arc::new(Mutex::new(DiskHeader::new(FIRST_DATA_PAGE_ID))),
+        };
+
+        let mut page = [0u8; PAGE_SIZE];
+        dm.read_raw(DISK_HEADER_PAGE_ID, &mut page)?;
+        let header = match DiskHeader::from_page(&page)? {
+            Some(header) => header,
+            None if page.iter().all(|&b| b == 0) => {
+                let header = DiskHeader::new((dm.num_pages()? as PageId).max(FIRST_DATA_PAGE_ID));
+                dm.write_raw(DISK_HEADER_PAGE_ID, &header.to_page())?;
+                header
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not a db file (page 0 is not a disk header)", db_file),
+                ))
+            }
+        };
+        *dm.header.lock().unwrap() = header;
+        Ok(dm)
     }
 
+    fn page_offset(page_id: PageId) -> io::Result<u64> {
+        if page_id < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid page id {}", page_id)));
+        }
+        Ok(page_id as u64 * PAGE_SIZE as u64)
+    }
+
+    fn check_data_page(page_id: PageId) -> io::Result<()> {
+        if page_id == DISK_HEADER_PAGE_ID {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page 0 is reserved for the disk header"));
+        }
+        Ok(())
+    }
+
+    /// Reads page `page_id` into `data`. A page that was only partially written
+    /// is zero-filled past the end of the file.
     pub fn read_page(&self, page_id: PageId, data: &mut [u8]) -> io::Result<()> {
-        let mut file = self.file.lock().unwrap();
-        file.seek(SeekFrom::Start(0))?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
-
-        let entry_size = 4 + 4096;
-        let mut last_match = None;
-        for i in (0..buffer.len()).step_by(entry_size) {
-            if i + entry_size > buffer.len() { break; }
-            let pid = i32::from_le_bytes(buffer[i..i+4].try_into().unwrap());
-            if pid == page_id {
-                last_match = Some(i);
-            }            
-        }
-        match last_match {
-            Some(pos) => {
-                data.copy_from_slice(&buffer[pos + 4..pos + 4 + 4096]);
-                Ok(())
-            }
-            None => Err(io::Error::new(io::ErrorKind::NotFound, "Page not found")),
+        Self::check_data_page(page_id)?;
+        let offset = Self::page_offset(page_id)?;
+        if offset >= self.file_size()? {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "Page not found"));
         }
+        self.read_raw(page_id, data)
     }
 
     pub fn write_page(&self, page_id: PageId, data: &[u8]) -> io::Result<()> {
-        let mut file = self.file.lock().unwrap();
-        let mut buffer = Vec::new();
-        buffer.extend_from_slice(&page_id.to_le_bytes());
-        buffer.extend_from_slice(data);
-        file.write_all(&buffer)?;
-        file.flush()?;
+        Self::check_data_page(page_id)?;
+        self.write_raw(page_id, data)
+    }
+
+    fn read_raw(&self, page_id: PageId, data: &mut [u8]) -> io::Result<()> {
+        let offset = Self::page_offset(page_id)?;
+        let mut read = 0;
+        while read < data.len() {
+            match self.file.read_at(&mut data[read..], offset + read as u64) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        data[read..].fill(0);
+        Ok(())
+    }
+
+    fn write_raw(&self, page_id: PageId, data: &[u8]) -> io::Result<()> {
+        let offset = Self::page_offset(page_id)?;
+        self.file.write_all_at(data, offset)?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Hands out a page id, reusing the most recently freed page if there is one.
+    /// The returned page is zeroed on disk and the header is persisted before returning.
+    pub fn allocate_page(&self) -> io::Result<PageId> {
+        let mut header = self.header.lock().unwrap();
+        let page_id = if header.free_list_head != INVALID_PAGE_ID {
+            let page_id = header.free_list_head;
+            let mut page = [0u8; PAGE_SIZE];
+            self.read_raw(page_id, &mut page)?;
+            let (magic, next_free) = Self::decode_free_page(&page);
+            if magic != FREE_PAGE_MAGIC {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Free list is corrupted at page {}", page_id)));
+            }
+            header.free_list_head = next_free;
+            header.free_page_count -= 1;
+            page_id
+        } else {
+            let page_id = header.next_page_id;
+            header.next_page_id += 1;
+            page_id
+        };
+        self.grow_to(header.next_page_id as usize)?;
+        self.write_raw(page_id, &[0u8; PAGE_SIZE])?;
+        self.write_raw(DISK_HEADER_PAGE_ID, &header.to_page())?;
+        Ok(page_id)
+    }
+
+    /// Returns a page to the free list so the next allocation can reuse it.
+    pub fn deallocate_page(&self, page_id: PageId) -> io::Result<()> {
+        Self::check_data_page(page_id)?;
+        let mut header = self.header.lock().unwrap();
+        if page_id < FIRST_DATA_PAGE_ID || page_id >= header.next_page_id {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Page {} was never allocated", page_id)));
+        }
+        let mut page = [0u8; PAGE_SIZE];
+        self.read_raw(page_id, &mut page)?;
+        if Self::decode_free_page(&page).0 == FREE_PAGE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Page {} is already free", page_id)));
+        }
+
+        let mut page = [0u8; PAGE_SIZE];
+        page[0..4].copy_from_slice(&FREE_PAGE_MAGIC.to_le_bytes());
+        page[4..8].copy_from_slice(&header.free_list_head.to_le_bytes());
+        self.write_raw(page_id, &page)?;
+        header.free_list_head = page_id;
+        header.free_page_count += 1;
+        self.write_raw(DISK_HEADER_PAGE_ID, &header.to_page())
+    }
+
+    fn decode_free_page(page: &[u8]) -> (u32, PageId) {
+        let magic = u32::from_le_bytes(page[0..4].try_into().unwrap());
+        let next_free = i32::from_le_bytes(page[4..8].try_into().unwrap());
+        (magic, next_free)
+    }
+
+    /// Snapshot of the allocation state stored in the header page.
+    pub fn header(&self) -> DiskHeader {
+        *self.header.lock().unwrap()
+    }
+
+    /// Grows the file so that it can hold at least `pages` pages.
+    /// The file is never shrunk.
+    pub fn increase_disk_space(&self, pages: usize) -> io::Result<()> {
+        let _guard = self.header.lock().unwrap();
+        self.grow_to(pages)
+    }
+
+    // Caller must hold the header latch so a smaller resize never truncates a larger one
+    fn grow_to(&self, pages: usize) -> io::Result<()> {
+        let required = pages as u64 * PAGE_SIZE as u64;
+        if self.file_size()? < required {
+            self.file.set_len(required)?;
+        }
+        Ok(())
+    }
+
+    /// Number of whole or partial pages currently backed by the file.
+    pub fn num_pages(&self) -> io::Result<usize> {
+        Ok(self.file_size()?.div_ceil(PAGE_SIZE as u64) as usize)
+    }
+
+    pub fn file_size(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Flushes written pages to stable storage.
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
+    /// Converts a file written in the old append-only `(page_id, page bytes)`
+    /// log format into the fixed-offset layout, in place.
+    /// The last record for each page wins. Page 0 is now the disk header, so every
+    /// legacy page id is shifted up by `FIRST_DATA_PAGE_ID`.
+    /// Returns the legacy id to new id mapping of the recovered pages.
+    pub fn migrate_append_log(db_file: &str) -> io::Result<HashMap<PageId, PageId>> {
+        let mut buffer = Vec::new();
+        File::open(db_file)?.read_to_end(&mut buffer)?;
+        if buffer.len() % APPEND_LOG_ENTRY_SIZE != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "File is not an append-log database"));
+        }
+
+        let mut latest: HashMap<PageId, usize> = HashMap::new();
+        for pos in (0..buffer.len()).step_by(APPEND_LOG_ENTRY_SIZE) {
+            let pid = i32::from_le_bytes(buffer[pos..pos + 4].try_into().unwrap());
+            if pid < 0 || pid.checked_add(FIRST_DATA_PAGE_ID).is_none() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Page id {} in append log cannot be migrated", pid)));
+            }
+            latest.insert(pid, pos + 4);
+        }
+        let mapping: HashMap<PageId, PageId> = latest.keys().map(|&pid| (pid, pid + FIRST_DATA_PAGE_ID)).collect();
+
+        // Write the compacted file next to the original and swap it in atomically.
+        let tmp_file = format!("{}.migrate", db_file);
+        let _ = fs::remove_file(&tmp_file);
+        let migrated = DiskManager::new(&tmp_file)?;
+        let num_pages = mapping.values().max().map_or(0, |&max| max as usize + 1);
+        migrated.increase_disk_space(num_pages)?;
+        for (&pid, &pos) in latest.iter() {
+            migrated.write_page(mapping[&pid], &buffer[pos..pos + PAGE_SIZE])?;
+        }
+        {
+            let mut header = migrated.header.lock().unwrap();
+            header.next_page_id = header.next_page_id.max(num_pages as PageId);
+            migrated.write_raw(DISK_HEADER_PAGE_ID, &header.to_page())?;
+        }
+        migrated.sync()?;
+        drop(migrated);
+        fs::rename(&tmp_file, db_file)?;
+        Ok(mapping)
+    }
+}
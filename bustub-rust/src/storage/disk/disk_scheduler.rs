@@ -1,6 +1,7 @@
 use crate::include::common::config::PageId;
 use crate::include::storage::disk::disk_scheduler::{ DiskRequest, DiskSchedulerTrait};
 use crate::storage::disk::disk_manager::DiskManager;
+use std::io;
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::thread;
 use std::sync::Arc;
@@ -37,7 +38,7 @@ impl DiskSchedulerTrait for DiskScheduler {
     }
 
     fn start_worker_thread(&mut self) {
-        if self.background_threads.len() == 0 {
+        if self.background_threads.is_empty() {
             let max_num_threads = 10;
             // start only if the thread is not running
             let dm = Arc::new(self.disk_manager.clone()); //Arc for sharing across multiple threads
@@ -61,14 +62,15 @@ impl DiskSchedulerTrait for DiskScheduler {
                     let rx_new: std::sync::MutexGuard<'_, Receiver<Option<DiskRequest>>> = clone.lock().unwrap();
                     //
                     while let Ok(Some(req)) = rx_new.recv() {
-                        if req.is_write {
+                        let result = if req.is_write {
                             let data = req.data.lock().unwrap();
-                            dm_clone.write_page(req.page_id, &data);
+                            dm_clone.write_page(req.page_id, &data)
                         } else {
                             let mut data = req.data.lock().unwrap();
-                            dm_clone.read_page(req.page_id, &mut data);
-                        }
-                        req.is_done.send(true).unwrap();
+                            dm_clone.read_page(req.page_id, &mut data)
+                        };
+                        // The issuer may have stopped waiting, that's not the worker's problem
+                        let _ = req.is_done.send(result);
                     }
                 });
                 self.background_threads.push(handle)
@@ -82,12 +84,20 @@ impl DiskSchedulerTrait for DiskScheduler {
         }
     }
 
-    fn deallocate_page(&self) {
-        // In this implementation, we don't actually deallocate pages
-        // as we're using a simple append-only file structure
-        // In a real implementation, you might want to:
-        // 1. Mark the page as deleted in a free space map
-        // 2. Add the page to a free list
-        // 3. Or implement actual file space reclamation
+    fn allocate_page(&self) -> io::Result<PageId> {
+        self.disk_manager.allocate_page()
+    }
+
+    fn deallocate_page(&self, page_id: PageId) -> io::Result<()> {
+        // The page goes onto the free list in the db file, so it is reused after a restart too
+        self.disk_manager.deallocate_page(page_id)
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        self.disk_manager.sync()
+    }
+
+    fn increase_disk_space(&self, pages: usize) -> io::Result<()> {
+        self.disk_manager.increase_disk_space(pages)
     }
 }
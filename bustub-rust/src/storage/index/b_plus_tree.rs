@@ -1,103 +1,256 @@
-use std::slice::Windows;
-use std::collections::HashMap;
-
-use crate::{buffer::bufferpool_manager::BufferPoolManager, include::{buffer::bufferpool_manager::BufferPoolManagerImpl, common::config::{PageId, INVALID_PAGE_ID}, storage::{index::b_plus_tree::{BplusTree, BplusTreeImpl, IndexIterator}, page::{b_plus_tree_internal_page::KeyType, b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl}, b_plus_tree_internal_page::{BplusTreeInternalPageImpl}, b_plus_tree_page::BplusTreePageImpl, page_guard::ReadPageGuardImpl}}}, storage::page::page_guard::WritePageGuard};
-use crate::include::common::config::{AccessType, IndexPageType, ValueType};
-use crate::storage::page::b_plus_tree_page::{BPlusTreeHeaderPage};
-use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageTrait};
-use crate::include::storage::page::page_guard::{PageguardImpl, WritePageGuardImpl};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::io;
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::{buffer::bufferpool_manager::BufferPoolManager, include::{buffer::bufferpool_manager::BufferPoolManagerImpl, common::config::{PageId, INVALID_PAGE_ID}, storage::{index::b_plus_tree::{BplusTree, BplusTreeImpl, DuplicateKey, IndexMode, IntegrityViolation, KeyCompression, TreeStats}, page::{b_plus_tree_internal_page::KeyType, b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl}, b_plus_tree_internal_page::{BplusTreeInternalPageImpl}, b_plus_tree_page::BplusTreePageImpl, page_guard::{PageguardImpl, ReadPageGuardImpl, WritePageGuardImpl}}}}, storage::page::page_guard::{ReadPageGuard, WritePageGuard}};
+use crate::include::common::config::{AccessType, ValueType, PAGE_SIZE};
+use crate::storage::page::b_plus_tree_page::{filler_key, BPlusTreeHeaderPage};
+use crate::storage::page::index_page::decode_page;
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageTrait, BPLUS_TREE_PAGE_HEADER_SIZE};
 use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator, OrdComparator};
+use crate::include::storage::index::index_iterator::IndexIterator;
 
 use crate::include::storage::page::b_plus_tree_internal_page::BplusTreeInternalPage;
 
-impl<'a> BplusTree<'a> {
+// Slotted pages, which hold variable-length keys, split and merge by bytes rather
+// than by count. Their min size is a quarter of the page.
+const SLOTTED_MIN_USED: usize = BPLUS_TREE_PAGE_HEADER_SIZE + (PAGE_SIZE - BPLUS_TREE_PAGE_HEADER_SIZE) / 4;
+
+impl std::fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Duplicate key, the index already maps it to rid {}", self.existing)
+    }
+}
+
+impl std::error::Error for DuplicateKey {}
+
+impl std::fmt::Display for IntegrityViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Corrupted { page_id, reason } => write!(f, "Page {} is corrupted: {}", page_id, reason),
+            Self::PageIdMismatch { page_id, stored } => write!(f, "Page {} stores page id {}", page_id, stored),
+            Self::ReachedTwice { page_id } => write!(f, "Page {} is reached more than once", page_id),
+            Self::KeysOutOfOrder { page_id, index } => write!(f, "Page {} has entry {} out of order", page_id, index),
+            Self::KeyOutOfRange { page_id, index } => write!(f, "Page {} has entry {} outside its separators", page_id, index),
+            Self::Underflow { page_id, size, min_size } => write!(f, "Page {} has size {} under its min size {}", page_id, size, min_size),
+            Self::ByteUnderflow { page_id, used_bytes, min_used } => {
+                write!(f, "Page {} is under its min size and uses {} bytes, under its min {}", page_id, used_bytes, min_used)
+            }
+            Self::Overflow { page_id, size, max_size } => write!(f, "Page {} has size {} over its max size {}", page_id, size, max_size),
+            Self::UnevenLeafDepth { page_id, depth, expected } => write!(f, "Leaf {} is at depth {}, expected {}", page_id, depth, expected),
+            Self::BrokenLeafChain { page_id, next_page_id, expected } => {
+                write!(f, "Leaf {} links to {}, expected {}", page_id, next_page_id, expected)
+            }
+        }
+    }
+}
+
+impl BplusTree {
     pub fn new(
         index_name: String, 
-        bpm: &'a BufferPoolManager, 
+        bpm: &BufferPoolManager, 
         leaf_max_size: i32, 
         internal_max_size: i32, 
         header_page_id: PageId
     ) -> Self {
+        Self::with_comparator(index_name, bpm, OrdComparator, leaf_max_size, internal_max_size, header_page_id)
+    }
+}
+
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // A unique index ordered by the comparator
+    pub fn with_comparator(
+        index_name: String,
+        bpm: &BufferPoolManager,
+        comparator: C,
+        leaf_max_size: i32,
+        internal_max_size: i32,
+        header_page_id: PageId
+    ) -> Self {
+        Self::with_mode(index_name, bpm, comparator, IndexMode::Unique, leaf_max_size, internal_max_size, header_page_id)
+    }
+
+    // The max sizes have to fit the slots a page has for keys of this width.
+    // An invalid header page id allocates the header of a new, empty tree.
+    pub fn with_mode(
+        index_name: String,
+        bpm: &BufferPoolManager,
+        comparator: C,
+        mode: IndexMode,
+        leaf_max_size: i32,
+        internal_max_size: i32,
+        header_page_id: PageId
+    ) -> Self {
+        assert!(leaf_max_size as usize <= BplusTreeLeafPage::<K>::SLOT_CNT,
+            "Leaf max size {} exceeds the {} slots of a leaf with {} byte keys", leaf_max_size, BplusTreeLeafPage::<K>::SLOT_CNT, K::SIZE);
+        // separators of a non-unique index take up room for their rid
+        let internal_slot_cnt = match mode {
+            IndexMode::Unique => BplusTreeInternalPage::<K>::SLOT_CNT,
+            IndexMode::NonUnique => BplusTreeInternalPage::<K>::NON_UNIQUE_SLOT_CNT,
+        };
+        // a full internal page has one more child than keys
+        assert!((internal_max_size as usize) < internal_slot_cnt,
+            "Internal max size {} needs more than the {} child slots of an internal page with {} byte keys", internal_max_size, internal_slot_cnt, K::SIZE);
+        let header_page_id = if header_page_id == INVALID_PAGE_ID {
+            // Header Page will just store some metadata of the btree
+            let new_page_id = bpm.new_page().unwrap_or_else(|e| panic!("Failed to allocate the header page of {}: {}", index_name, e));
+            // the new header starts out with an invalid root page id
+            BPlusTreeHeaderPage::new(new_page_id).encode(bpm.write_page(new_page_id, AccessType::Index).as_mut());
+            new_page_id
+        } else {
+            header_page_id
+        };
         BplusTree {
             index_name,
-            bpm,
+            bpm: bpm.clone(),
             log: Vec::new(),
             leaf_max_size,
             internal_max_size,
             header_page_id,
-            parent_map: HashMap::new(),
+            comparator,
+            mode,
+            compression: KeyCompression::Off,
+            key_type: PhantomData,
         }
-        //BPlusTreeHeaderPage::new();
-        
-        
+    }
+
+    // Compresses the keys of pages the tree writes from now on. Like the mode it
+    // isn't stored in the header, reopen a tree with the compression it was built with.
+    pub fn with_compression(mut self, compression: KeyCompression) -> Self {
+        self.compression = compression;
+        self
     }
 }
 
-impl<'a> BplusTreeImpl for BplusTree<'a> {
+impl<K: IndexKey, C: KeyComparator<K>> BplusTreeImpl<K> for BplusTree<K, C> {
 
-    fn is_empty(&mut self) -> bool {
-        let header = self.acquire_header_guard();
-        header.is_empty()                
+    fn is_empty(&self) -> bool {
+        self.get_root_page_id() == INVALID_PAGE_ID
     }
 
-    fn insert(&mut self, key:KeyType, value:ValueType) -> bool {        
-        // check if the tree is empty
-        let mut header = self.acquire_header_guard();
-        if header.is_empty() {
-            // create a new page_id in BPM
-            let new_page_id = self.bpm.new_page();
-            header.set_root_page_id(new_page_id);
-            // Create root as a leaf page 
-            let mut leaf = LeafPageGuard::new(self.bpm.write_page(new_page_id, AccessType::Index));
-            leaf.initialize(new_page_id, self.leaf_max_size);
-        }
-        // get the the leaf page
-        let mut leaf_page_guard = self.find_leaf(key);
-        let mut leaf_page = leaf_page_guard.as_mut();
-        // check if it is safe to insert 
-        if self.is_safe_to_insert(leaf_page) {
-            // get the right index position to insert. 
-            let index_position = leaf_page.find_insert_position(key);
+    fn insert(&self, key:K, value:ValueType) -> Result<(), DuplicateKey> {        
+        let rid = match value {
+            ValueType::Rid(rid) => rid,
+            _ => panic!("Invalid value type for leaf page"),
+        };
+        // Optimistic pass: most inserts fit the leaf and never touch its ancestors
+        if let Some((mut leaf_page_guard, _)) = self.find_leaf_write(key, Some(rid)) {
+            let index_position = self.insert_position(leaf_page_guard.as_ref(), key, rid)?;
+            if self.leaf_has_room(leaf_page_guard.as_ref(), key) {
+                leaf_page_guard.as_mut().insert(index_position, key, value);
+                return Ok(());
+            }
+        }
+        // The leaf may split, latch every page the split can reach
+        let mut path = self.find_write_path(key, Some(rid), WriteOp::Insert).expect("An insert always finds a root");
+        // another thread may have inserted the key or split the leaf meanwhile
+        let index_position = self.insert_position(path.leaf.as_ref(), key, rid)?;
+        let leaf_page = path.leaf.as_mut();
+        // check if it is safe to insert
+        if self.leaf_has_room(leaf_page, key) {
             leaf_page.insert(index_position, key , value );
-            return true 
+            return Ok(());
+        }
+        // split the leaf so both halves keep their min size with the new entry,
+        // the new leaf takes the larger half
+        let left_size = self.leaf_split_point(leaf_page, index_position, key);
+        let mut new_leaf_page_guard;
+        if index_position < left_size {
+            new_leaf_page_guard = self.split_leaf(leaf_page, left_size - 1);
+            leaf_page.insert(index_position, key, value);
         } else {
-            // split the leaf and insert 
-            let (mut new_leaf_page_guard, promoted_key) = self.split_leaf(leaf_page);
-            // cast
-            let mut new_leaf_page = new_leaf_page_guard.as_mut();
-            if key < promoted_key {
-                // insert into old leaf
-                let index_position = leaf_page.find_insert_position(key);
-                leaf_page.insert(index_position, key , value );
-            } else {
-                let index_position = new_leaf_page.find_insert_position(key);
-                new_leaf_page.insert(index_position, key , value );
-                // check if the parent require any adjustment. 
-                self.insert_into_parent(leaf_page.base_page.page_id, promoted_key, new_leaf_page.base_page.page_id)
-            }
-            return true
+            new_leaf_page_guard = self.split_leaf(leaf_page, left_size);
+            new_leaf_page_guard.as_mut().insert(index_position - left_size, key, value);
+        }
+        // The first entry of the new leaf is promoted as the separator
+        let new_leaf_page = new_leaf_page_guard.as_ref();
+        let last = leaf_page.get_size() as usize - 1;
+        let promoted = self.leaf_separator((leaf_page.key_array[last], leaf_page.rid_array[last]), (new_leaf_page.key_array[0], new_leaf_page.rid_array[0]));
+        // the new leaf has to be linked into the parent whichever half got the key
+        let (old_page_id, new_page_id) = (leaf_page.base_page.page_id, new_leaf_page.base_page.page_id);
+        drop(new_leaf_page_guard);
+        self.insert_into_parent(&mut path.header, &mut path.parents, old_page_id, promoted, new_page_id);
+        Ok(())
+    }
+
+    fn remove(&self, key: K, rid: Rid) {
+        // Optimistic pass: done if the leaf doesn't have the entry or keeps enough of them
+        let Some((mut leaf_guard, is_root)) = self.find_leaf_write(key, Some(rid)) else {
+            return;
+        };
+        let Some(index) = self.remove_position(leaf_guard.as_ref(), key, rid) else {
+            return;
+        };
+        if self.is_safe_to_remove(leaf_guard.as_ref(), is_root) {
+            leaf_guard.as_mut().remove_at(index);
+            return;
+        }
+        drop(leaf_guard);
+
+        // The leaf may underflow, latch every page a merge can reach
+        let Some(mut path) = self.find_write_path(key, Some(rid), WriteOp::Remove) else {
+            return;
+        };
+        let leaf_page_id = path.leaf.guard.get_page_id();
+        let is_root = leaf_page_id == path.root_page_id;
+        let Some(index) = self.remove_position(path.leaf.as_ref(), key, rid) else {
+            return;
+        };
+        let leaf = path.leaf.as_mut();
+        leaf.remove_at(index);
+        if !self.is_underflowing(leaf, is_root) {
+            return;
+        }
+        if is_root {
+            // The last key is gone, the tree is empty again
+            let WritePath { header, leaf, .. } = path;
+            drop(leaf);
+            header.expect("An empty root leaf keeps the header latched").set_root_page_id(INVALID_PAGE_ID);
+            self.delete_tree_page(leaf_page_id);
+            return;
         }
-        false
+        self.rebalance(path);
+    }
+
+    fn get_value(&self, key: K) -> Vec<Rid> {
+        // equal keys of a non-unique index may span several leaves
+        self.range(key..=key).map(|(_, rid)| rid).collect()
     }
 
-    fn remove(&mut self, _key: KeyType) {
-        // TODO: Implement removal
+    fn get_root_page_id(&self) -> PageId {
+        let header_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        decode_page(BPlusTreeHeaderPage::decode(header_guard.as_ref()), self.header_page_id).root_page_id
     }
 
-    fn get_value(&self, _key: KeyType) {
-         // TODO: Implement value retrieval
+    fn begin(&self) -> IndexIterator<'_, K> {
+        self.range(..)
     }
 
-    fn get_root_page_id(&mut self) -> PageId {
-        let header = self.acquire_header_guard();
-        header.root_page_id()
+    fn begin_at(&self, key: K) -> IndexIterator<'_, K> {
+        self.range(key..)
     }
 
-    fn begin(&self) {
-         // TODO: Implement iterator
+    fn range<R: RangeBounds<K>>(&self, range: R) -> IndexIterator<'_, K> {
+        let (lower, upper) = (range.start_bound().cloned(), range.end_bound().cloned());
+        let leaf_guard = match lower {
+            Bound::Included(key) | Bound::Excluded(key) => self.find_leaf_read(key),
+            Bound::Unbounded => self.find_leftmost_leaf_read(),
+        };
+        IndexIterator::new(&self.bpm, &self.comparator, leaf_guard, lower, upper)
+    }
+
+    fn end(&self) -> IndexIterator<'_, K> {
+        IndexIterator::new(&self.bpm, &self.comparator, None, Bound::Unbounded, Bound::Unbounded)
     }
 
     fn is_safe_to_insert(&self, page: &dyn BplusTreePageTrait) -> bool {
+        // a slotted page also needs the bytes for the largest entry it can get
+        if K::VARIABLE_LENGTH && page.used_bytes() + page.max_entry_bytes() > PAGE_SIZE {
+            return false;
+        }
         if page.is_leaf() {
             return page.get_size() < self.leaf_max_size
         }
@@ -107,219 +260,924 @@ impl<'a> BplusTreeImpl for BplusTree<'a> {
 
 }
 
-impl<'a> BplusTree<'a> {
-    // 
-    pub fn acquire_header_guard(&mut self) -> HeaderPageGuard {
-        // if there is an invalid header page id, then allocate a new header page
-        if self.header_page_id == INVALID_PAGE_ID {
-            // Now allocate a new header page
-            // Header Page will just store some metadata of the btree
-            let new_page_id = self.bpm.new_page();
-            // assign the new header pageid 
-            self.header_page_id = new_page_id;
-
-            // Initialize the Header page now, as until now just the headerpage id is created
-            let mut guard = self.bpm.write_page(new_page_id, AccessType::Index);
-            let mut header = BPlusTreeHeaderPage::new();
-            // assign the root page id as invalid page id
-            header.root_page_id = INVALID_PAGE_ID; 
-
-            // Convert the guard's mutable refernce to a raw pointer, cast it
-            // and write the header data directly into that memeory location.
-            unsafe {
-                let ptr = guard.as_mut().as_mut_ptr() as *mut BPlusTreeHeaderPage;
-                std::ptr::write(ptr, header);
-            } 
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // Builds the tree bottom-up from entries in any order, the index has to be empty.
+    // Pages are filled to fill_factor of their max size, but no page is left under its
+    // min size where the number of entries allows it. Slotted pages are filled to
+    // fill_factor of their bytes. Leaves are linked left to right.
+    pub fn bulk_load<I: IntoIterator<Item = (K, Rid)>>(&self, entries: I, fill_factor: f64) -> io::Result<()> {
+        if !(fill_factor > 0.0 && fill_factor <= 1.0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Fill factor {} is not in (0, 1]", fill_factor)));
+        }
+        let mut entries: Vec<(K, Rid)> = entries.into_iter().collect();
+        if entries.windows(2).any(|pair| self.compare_entries(pair[0], pair[1]) == Ordering::Greater) {
+            entries.sort_by(|lhs, rhs| self.compare_entries(*lhs, *rhs));
+        }
+        if let Some(pair) = entries.windows(2).find(|pair| self.compare_entries(pair[0], pair[1]) == Ordering::Equal) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, DuplicateKey { existing: pair[0].1 }));
+        }
+        // The header stays latched until the new root is in place
+        let mut header = self.acquire_header_guard();
+        if !header.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Bulk loading needs an empty index, {} has root page {}", self.index_name, header.root_page_id()),
+            ));
+        }
+        if entries.is_empty() {
+            return Ok(());
         }
+
+        // The pages of the level built last, each with the separator in front of it
+        let mut level: Vec<((K, Rid), PageId)> = Vec::new();
+        let sizes = if K::VARIABLE_LENGTH {
+            let largest_entry = entries.iter().map(|(key, _)| BplusTreeLeafPage::slotted_entry_size(key)).max().unwrap();
+            slotted_page_sizes(entries.len(), self.leaf_max_size as usize, largest_entry, fill_factor, |range| {
+                BplusTreeLeafPage::slotted_sizes(entries[range].iter().map(|(key, _)| key), self.compresses_keys())
+            })
+        } else {
+            let leaf_min_size = (self.leaf_max_size as usize).div_ceil(2);
+            let target = (self.leaf_max_size as f64 * fill_factor).ceil() as usize;
+            page_sizes(entries.len(), leaf_min_size, self.leaf_max_size as usize, target)
+        };
+        // every page written so far is freed again if an allocation fails
+        let mut allocated = Vec::new();
+        let page_ids = self.allocate_pages(sizes.len(), &mut allocated)?;
+        let (mut rest, mut consumed) = (&entries[..], 0);
+        for (index, (&size, &page_id)) in sizes.iter().zip(page_ids.iter()).enumerate() {
+            let (chunk, tail) = rest.split_at(size);
+            rest = tail;
+            let mut leaf_guard = self.format_leaf(page_id);
+            let leaf = leaf_guard.as_mut();
+            for (slot, &(key, rid)) in chunk.iter().enumerate() {
+                leaf.key_array[slot] = key;
+                leaf.rid_array[slot] = rid;
+            }
+            leaf.base_page.set_size(size as i32);
+            leaf.next_page_id = page_ids.get(index + 1).copied().unwrap_or(INVALID_PAGE_ID);
+            // the first leaf has no separator in front of it
+            let separator = if index == 0 { chunk[0] } else { self.leaf_separator(entries[consumed - 1], chunk[0]) };
+            consumed += size;
+            level.push((separator, page_id));
+        }
+
+        // Internal pages count their children, one more than their keys
+        let internal_min_size = self.internal_max_size as usize / 2 + 1;
+        let target = (self.internal_max_size as f64 * fill_factor).ceil() as usize + 1;
+        let separator_rids = self.mode == IndexMode::NonUnique;
+        while level.len() > 1 {
+            let sizes = if K::VARIABLE_LENGTH {
+                let entry_bytes: Vec<usize> = level.iter().map(|((key, _), _)| BplusTreeInternalPage::slotted_entry_size(key, separator_rids)).collect();
+                let largest_entry = entry_bytes.iter().copied().max().unwrap();
+                slotted_page_sizes(level.len(), self.internal_max_size as usize + 1, largest_entry, fill_factor, |range| {
+                    // the first child's key goes up a level, the page doesn't store it
+                    let mut used = vec![BplusTreeInternalPage::<K>::SLOTTED_HEADER_SIZE];
+                    for (position, &bytes) in entry_bytes[range].iter().enumerate() {
+                        used.push(used[position] + if position == 0 { 0 } else { bytes });
+                    }
+                    used
+                })
+            } else {
+                page_sizes(level.len(), internal_min_size, self.internal_max_size as usize + 1, target)
+            };
+            let page_ids = self.allocate_pages(sizes.len(), &mut allocated)?;
+            let mut rest = &level[..];
+            let mut parents = Vec::with_capacity(sizes.len());
+            for (size, page_id) in sizes.into_iter().zip(page_ids) {
+                let (children, tail) = rest.split_at(size);
+                rest = tail;
+                let mut internal_guard = self.format_internal(page_id);
+                let internal = internal_guard.as_mut();
+                internal.page_id_array[0] = children[0].1;
+                // every child but the first is preceded by its first entry
+                for (slot, &((key, rid), page_id)) in children.iter().enumerate().skip(1) {
+                    internal.key_array[slot - 1] = key;
+                    internal.rid_array[slot - 1] = rid;
+                    internal.page_id_array[slot] = page_id;
+                }
+                internal.base_page.set_size(size as i32 - 1);
+                parents.push((children[0].0, page_id));
+            }
+            level = parents;
+        }
+        header.set_root_page_id(level[0].1);
+        Ok(())
+    }
+
+    // Allocates count pages for bulk_load and adds them to allocated. On failure
+    // every page in allocated is deleted, none of them is latched by then.
+    fn allocate_pages(&self, count: usize, allocated: &mut Vec<PageId>) -> io::Result<Vec<PageId>> {
+        let mut page_ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.bpm.new_page() {
+                Ok(page_id) => page_ids.push(page_id),
+                Err(e) => {
+                    for &page_id in allocated.iter().chain(page_ids.iter()) {
+                        self.bpm.delete_page(page_id);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        allocated.extend_from_slice(&page_ids);
+        Ok(page_ids)
+    }
+}
+
+// What check_integrity collects while it walks the tree
+struct IntegrityWalk {
+    visited: HashSet<PageId>,
+    // The depth of the first leaf, every other leaf has to be at it
+    leaf_depth: Option<usize>,
+    // Leaves in key order, each with the next page id it stores
+    leaves: Vec<(PageId, PageId)>,
+    violations: Vec<IntegrityViolation>,
+}
+
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // Walks the whole tree and reports every broken invariant it finds, in the order
+    // found. Pages are read latched one at a time while the header is read latched,
+    // writers already past the header still run, so check a tree nobody writes to.
+    pub fn check_integrity(&self) -> Result<(), Vec<IntegrityViolation>> {
+        let header_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        let root_page_id = match BPlusTreeHeaderPage::decode(header_guard.as_ref()) {
+            Ok(header) => header.root_page_id,
+            Err(e) => return Err(vec![IntegrityViolation::Corrupted { page_id: self.header_page_id, reason: e.to_string() }]),
+        };
+        if root_page_id == INVALID_PAGE_ID {
+            return Ok(());
+        }
+        let mut walk = IntegrityWalk { visited: HashSet::new(), leaf_depth: None, leaves: Vec::new(), violations: Vec::new() };
+        self.check_page(&mut walk, root_page_id, 0, None, None);
+        drop(header_guard);
+
+        for (position, &(page_id, next_page_id)) in walk.leaves.iter().enumerate() {
+            let expected = walk.leaves.get(position + 1).map_or(INVALID_PAGE_ID, |&(next, _)| next);
+            if next_page_id != expected {
+                walk.violations.push(IntegrityViolation::BrokenLeafChain { page_id, next_page_id, expected });
+            }
+        }
+        if walk.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(walk.violations)
+        }
+    }
+
+    // Checks the page and everything below it. Its entries have to be at least lower
+    // and less than upper, the separators around it in the parents; None is unbounded.
+    fn check_page(&self, walk: &mut IntegrityWalk, page_id: PageId, depth: usize, lower: Option<(K, Rid)>, upper: Option<(K, Rid)>) {
+        if !walk.visited.insert(page_id) {
+            walk.violations.push(IntegrityViolation::ReachedTwice { page_id });
+            return;
+        }
+        let corrupted = |reason: String| IntegrityViolation::Corrupted { page_id, reason };
+        let Some(guard) = self.bpm.checked_read_page(page_id, AccessType::Index) else {
+            walk.violations.push(corrupted(String::from("the buffer pool can't read it")));
+            return;
+        };
+        let header = match BplusTreePage::peek_header(guard.as_ref()) {
+            Ok((header, _)) => header,
+            Err(e) => return walk.violations.push(corrupted(e.to_string())),
+        };
+        if header.page_id != page_id {
+            walk.violations.push(IntegrityViolation::PageIdMismatch { page_id, stored: header.page_id });
+        }
+        let (size, max_size) = (header.get_size(), header.get_max_size());
+        if size > max_size {
+            walk.violations.push(IntegrityViolation::Overflow { page_id, size, max_size });
+        }
+        // The root only has to hold something. Slotted pages fill by bytes, their
+        // min bytes are checked once the page is decoded.
+        let min_size = if depth == 0 { 1 } else { header.get_min_size() };
+        if size < min_size && (depth == 0 || !K::VARIABLE_LENGTH) {
+            walk.violations.push(IntegrityViolation::Underflow { page_id, size, min_size });
+        }
+        let check_min_bytes = |walk: &mut IntegrityWalk, page: &dyn BplusTreePageTrait| {
+            if K::VARIABLE_LENGTH && depth > 0 && self.is_underflowing(page, false) {
+                walk.violations.push(IntegrityViolation::ByteUnderflow { page_id, used_bytes: page.used_bytes(), min_used: SLOTTED_MIN_USED });
+            }
+        };
+
+        if header.is_leaf_page() {
+            let leaf = match BplusTreeLeafPage::<K>::decode(guard.as_ref()) {
+                Ok(leaf) => leaf,
+                Err(e) => return walk.violations.push(corrupted(e.to_string())),
+            };
+            drop(guard);
+            check_min_bytes(walk, &leaf);
+            let entries: Vec<(K, Rid)> = (0..size as usize).map(|index| (leaf.key_array[index], leaf.rid_array[index])).collect();
+            self.check_entries(walk, page_id, &entries, lower, upper);
+            match walk.leaf_depth {
+                None => walk.leaf_depth = Some(depth),
+                Some(expected) if expected != depth => {
+                    walk.violations.push(IntegrityViolation::UnevenLeafDepth { page_id, depth, expected });
+                }
+                Some(_) => {}
+            }
+            walk.leaves.push((page_id, leaf.next_page_id));
+            return;
+        }
+        let internal = match BplusTreeInternalPage::<K>::decode(guard.as_ref()) {
+            Ok(internal) => internal,
+            Err(e) => return walk.violations.push(corrupted(e.to_string())),
+        };
+        drop(guard);
+        check_min_bytes(walk, &internal);
+        let separators: Vec<(K, Rid)> = (0..size as usize).map(|index| (internal.key_array[index], internal.rid_array[index])).collect();
+        self.check_entries(walk, page_id, &separators, lower, upper);
+        // A child covers the entries from the separator before it up to the one after it
+        for child in 0..=separators.len() {
+            let child_lower = if child == 0 { lower } else { Some(separators[child - 1]) };
+            let child_upper = separators.get(child).copied().or(upper);
+            self.check_page(walk, internal.page_id_array[child], depth + 1, child_lower, child_upper);
+        }
+    }
+
+    // Entries have to ascend strictly and stay within [lower, upper)
+    fn check_entries(&self, walk: &mut IntegrityWalk, page_id: PageId, entries: &[(K, Rid)], lower: Option<(K, Rid)>, upper: Option<(K, Rid)>) {
+        for (index, &entry) in entries.iter().enumerate() {
+            if index > 0 && self.compare_entries(entries[index - 1], entry) != Ordering::Less {
+                walk.violations.push(IntegrityViolation::KeysOutOfOrder { page_id, index });
+            }
+            let below = lower.is_some_and(|lower| self.compare_entries(entry, lower) == Ordering::Less);
+            let above = upper.is_some_and(|upper| self.compare_entries(entry, upper) != Ordering::Less);
+            if below || above {
+                walk.violations.push(IntegrityViolation::KeyOutOfRange { page_id, index });
+            }
+        }
+    }
+}
+
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // Counts the pages, entries and key bytes of the tree level by level, what key
+    // compression gains shows in the fan-out. Pages are read latched one at a time,
+    // so gather the stats of a tree nobody writes to.
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        let root_page_id = self.get_root_page_id();
+        let mut level = if root_page_id == INVALID_PAGE_ID { Vec::new() } else { vec![root_page_id] };
+        while !level.is_empty() {
+            stats.height += 1;
+            let mut next_level = Vec::new();
+            for page_id in level {
+                let guard = self.bpm.read_page(page_id, AccessType::Index);
+                let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), page_id);
+                let size = page.get_size() as usize;
+                if page.is_leaf_page() {
+                    let leaf = decode_page(BplusTreeLeafPage::<K>::decode(guard.as_ref()), page_id);
+                    let key_bytes: usize = leaf.key_array[..size].iter().map(IndexKey::encoded_len).sum();
+                    // every key but the one the prefix is counted with leaves it out
+                    let saved = size.saturating_sub(1) * leaf.common_prefix_len();
+                    stats.leaf_pages += 1;
+                    stats.entries += size;
+                    stats.leaf_key_bytes += key_bytes - saved;
+                    stats.prefix_bytes_saved += saved;
+                } else {
+                    let internal = decode_page(BplusTreeInternalPage::<K>::decode(guard.as_ref()), page_id);
+                    stats.internal_pages += 1;
+                    stats.children += size + 1;
+                    stats.separator_key_bytes += internal.key_array[..size].iter().map(IndexKey::encoded_len).sum::<usize>();
+                    next_level.extend_from_slice(&internal.page_id_array[..=size]);
+                }
+            }
+            level = next_level;
+        }
+        stats
+    }
+}
+
+impl TreeStats {
+    // Children per internal page, 0 for a tree without internal pages
+    pub fn fan_out(&self) -> f64 {
+        if self.internal_pages == 0 {
+            return 0.0;
+        }
+        self.children as f64 / self.internal_pages as f64
+    }
+
+    // Entries per leaf, 0 for an empty tree
+    pub fn entries_per_leaf(&self) -> f64 {
+        if self.leaf_pages == 0 {
+            return 0.0;
+        }
+        self.entries as f64 / self.leaf_pages as f64
+    }
+}
+
+// The change a pessimistic descent prepares for, it decides which pages are safe
+#[derive(Clone, Copy, PartialEq)]
+enum WriteOp {
+    Insert,
+    Remove,
+}
+
+// The write latched pages of a pessimistic descent. Pages above the lowest safe
+// page are released on the way down, so `parents` only starts at the root while
+// the header is still held.
+struct WritePath<K: IndexKey> {
+    header: Option<HeaderPageGuard>,
+    // The root while the descent began, it can't change under the latches held
+    root_page_id: PageId,
+    // Internal pages top down, each with the index of the child the descent took
+    parents: Vec<(InternalPageGuard<K>, usize)>,
+    leaf: LeafPageGuard<K>,
+}
+
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // Write latches the header, the root can only change while it is held
+    pub fn acquire_header_guard(&self) -> HeaderPageGuard {
         HeaderPageGuard::new(self.bpm.write_page(self.header_page_id, AccessType::Index))
     }
-    // Just return the leaf page of a specific page id. 
-    // 
-    pub fn find_leaf(&mut self, key:KeyType) -> LeafPageGuard{
-        // get the root page id
-        let mut guard = self.bpm.read_page(self.get_root_page_id(), AccessType::Index);
-        let mut target_leaf_page_ref: Option<&BplusTreePage> = None;
+    // Read latches the root, the header stays latched until the root is. None if the tree has no root.
+    fn read_root_guard(&self) -> Option<ReadPageGuard> {
+        let header_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        let root_page_id = decode_page(BPlusTreeHeaderPage::decode(header_guard.as_ref()), self.header_page_id).root_page_id;
+        if root_page_id == INVALID_PAGE_ID {
+            return None;
+        }
+        Some(self.bpm.read_page(root_page_id, AccessType::Index))
+    }
+
+    // Descends to the leaf covering the key with read latches only, each child is
+    // latched before its parent is released. None if the tree has no root.
+    pub fn find_leaf_read(&self, key: K) -> Option<ReadPageGuard> {
+        let mut guard = self.read_root_guard()?;
+        loop {
+            let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), guard.get_page_id());
+            if page.is_leaf_page() {
+                return Some(guard);
+            }
+            let internal_page = decode_page(BplusTreeInternalPage::<K>::decode(guard.as_ref()), guard.get_page_id());
+            // the first leaf that may hold the key
+            let index = internal_page.lookup_entry_index(key, None, &self.comparator);
+            guard = self.bpm.read_page(internal_page.page_id_value_at(index), AccessType::Index);
+        }
+    }
+
+    // Descends to the first leaf of the chain, latched like find_leaf_read
+    fn find_leftmost_leaf_read(&self) -> Option<ReadPageGuard> {
+        let mut guard = self.read_root_guard()?;
         loop {
-            let data = guard.as_ref();
-            // cast the guard as genric Tree page
-            let page = unsafe { &*(data.as_ptr() as *const BplusTreePage) };
+            let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), guard.get_page_id());
+            if page.is_leaf_page() {
+                return Some(guard);
+            }
+            let internal_page = decode_page(BplusTreeInternalPage::<K>::decode(guard.as_ref()), guard.get_page_id());
+            guard = self.bpm.read_page(internal_page.page_id_value_at(0), AccessType::Index);
+        }
+    }
 
+    // Write latches the leaf covering the (key, rid) entry after descending with read latches.
+    // The parent (the header for a root leaf) stays read latched until the leaf is
+    // write latched, so the leaf can't be split or merged away in between.
+    // Also tells whether the leaf is the root. None if the tree has no root.
+    fn find_leaf_write(&self, key: K, rid: Option<Rid>) -> Option<(LeafPageGuard<K>, bool)> {
+        // Only held for its latch
+        let mut _parent_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        let mut page_id = decode_page(BPlusTreeHeaderPage::decode(_parent_guard.as_ref()), self.header_page_id).root_page_id;
+        if page_id == INVALID_PAGE_ID {
+            return None;
+        }
+        let mut is_root = true;
+        loop {
+            let guard = self.bpm.read_page(page_id, AccessType::Index);
+            let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), page_id);
             if page.is_leaf_page() {
-                target_leaf_page_ref = Some(page);
-                break; // found the leaf page
+                // The read latch has to go before the same page can be latched for writing
+                drop(guard);
+                let leaf_guard = LeafPageGuard::new(self.bpm.write_page(page_id, AccessType::Index));
+                return Some((leaf_guard, is_root));
             }
+            let internal_page = decode_page(BplusTreeInternalPage::<K>::decode(guard.as_ref()), page_id);
+            page_id = internal_page.page_id_value_at(internal_page.lookup_entry_index(key, rid, &self.comparator));
+            _parent_guard = guard;
+            is_root = false;
+        }
+    }
 
-            let internal_page = unsafe { &mut *(data.as_ptr() as *mut BplusTreeInternalPage) };
-            // find the child page ID 
-            let mut index: usize = 0;
-            while index < internal_page.get_size() as usize && key >= internal_page.key_array[index] {
-                index += 1;
+    // Descends to the leaf covering the (key, rid) entry with write latches. Each page is latched
+    // before its ancestors are released, which happens as soon as the page is safe
+    // for `op`: a change below it can't reach further up. An insert into an empty
+    // tree creates the root leaf first, a remove returns None.
+    fn find_write_path(&self, key: K, rid: Option<Rid>, op: WriteOp) -> Option<WritePath<K>> {
+        let mut header = self.acquire_header_guard();
+        if header.is_empty() {
+            if op == WriteOp::Remove {
+                return None;
+            }
+            // create a new page_id in BPM
+            let new_page_id = self.allocate_page();
+            header.set_root_page_id(new_page_id);
+            // Create root as a leaf page 
+            self.format_leaf(new_page_id);
+        }
+        let root_page_id = header.root_page_id();
+        let mut header = Some(header);
+        let mut parents: Vec<(InternalPageGuard<K>, usize)> = Vec::new();
+        let mut page_id = root_page_id;
+        loop {
+            let guard = self.bpm.write_page(page_id, AccessType::Index);
+            let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), page_id);
+            let is_root = page_id == root_page_id;
+            if page.is_leaf_page() {
+                let leaf = LeafPageGuard::new(guard);
+                let is_safe = match op {
+                    WriteOp::Insert => self.leaf_has_room(leaf.as_ref(), key),
+                    WriteOp::Remove => self.is_safe_to_remove(leaf.as_ref(), is_root),
+                };
+                if is_safe {
+                    header = None;
+                    parents.clear();
+                }
+                return Some(WritePath { header, root_page_id, parents, leaf });
+            }
+            let internal_guard = InternalPageGuard::<K>::new(guard);
+            if self.is_safe_for(op, internal_guard.as_ref(), is_root) {
+                header = None;
+                parents.clear();
             }
-            let child_page_id = internal_page.page_id_array[index];
-            guard = self.bpm.read_page(child_page_id, AccessType::Index);
+            let index = internal_guard.as_ref().lookup_entry_index(key, rid, &self.comparator);
+            page_id = internal_guard.as_ref().page_id_value_at(index);
+            parents.push((internal_guard, index as usize));
+        }
+    }
 
+    // Entries order by key, then by rid in a non-unique index
+    fn compare_entries(&self, lhs: (K, Rid), rhs: (K, Rid)) -> Ordering {
+        let ordering = self.comparator.compare(&lhs.0, &rhs.0);
+        match self.mode {
+            IndexMode::Unique => ordering,
+            IndexMode::NonUnique => ordering.then(lhs.1.cmp(&rhs.1)),
         }
-        let target_leaf_page = target_leaf_page_ref.unwrap();
-        let leaf_page_id = target_leaf_page.page_id;
-        let write_guard = self.bpm.write_page(leaf_page_id, AccessType::Index);
-        LeafPageGuard::new(write_guard)
-        
     }
 
-    fn is_leaf_page(&self, page_id: PageId) -> bool {
-        let guard = self.bpm.read_page(page_id, AccessType::Index);
-        let page = unsafe {
-            // Cast the raw byte slice to BplusTreePage for page_type() access
-            let data = guard.as_ref();
-            &*(data.as_ptr() as *const BplusTreePage)
+    // Where the (key, rid) entry goes in the leaf, fails if the index already has
+    // the key (unique) or the entry (non-unique)
+    fn insert_position(&self, leaf: &BplusTreeLeafPage<K>, key: K, rid: Rid) -> Result<i32, DuplicateKey> {
+        if self.mode == IndexMode::Unique {
+            if let Some(existing) = leaf.lookup(key, &self.comparator) {
+                return Err(DuplicateKey { existing });
+            }
+        }
+        match leaf.search_entry(key, rid, &self.comparator) {
+            Ok(index) => Err(DuplicateKey { existing: leaf.rid_array[index as usize] }),
+            Err(index) => Ok(index),
+        }
+    }
+
+    // Where the entry remove(key, rid) deletes is in the leaf: the key's entry
+    // whatever its rid (unique) or the (key, rid) entry (non-unique)
+    fn remove_position(&self, leaf: &BplusTreeLeafPage<K>, key: K, rid: Rid) -> Option<i32> {
+        let rid = match self.mode {
+            IndexMode::Unique => leaf.lookup(key, &self.comparator)?,
+            IndexMode::NonUnique => rid,
         };
-        page.page_type == IndexPageType::LEAF_PAGE
+        leaf.search_entry(key, rid, &self.comparator).ok()
+    }
+
+    fn is_safe_for(&self, op: WriteOp, page: &dyn BplusTreePageTrait, is_root: bool) -> bool {
+        match op {
+            WriteOp::Insert => self.is_safe_to_insert(page),
+            WriteOp::Remove => self.is_safe_to_remove(page, is_root),
+        }
+    }
+
+    // A page is safe to remove from when it stays at its min size, the root
+    // only needs to keep a key (a leaf) or two children (an internal page)
+    fn is_safe_to_remove(&self, page: &dyn BplusTreePageTrait, is_root: bool) -> bool {
+        // a borrow below a slotted internal page may lengthen one of its separators,
+        // which splits the page if it has no room for one more entry
+        if K::VARIABLE_LENGTH && !page.is_leaf() && !self.is_safe_to_insert(page) {
+            return false;
+        }
+        if is_root {
+            return page.get_size() > 1;
+        }
+        let above_min_bytes = K::VARIABLE_LENGTH && page.used_bytes() >= SLOTTED_MIN_USED + page.max_entry_bytes();
+        above_min_bytes || page.get_size() > page.min_size()
     }
 
-    fn split_leaf(&self, leaf_page: &mut BplusTreeLeafPage) -> (LeafPageGuard, i64){
+    // A page under its min size, slotted pages have to be under their min bytes as
+    // well. The root only has to hold something.
+    fn is_underflowing(&self, page: &dyn BplusTreePageTrait, is_root: bool) -> bool {
+        if is_root {
+            return page.get_size() < 1;
+        }
+        self.underflows(page.get_size() as usize, page.min_size() as usize, page.used_bytes())
+    }
+
+    // Whether a page other than the root with size entries taking used_bytes is under its
+    // min size. A slotted page may hold fewer entries if they take its min bytes.
+    fn underflows(&self, size: usize, min_size: usize, used_bytes: usize) -> bool {
+        size < min_size && (!K::VARIABLE_LENGTH || used_bytes < SLOTTED_MIN_USED)
+    }
+
+    // Whether the leaf takes the key without a split
+    fn leaf_has_room(&self, leaf: &BplusTreeLeafPage<K>, key: K) -> bool {
+        // a slotted leaf may lose part of its shared prefix to the key
+        leaf.get_size() < self.leaf_max_size && (!K::VARIABLE_LENGTH || leaf.used_bytes_with(&key) <= PAGE_SIZE)
+    }
+
+    // How many of the full leaf's entries and the new one at index_position the old
+    // leaf keeps in a split. Slotted leaves split where the larger half takes the
+    // fewest bytes, which keeps a key sharing little of a compressed leaf's prefix
+    // away from the rest.
+    fn leaf_split_point(&self, leaf: &BplusTreeLeafPage<K>, index_position: i32, key: K) -> i32 {
+        if !K::VARIABLE_LENGTH {
+            // the old leaf keeps exactly its min size, so sorted inserts, which only
+            // ever split the last leaf, leave the leaves behind them half full
+            return (self.leaf_max_size + 1) / 2;
+        }
+        let mut keys = leaf.key_array[..leaf.get_size() as usize].to_vec();
+        keys.insert(index_position as usize, key);
+        let left_bytes = BplusTreeLeafPage::slotted_sizes(&keys, leaf.prefix_compression);
+        let right_bytes = BplusTreeLeafPage::slotted_sizes(keys.iter().rev(), leaf.prefix_compression);
+        // neither half may go over the max size by count
+        let (count, leaf_max_size) = (keys.len(), self.leaf_max_size as usize);
+        let smallest = count.saturating_sub(leaf_max_size).max(1);
+        let largest = leaf_max_size.min(count - 1).max(smallest);
+        // halves that would underflow right away only if no other split is left
+        let min_size = leaf.min_size() as usize;
+        let left_size = (smallest..=largest).min_by_key(|&left_size| {
+            let (left, right) = (left_bytes[left_size], right_bytes[count - left_size]);
+            (self.underflows(left_size, min_size, left) || self.underflows(count - left_size, min_size, right), left.max(right))
+        });
+        left_size.unwrap() as i32
+    }
+
+    // The separator between two neighbouring leaves, the right one's first entry or
+    // with suffix truncation a shorter key between the two
+    fn leaf_separator(&self, left_last: (K, Rid), right_first: (K, Rid)) -> (K, Rid) {
+        if !self.compresses_keys() {
+            return right_first;
+        }
+        let key = K::shortest_separator(&left_last.0, &right_first.0);
+        // the comparator has the last word, keys may not sort by their bytes
+        let is_between = self.comparator.compare(&left_last.0, &key) == Ordering::Less
+            && self.comparator.compare(&key, &right_first.0) != Ordering::Greater;
+        if is_between { (key, right_first.1) } else { right_first }
+    }
+
+    fn compresses_keys(&self) -> bool {
+        K::VARIABLE_LENGTH && self.compression == KeyCompression::PrefixSuffix
+    }
+
+    // Like the page fetches, a failed allocation panics: it can happen halfway
+    // through a split, which has no way back
+    fn allocate_page(&self) -> PageId {
+        self.bpm.new_page().unwrap_or_else(|e| panic!("Failed to allocate a page for {}: {}", self.index_name, e))
+    }
+
+    // Formats the page as an empty leaf, prefix compressed if the tree compresses keys
+    fn format_leaf(&self, page_id: PageId) -> LeafPageGuard<K> {
+        let mut leaf_guard = LeafPageGuard::initialize(self.bpm.write_page(page_id, AccessType::Index), self.leaf_max_size);
+        leaf_guard.as_mut().prefix_compression = self.compresses_keys();
+        leaf_guard
+    }
+
+    // Moves the entries from mid on into a new leaf linked in after the old one
+    fn split_leaf(&self, leaf_page: &mut BplusTreeLeafPage<K>, mid: i32) -> LeafPageGuard<K> {
         // get the new page id from BPm
-        let new_leaf_page_id = self.bpm.new_page();
+        let new_leaf_page_id = self.allocate_page();
         // create a new leaf page and intitalize it
-        let mut leaf_guard = LeafPageGuard::new(self.bpm.write_page(new_leaf_page_id, AccessType::Index));
-        leaf_guard.initialize(new_leaf_page_id, self.leaf_max_size);
+        let mut leaf_guard = self.format_leaf(new_leaf_page_id);
         //cast the new_leaf_page guard as leaf page
-        // let new_leaf_page =  unsafe {
-        //     // Cast the raw byte slice to BplusTreePage for page_type() access
-        //     let data = leaf_guard.as_mut();
-        //     &mut *(data.as_mut_ptr() as *mut BplusTreeLeafPage)
-        // };
         let new_leaf_page = leaf_guard.as_mut();
-        let mid = self.leaf_max_size / 2;  
+        let empty_key = filler_key(0);
         // iterate mid to leaf.size times 
         for index in mid..leaf_page.get_size() {
             new_leaf_page.key_array[(index - mid) as usize] = leaf_page.key_array[index as usize];
             new_leaf_page.rid_array[(index - mid) as usize] = leaf_page.rid_array[index as usize];
-            leaf_page.key_array[index as usize] = 0;
+            leaf_page.key_array[index as usize] = empty_key;
             leaf_page.rid_array[index as usize] = Rid::new(0, 0);
         } 
         new_leaf_page.base_page.set_size(leaf_page.base_page.size_ - mid);
         leaf_page.base_page.set_size(mid);
-        let promoted_key = new_leaf_page.key_array[0];
-        (leaf_guard, promoted_key as i64)
-    }
-
-    // insert the promoted key into parent page
-    fn insert_into_parent(&mut self, old_leaf_page_id: PageId, promoted_key: KeyType, new_leaf_page_id: PageId ) {
-        // updated parent _id 
-        let parent_id = match self.parent_map.get(&old_leaf_page_id) {
-            Some(&id) => id,
-            None => {
-                let new_root_page_id = self.bpm.new_page();
-                let mut internal_guard = InternalPageGuard::new(self.bpm.write_page(new_root_page_id, AccessType::Index));
-                internal_guard.initialize(new_root_page_id, self.internal_max_size);
-                // get the mutable refernce
-                let mut internal_page = internal_guard.as_mut();
-                internal_page.key_array[0] = promoted_key;
-                internal_page.page_id_array[0] = old_leaf_page_id;
-                internal_page.page_id_array[1] = new_leaf_page_id;
-                internal_page.base_page.set_size(1);
-                // set the root page id to new root id
-                // acquire the header page guard
-                let mut header_guard = self.acquire_header_guard();
-                header_guard.set_root_page_id(new_root_page_id);
-                // Update the parent map
-                self.parent_map.insert(old_leaf_page_id, new_root_page_id);
-                self.parent_map.insert(new_leaf_page_id, new_root_page_id);
-                return;
-            }
+        // the new leaf goes right after the old one in the leaf chain
+        new_leaf_page.next_page_id = leaf_page.next_page_id;
+        leaf_page.next_page_id = new_leaf_page_id;
+        leaf_guard
+    }
+
+    // insert the promoted key into the parent page, the last one the path still
+    // holds. Without one the root itself split, and the path holds the header.
+    fn insert_into_parent(&self, header: &mut Option<HeaderPageGuard>, parents: &mut Vec<(InternalPageGuard<K>, usize)>, old_page_id: PageId, promoted: (K, Rid), new_page_id: PageId) {
+        let Some((mut parent_page_guard, _)) = parents.pop() else {
+            let mut internal_guard = self.format_internal(self.allocate_page());
+            let new_root_page_id = internal_guard.guard.get_page_id();
+            // get the mutable refernce
+            let internal_page = internal_guard.as_mut();
+            (internal_page.key_array[0], internal_page.rid_array[0]) = promoted;
+            internal_page.page_id_array[0] = old_page_id;
+            internal_page.page_id_array[1] = new_page_id;
+            internal_page.base_page.set_size(1);
+            drop(internal_guard);
+            // set the root page id to new root id
+            let header_guard = header.as_mut().expect("A splitting root keeps the header latched");
+            header_guard.set_root_page_id(new_root_page_id);
+            return;
         };
-        // let parent_id = self.parent_map.get(&old_leaf_page_id);
-        // if parent_id.is_none(){
-        //     // do some processing
-        //     // no parent exsits, need to create a new page 
-        //     let new_root_page_id = self.bpm.new_page();
-        //     let mut internal_guard = InternalPageGuard::new(self.bpm.write_page(new_root_page_id, AccessType::Index));
-        //     internal_guard.initialize(new_root_page_id, self.internal_max_size);
-        //     // get the mutable refernce
-        //     let mut internal_page = internal_guard.as_mut();
-        //     internal_page.key_array[0] = promoted_key;
-        //     internal_page.page_id_array[0] = old_leaf_page_id;
-        //     internal_page.page_id_array[1] = new_leaf_page_id;
-        //     internal_page.base_page.set_size(1);
-        //     // set the root page id to new root id
-        //     // acquire the header page guard
-        //     let mut header_guard = self.acquire_header_guard();
-        //     header_guard.set_root_page_id(new_root_page_id);
-        //     // Update the parent map
-        //     self.parent_map.insert(old_leaf_page_id, new_root_page_id);
-        //     self.parent_map.insert(new_leaf_page_id, new_root_page_id);
-           
-        // } else {
-
-        // }
-        // get the parent page
-        let mut parent_page_guard = InternalPageGuard::new(self.bpm.write_page(parent_id, AccessType:: Index));
-        let mut parent_page = parent_page_guard.as_mut();
+        let parent_page = parent_page_guard.as_mut();
         // check if it is safe to insert
         if !self.is_safe_to_insert(parent_page){
-            // split the internal page
-            let (new_internal_page_id, promoted_key) = self.split_internal(parent_page);
+            // split the internal page around the new child
+            let (new_internal_page_guard, split_key) = self.split_internal(parent_page, promoted, new_page_id);
+            let new_internal_page_id = new_internal_page_guard.guard.get_page_id();
+            drop(new_internal_page_guard);
             // insert again into parent
-            self.insert_into_parent(parent_page.base_page.page_id, promoted_key, new_internal_page_id);
-            let parent_id = *self.parent_map.get(&old_leaf_page_id).unwrap();
-            parent_page_guard = InternalPageGuard::new(self.bpm.write_page(parent_id, AccessType:: Index));
-            parent_page = parent_page_guard.as_mut(); 
-        }
-        // safe to insert
-        let mut index = parent_page.base_page.get_size();
-        while index > 0 && promoted_key < parent_page.key_array[(index-1) as usize] {
-            parent_page.key_array[index as usize] = parent_page.key_array[(index - 1) as usize];
-            parent_page.page_id_array[(index + 1) as usize] = parent_page.page_id_array[index as usize];
+            let parent_id = parent_page.base_page.page_id;
+            self.insert_into_parent(header, parents, parent_id, split_key, new_internal_page_id);
+        } else {
+            // safe to insert
+            self.insert_into_internal(parent_page, promoted, new_page_id);
+        }
+    }
+
+    // Inserts a separator and the child to its right, the page must have room for it
+    fn insert_into_internal(&self, page: &mut BplusTreeInternalPage<K>, separator: (K, Rid), page_id: PageId) {
+        let mut index = page.base_page.get_size();
+        while index > 0 && self.compare_entries(separator, (page.key_array[(index-1) as usize], page.rid_array[(index-1) as usize])) == Ordering::Less {
+            page.key_array[index as usize] = page.key_array[(index - 1) as usize];
+            page.rid_array[index as usize] = page.rid_array[(index - 1) as usize];
+            page.page_id_array[(index + 1) as usize] = page.page_id_array[index as usize];
             index -= 1;
         }
-        parent_page.key_array[index as usize] = promoted_key;
-        parent_page.page_id_array[(index+1) as usize] = new_leaf_page_id;
-        parent_page.base_page.size_ += 1;
+        (page.key_array[index as usize], page.rid_array[index as usize]) = separator;
+        page.page_id_array[(index+1) as usize] = page_id;
+        page.base_page.size_ += 1;
+    }
 
-        self.parent_map.insert(new_leaf_page_id, parent_id);
+    // Splits a full internal page while inserting the separator and the child to its
+    // right. Of the max size + 1 keys the middle one is promoted and the old page
+    // keeps the larger half of the rest, so both halves keep their min size.
+    fn split_internal(&self, old_internal_page: &mut BplusTreeInternalPage<K>, separator: (K, Rid), page_id: PageId) -> (InternalPageGuard<K>, (K, Rid)) {
+        let size = old_internal_page.get_size() as usize;
+        let mut keys: Vec<(K, Rid)> = (0..size).map(|index| (old_internal_page.key_array[index], old_internal_page.rid_array[index])).collect();
+        let mut children = old_internal_page.page_id_array[..=size].to_vec();
+        // equal separators go after the ones already there, like insert_into_internal
+        let index = keys.partition_point(|&entry| self.compare_entries(entry, separator) != Ordering::Greater);
+        keys.insert(index, separator);
+        children.insert(index + 1, page_id);
 
+        let mid = if K::VARIABLE_LENGTH {
+            // the promoted key leaves the page, the rest is split by bytes, into halves
+            // that don't underflow if there are any
+            let separator_rids = old_internal_page.separator_rids;
+            let entry_bytes: Vec<usize> = keys.iter().map(|(key, _)| BplusTreeInternalPage::slotted_entry_size(key, separator_rids)).collect();
+            let min_size = old_internal_page.min_size() as usize;
+            let page_bytes = |entries: &[usize]| BplusTreeInternalPage::<K>::SLOTTED_HEADER_SIZE + entries.iter().sum::<usize>();
+            let mid = (1..size.max(2)).min_by_key(|&mid| {
+                let (left, right) = (page_bytes(&entry_bytes[..mid]), page_bytes(&entry_bytes[mid + 1..]));
+                (self.underflows(mid, min_size, left) || self.underflows(size - mid, min_size, right), left.max(right))
+            });
+            mid.unwrap()
+        } else {
+            size.div_ceil(2)
+        };
+        let mut internal_guard = self.format_internal(self.allocate_page());
+        let new_internal_page = internal_guard.as_mut();
+        for (slot, &(key, rid)) in keys[mid + 1..].iter().enumerate() {
+            (new_internal_page.key_array[slot], new_internal_page.rid_array[slot]) = (key, rid);
+        }
+        new_internal_page.page_id_array[..children.len() - mid - 1].copy_from_slice(&children[mid + 1..]);
+        new_internal_page.base_page.set_size((size - mid) as i32);
+        for (slot, &(key, rid)) in keys[..mid].iter().enumerate() {
+            (old_internal_page.key_array[slot], old_internal_page.rid_array[slot]) = (key, rid);
+        }
+        old_internal_page.page_id_array[..=mid].copy_from_slice(&children[..=mid]);
+        old_internal_page.base_page.set_size(mid as i32);
+        (internal_guard, keys[mid])
+    }
 
+    // Formats the page as an empty internal page, with separator rids in a non-unique index
+    fn format_internal(&self, page_id: PageId) -> InternalPageGuard<K> {
+        let mut internal_guard = InternalPageGuard::initialize(self.bpm.write_page(page_id, AccessType::Index), self.internal_max_size);
+        internal_guard.as_mut().separator_rids = self.mode == IndexMode::NonUnique;
+        internal_guard
     }
 
-    fn split_internal(&mut self, old_internal_page: &mut BplusTreeInternalPage) -> (PageId, i64) {
-        let new_internal_page_id = self.bpm.new_page();
-        // create a new leaf page and intitalize it
-        let mut internal_guard = InternalPageGuard::new(self.bpm.write_page(new_internal_page_id, AccessType::Index));        
-        internal_guard.initialize(new_internal_page_id, self.internal_max_size);
-        let mut new_internal_page = internal_guard.as_mut(); 
-        let mid = self.internal_max_size / 2; 
-        // get the promoted key
-        let promoted_key =  old_internal_page.key_array[mid as usize];
-        new_internal_page.base_page.size_ = old_internal_page.base_page.size_ - mid - 1; 
+    // Fixes the underflowing leaf at the end of the path, then any parent the fix
+    // leaves underflowing, by merging a page with a sibling when both fit in one
+    // page, otherwise by borrowing entries from the sibling. A merge takes a key
+    // out of the parent, which may in turn underflow or, as the root, be left with
+    // a single child that becomes the new root. A borrow may lengthen a separator of
+    // a slotted parent past its page, the parent then splits like on an insert.
+    // Every parent involved is still latched by the path; siblings are latched left
+    // before right.
+    fn rebalance(&self, path: WritePath<K>) {
+        let WritePath { mut header, root_page_id, mut parents, leaf } = path;
+        let mut page_id = leaf.guard.get_page_id();
+        // The page is latched again as one of the pair
+        drop(leaf);
+        let mut is_leaf = true;
+        while let Some((mut parent_guard, index)) = parents.pop() {
+            let parent_id = parent_guard.guard.get_page_id();
+            let parent = parent_guard.as_mut();
+            if !self.rebalance_pair(parent, page_id, index, is_leaf) {
+                if !self.fits_page(parent.used_bytes()) {
+                    // any entry will do, split_internal takes it back in
+                    let last = parent.get_size() as usize - 1;
+                    let entry = (parent.key_array[last], parent.rid_array[last]);
+                    let child = parent.page_id_array[last + 1];
+                    parent.base_page.size_ -= 1;
+                    let (new_internal_page_guard, split_key) = self.split_internal(parent, entry, child);
+                    let new_internal_page_id = new_internal_page_guard.guard.get_page_id();
+                    drop(new_internal_page_guard);
+                    self.insert_into_parent(&mut header, &mut parents, parent_id, split_key, new_internal_page_id);
+                }
+                return;
+            }
+            let parent_size = parent.get_size();
+            if parent_id == root_page_id {
+                if parent_size == 0 {
+                    // The root is down to one child, which takes its place
+                    let new_root_id = parent.page_id_array[0];
+                    drop(parent_guard);
+                    header.as_mut().expect("A collapsing root keeps the header latched").set_root_page_id(new_root_id);
+                    self.delete_tree_page(parent_id);
+                }
+                return;
+            }
+            if !self.is_underflowing(parent, false) {
+                return;
+            }
+            page_id = parent_id;
+            is_leaf = false;
+        }
+    }
+
+    // Merges the page at `index` of the parent with a sibling or borrows entries
+    // from it, as many as the page needs to stop underflowing while the sibling keeps
+    // its min size. True if the two merged, the emptied right page is then deleted.
+    fn rebalance_pair(&self, parent: &mut BplusTreeInternalPage<K>, page_id: PageId, index: usize, is_leaf: bool) -> bool {
+        // Pair the page with its left sibling, the first child pairs with its right sibling
+        let right_index = index.max(1);
+        let left_index = right_index - 1;
+        let left_id = parent.page_id_array[left_index];
+        let right_id = parent.page_id_array[right_index];
+        let page_is_left = page_id == left_id;
+
+        let merged = if is_leaf {
+            let mut left_guard = LeafPageGuard::<K>::new(self.bpm.write_page(left_id, AccessType::Index));
+            let mut right_guard = LeafPageGuard::<K>::new(self.bpm.write_page(right_id, AccessType::Index));
+            let (left, right) = (left_guard.as_mut(), right_guard.as_mut());
+            let (left_size, right_size) = (left.get_size() as usize, right.get_size() as usize);
+            let merged_keys = left.key_array[..left_size].iter().chain(&right.key_array[..right_size]);
+            let merged_bytes = BplusTreeLeafPage::slotted_sizes(merged_keys, left.prefix_compression).pop().unwrap();
+            if left_size + right_size <= self.leaf_max_size as usize && self.fits_page(merged_bytes) {
+                left.key_array[left_size..left_size + right_size].copy_from_slice(&right.key_array[..right_size]);
+                left.rid_array[left_size..left_size + right_size].copy_from_slice(&right.rid_array[..right_size]);
+                left.base_page.size_ += right_size as i32;
+                left.next_page_id = right.next_page_id;
+                true
+            } else {
+                let mut borrowed = false;
+                while self.can_borrow_leaf_entry(left, right, page_is_left) {
+                    let (left_size, right_size) = (left.get_size() as usize, right.get_size() as usize);
+                    if page_is_left {
+                        // the right sibling's first entry moves to the end of the page
+                        left.key_array[left_size] = right.key_array[0];
+                        left.rid_array[left_size] = right.rid_array[0];
+                        right.key_array.copy_within(1..right_size, 0);
+                        right.rid_array.copy_within(1..right_size, 0);
+                    } else {
+                        // the left sibling's last entry moves to the front of the page
+                        right.key_array.copy_within(0..right_size, 1);
+                        right.rid_array.copy_within(0..right_size, 1);
+                        right.key_array[0] = left.key_array[left_size - 1];
+                        right.rid_array[0] = left.rid_array[left_size - 1];
+                    }
+                    let shift = if page_is_left { 1 } else { -1 };
+                    left.base_page.size_ += shift;
+                    right.base_page.size_ -= shift;
+                    borrowed = true;
+                }
+                if borrowed {
+                    // the separator goes between the entries on both sides of the new boundary
+                    let last = left.get_size() as usize - 1;
+                    let separator = self.leaf_separator((left.key_array[last], left.rid_array[last]), (right.key_array[0], right.rid_array[0]));
+                    (parent.key_array[left_index], parent.rid_array[left_index]) = separator;
+                }
+                false
+            }
+        } else {
+            let mut left_guard = InternalPageGuard::<K>::new(self.bpm.write_page(left_id, AccessType::Index));
+            let mut right_guard = InternalPageGuard::<K>::new(self.bpm.write_page(right_id, AccessType::Index));
+            let (left, right) = (left_guard.as_mut(), right_guard.as_mut());
+            let (left_size, right_size) = (left.get_size() as usize, right.get_size() as usize);
+            let separator = (parent.key_array[left_index], parent.rid_array[left_index]);
+            let merged_bytes = left.used_bytes() + right.used_bytes() - BplusTreeInternalPage::<K>::SLOTTED_HEADER_SIZE
+                + BplusTreeInternalPage::slotted_entry_size(&separator.0, left.separator_rids);
+            if left_size + right_size < self.internal_max_size as usize && self.fits_page(merged_bytes) {
+                // the separator comes down between the two halves
+                (left.key_array[left_size], left.rid_array[left_size]) = separator;
+                left.key_array[left_size + 1..left_size + 1 + right_size].copy_from_slice(&right.key_array[..right_size]);
+                left.rid_array[left_size + 1..left_size + 1 + right_size].copy_from_slice(&right.rid_array[..right_size]);
+                left.page_id_array[left_size + 1..left_size + 2 + right_size].copy_from_slice(&right.page_id_array[..right_size + 1]);
+                left.base_page.size_ += right_size as i32 + 1;
+                true
+            } else {
+                while self.can_borrow_internal_entry(parent, left_index, left, right, page_is_left) {
+                    let (left_size, right_size) = (left.get_size() as usize, right.get_size() as usize);
+                    let separator = (parent.key_array[left_index], parent.rid_array[left_index]);
+                    // entries rotate through the parent's separator
+                    if page_is_left {
+                        (left.key_array[left_size], left.rid_array[left_size]) = separator;
+                        left.page_id_array[left_size + 1] = right.page_id_array[0];
+                        (parent.key_array[left_index], parent.rid_array[left_index]) = (right.key_array[0], right.rid_array[0]);
+                        right.key_array.copy_within(1..right_size, 0);
+                        right.rid_array.copy_within(1..right_size, 0);
+                        right.page_id_array.copy_within(1..right_size + 1, 0);
+                    } else {
+                        right.key_array.copy_within(0..right_size, 1);
+                        right.rid_array.copy_within(0..right_size, 1);
+                        right.page_id_array.copy_within(0..right_size + 1, 1);
+                        (right.key_array[0], right.rid_array[0]) = separator;
+                        right.page_id_array[0] = left.page_id_array[left_size];
+                        (parent.key_array[left_index], parent.rid_array[left_index]) = (left.key_array[left_size - 1], left.rid_array[left_size - 1]);
+                    }
+                    let shift = if page_is_left { 1 } else { -1 };
+                    left.base_page.size_ += shift;
+                    right.base_page.size_ -= shift;
+                }
+                false
+            }
+        };
+        if merged {
+            // The right page was emptied into the left one
+            parent.remove_at(right_index as i32);
+            self.delete_tree_page(right_id);
+        }
+        merged
+    }
 
-        for index in 0..new_internal_page.base_page.size_ {
-            new_internal_page.key_array[index as usize] = old_internal_page.key_array[(mid + 1 + index) as usize];
-            new_internal_page.page_id_array[index as usize] = old_internal_page.page_id_array[(mid + 1 + index) as usize];
-            self.parent_map.insert(new_internal_page.page_id_array[index as usize], new_internal_page_id);
+    // Whether the underflowing leaf can take one more entry from its sibling, which
+    // has to stay at its min size
+    fn can_borrow_leaf_entry(&self, left: &BplusTreeLeafPage<K>, right: &BplusTreeLeafPage<K>, page_is_left: bool) -> bool {
+        let (page, sibling) = if page_is_left { (left, right) } else { (right, left) };
+        let sibling_size = sibling.get_size() as usize;
+        if !self.is_underflowing(page, false) || sibling_size < 2 {
+            return false;
         }
-        new_internal_page.page_id_array[new_internal_page.get_size() as usize] = old_internal_page.page_id_array[old_internal_page.get_size() as usize];
-        self.parent_map.insert(new_internal_page.page_id_array[new_internal_page.get_size() as usize], new_internal_page_id);
-        old_internal_page.base_page.set_size(mid);
-        (new_internal_page_id, promoted_key as i64)
+        let (moved, rest) = if page_is_left {
+            (sibling.key_array[0], &sibling.key_array[1..sibling_size])
+        } else {
+            (sibling.key_array[sibling_size - 1], &sibling.key_array[..sibling_size - 1])
+        };
+        let rest_bytes = BplusTreeLeafPage::slotted_sizes(rest, sibling.prefix_compression).pop().unwrap();
+        !self.underflows(rest.len(), sibling.min_size() as usize, rest_bytes) && self.fits_page(page.used_bytes_with(&moved))
+    }
+
+    // Whether the underflowing internal page can take one more entry from its sibling
+    // through the parent's separator at index, the sibling has to stay at its min size
+    fn can_borrow_internal_entry(&self, parent: &BplusTreeInternalPage<K>, index: usize, left: &BplusTreeInternalPage<K>, right: &BplusTreeInternalPage<K>, page_is_left: bool) -> bool {
+        let (page, sibling) = if page_is_left { (left, right) } else { (right, left) };
+        let sibling_size = sibling.get_size() as usize;
+        if !self.is_underflowing(page, false) || sibling_size < 2 {
+            return false;
+        }
+        // the sibling's key goes up to the parent, the parent's separator comes down
+        let moved = if page_is_left { &sibling.key_array[0] } else { &sibling.key_array[sibling_size - 1] };
+        let entry_bytes = |key: &K| BplusTreeInternalPage::slotted_entry_size(key, sibling.separator_rids);
+        let rest_bytes = sibling.used_bytes() - entry_bytes(moved);
+        !self.underflows(sibling_size - 1, sibling.min_size() as usize, rest_bytes)
+            && self.fits_page(page.used_bytes() + entry_bytes(&parent.key_array[index]))
+    }
+
+    // Whether a page of used bytes fits, only slotted pages can run out of bytes
+    fn fits_page(&self, used_bytes: usize) -> bool {
+        !K::VARIABLE_LENGTH || used_bytes <= PAGE_SIZE
+    }
+
+    // Frees a page the tree no longer references. A reader may still have it
+    // pinned, the pool then frees it once the reader lets go.
+    fn delete_tree_page(&self, page_id: PageId) {
+        self.bpm.delete_page_deferred(page_id);
     }
 
 }
 
+// Decoded when latched and encoded again on drop, like the tree page guards below
 pub struct HeaderPageGuard {
     guard: WritePageGuard,
+    page: BPlusTreeHeaderPage,
+    is_modified: bool,
 }
 
 impl HeaderPageGuard {
     fn new(guard: WritePageGuard) -> Self {
-        Self { guard }
+        let page = decode_page(BPlusTreeHeaderPage::decode(guard.as_ref()), guard.get_page_id());
+        Self { guard, page, is_modified: false }
     }
     
     fn as_mut(&mut self) -> &mut BPlusTreeHeaderPage {
-        unsafe { &mut *(self.guard.as_mut().as_mut_ptr() as *mut BPlusTreeHeaderPage) }
+        self.is_modified = true;
+        &mut self.page
     }
 
     fn as_ref(&self) -> &BPlusTreeHeaderPage {
-        unsafe { &*(self.guard.as_ref().as_ptr() as *const BPlusTreeHeaderPage) }
+        &self.page
     }
 
     fn is_empty(&self) -> bool {
@@ -334,67 +1192,137 @@ impl HeaderPageGuard {
         self.as_mut().root_page_id = page_id
     }
 }
-pub struct InternalPageGuard {
-    pub guard: WritePageGuard
+
+impl Drop for HeaderPageGuard {
+    fn drop(&mut self) {
+        if self.is_modified {
+            self.page.encode(self.guard.as_mut());
+        }
+    }
+}
+// Tree pages are decoded when latched and encoded again when the guard is dropped,
+// the frame only ever holds the on-disk format.
+pub struct InternalPageGuard<K: IndexKey = KeyType> {
+    pub guard: WritePageGuard,
+    page: Box<BplusTreeInternalPage<K>>,
+    is_modified: bool,
 }
 
-impl InternalPageGuard {
-    pub fn new( guard: WritePageGuard) -> Self {
-        Self { guard }
+impl<K: IndexKey> InternalPageGuard<K> {
+    pub fn new(guard: WritePageGuard) -> Self {
+        let page = decode_page(BplusTreeInternalPage::<K>::decode(guard.as_ref()), guard.get_page_id());
+        Self { guard, page: Box::new(page), is_modified: false }
     }
 
-    pub fn initialize(&mut self, page_id: PageId, max_size: i32) {
-        let mut internal_page = BplusTreeInternalPage::new(max_size, page_id);
-        //leaf.base_page.page_id = page_id;
-        //println!("initialize page id {}", leaf.base_page.page_id);
-        unsafe {
-            let ptr = self.guard.as_mut().as_mut_ptr() as *mut BplusTreeInternalPage;
-            std::ptr::write(ptr, internal_page);            
-        }
+    // Formats the latched page as an empty internal page
+    pub fn initialize(guard: WritePageGuard, max_size: i32) -> Self {
+        let page = BplusTreeInternalPage::<K>::new(max_size, guard.get_page_id());
+        Self { guard, page: Box::new(page), is_modified: true }
     }
 }
 
-pub struct LeafPageGuard {
-    pub guard: WritePageGuard
+impl<K: IndexKey> AsRef<BplusTreeInternalPage<K>> for InternalPageGuard<K> {
+    fn as_ref(&self) -> &BplusTreeInternalPage<K> {
+        &self.page
+    }
 }
 
-impl LeafPageGuard {
-    pub fn new( guard: WritePageGuard) -> Self {
-        Self { guard }
+impl<K: IndexKey> AsMut<BplusTreeInternalPage<K>> for InternalPageGuard<K> {
+    fn as_mut(&mut self) -> &mut BplusTreeInternalPage<K> {
+        self.is_modified = true;
+        &mut self.page
     }
+}
 
-      // it is just interpreting the existing page to a Bplustree Leaf page
-      pub fn initialize(&mut self, page_id: PageId, max_size: i32) {
-        let mut leaf = BplusTreeLeafPage::new(max_size, page_id);
-        //leaf.base_page.page_id = page_id;
-        //println!("initialize page id {}", leaf.base_page.page_id);
-        unsafe {
-            let ptr = self.guard.as_mut().as_mut_ptr() as *mut BplusTreeLeafPage;
-            std::ptr::write(ptr, leaf);            
+impl<K: IndexKey> Drop for InternalPageGuard<K> {
+    fn drop(&mut self) {
+        if self.is_modified {
+            self.page.encode(self.guard.as_mut());
         }
     }
+}
 
-    // fn as_ref(&self) -> &BplusTreeLeafPage {
-    //     <Self as AsRef<BplusTreeLeafPage>>::as_ref(self) // Explicitly call AsRef
-    // }
+pub struct LeafPageGuard<K: IndexKey = KeyType> {
+    pub guard: WritePageGuard,
+    page: Box<BplusTreeLeafPage<K>>,
+    is_modified: bool,
+}
 
-    // fn as_mut(&mut self) -> &mut BplusTreeLeafPage {
-    //     <Self as AsMut<BplusTreeLeafPage>>::as_mut(self) // Explicitly call AsMut, avoid recursion
-    // }
+impl<K: IndexKey> LeafPageGuard<K> {
+    pub fn new(guard: WritePageGuard) -> Self {
+        let page = decode_page(BplusTreeLeafPage::<K>::decode(guard.as_ref()), guard.get_page_id());
+        Self { guard, page: Box::new(page), is_modified: false }
+    }
+
+    // Formats the latched page as an empty leaf
+    pub fn initialize(guard: WritePageGuard, max_size: i32) -> Self {
+        let page = BplusTreeLeafPage::<K>::new(max_size, guard.get_page_id());
+        Self { guard, page: Box::new(page), is_modified: true }
+    }
+}
+
+impl<K: IndexKey> AsRef<BplusTreeLeafPage<K>> for LeafPageGuard<K> {
+    fn as_ref(&self) -> &BplusTreeLeafPage<K> {
+        &self.page
+    }
+}
+
+impl<K: IndexKey> AsMut<BplusTreeLeafPage<K>> for LeafPageGuard<K> {
+    fn as_mut(&mut self) -> &mut BplusTreeLeafPage<K> {
+        self.is_modified = true;
+        &mut self.page
+    }
 }
 
-impl AsMut<BplusTreeLeafPage> for LeafPageGuard {
-    fn as_mut(&mut self) -> &mut BplusTreeLeafPage {
-        unsafe {
-            &mut *(self.guard.as_mut().as_mut_ptr() as *mut BplusTreeLeafPage)
+impl<K: IndexKey> Drop for LeafPageGuard<K> {
+    fn drop(&mut self) {
+        if self.is_modified {
+            self.page.encode(self.guard.as_mut());
         }
     }
 }
 
-impl AsMut<BplusTreeInternalPage> for InternalPageGuard {
-    fn as_mut(&mut self) -> &mut BplusTreeInternalPage {
-        unsafe {
-            &mut *(self.guard.as_mut().as_mut_ptr() as *mut BplusTreeInternalPage)
+// Splits total entries evenly into pages of about target entries, none over max.
+// The pages hold at least min entries unless there are too few entries for it.
+fn page_sizes(total: usize, min: usize, max: usize, target: usize) -> Vec<usize> {
+    let mut pages = total.div_ceil(target.clamp(min, max));
+    if pages > 1 && total / pages < min {
+        pages = (total / min).max(total.div_ceil(max));
+    }
+    (0..pages).map(|page| total / pages + usize::from(page < total % pages)).collect()
+}
+
+// Splits count entries into slotted pages filled up to fill_factor of the page's
+// bytes and max entries each. page_bytes(range) gives the used bytes of pages
+// holding the first 0, 1, 2, ... of the entries in range. Pages keep their min
+// bytes, the last one too if the entries allow it.
+fn slotted_page_sizes(count: usize, max: usize, largest_entry: usize, fill_factor: f64, page_bytes: impl Fn(Range<usize>) -> Vec<usize>) -> Vec<usize> {
+    let target = ((PAGE_SIZE as f64 * fill_factor) as usize).clamp(SLOTTED_MIN_USED + largest_entry, PAGE_SIZE);
+    let used = |range: Range<usize>| page_bytes(range).pop().unwrap();
+    let mut sizes = Vec::new();
+    let mut start = 0;
+    while start < count {
+        let bytes = page_bytes(start..count.min(start + max));
+        // the most entries within the target, at least one
+        let size = (1..bytes.len()).rev().find(|&size| bytes[size] <= target).unwrap_or(1);
+        sizes.push(size);
+        start += size;
+    }
+    // A short last page is merged into the one before it, or takes entries from it
+    let last = sizes.len() - 1;
+    let mut last_start = count - sizes[last];
+    if last > 0 && used(last_start..count) < SLOTTED_MIN_USED {
+        let previous_start = last_start - sizes[last - 1];
+        if sizes[last - 1] + sizes[last] <= max && used(previous_start..count) <= PAGE_SIZE {
+            sizes[last - 1] += sizes[last];
+            sizes.pop();
+        } else {
+            while sizes[last - 1] > 1 && sizes[last] < max && used(last_start..count) < SLOTTED_MIN_USED {
+                last_start -= 1;
+                sizes[last - 1] -= 1;
+                sizes[last] += 1;
+            }
         }
     }
-}
\ No newline at end of file
+    sizes
+}
@@ -0,0 +1,161 @@
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use crate::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use crate::include::common::config::{AccessType, PageId, INVALID_PAGE_ID};
+use crate::include::storage::index::b_plus_tree::BplusTree;
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator};
+use crate::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl};
+use crate::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl};
+use crate::include::storage::page::page_guard::ReadPageGuardImpl;
+use crate::storage::page::index_page::decode_page;
+use crate::storage::page::b_plus_tree_page::BPlusTreeHeaderPage;
+
+// A tree page decoded under its read latch, the latch is released again
+enum TreeNode<K: IndexKey> {
+    Leaf(BplusTreeLeafPage<K>),
+    Internal(BplusTreeInternalPage<K>),
+}
+
+// Debug dumps of the whole tree, like BusTub's b_plus_tree_printer. Pages are read
+// latched one at a time, so dump a tree nobody writes to.
+impl<K: IndexKey, C: KeyComparator<K>> BplusTree<K, C> {
+    // Writes the tree as a Graphviz graph to path, render it with `dot -Tpng`
+    pub fn draw<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_dot())
+    }
+
+    // The tree as a Graphviz graph: a node per page with its page id, sizes and
+    // entries, an edge from every separator to its child and one along the leaf chain
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph G {\n  node [shape=plaintext];\n");
+        let root_page_id = self.root_for_dump();
+        if root_page_id != INVALID_PAGE_ID {
+            self.dot_page(root_page_id, &mut out);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // Prints the tree to stdout, see draw_bplus_tree
+    pub fn print(&self) {
+        print!("{}", self.draw_bplus_tree());
+    }
+
+    // The tree as indented text, a line per page with its children below it:
+    //   Internal 7 size=1/4 (5)
+    //     Leaf 3 size=2/4 next=5 (1,2)
+    //     Leaf 5 size=3/4 next=-1 (5,6,7)
+    pub fn draw_bplus_tree(&self) -> String {
+        let root_page_id = self.root_for_dump();
+        if root_page_id == INVALID_PAGE_ID {
+            return String::from("Empty tree\n");
+        }
+        let mut out = String::new();
+        self.print_page(root_page_id, 0, &mut out);
+        out
+    }
+
+    fn root_for_dump(&self) -> PageId {
+        let guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        decode_page(BPlusTreeHeaderPage::decode(guard.as_ref()), self.header_page_id).root_page_id
+    }
+
+    fn read_node(&self, page_id: PageId) -> TreeNode<K> {
+        let guard = self.bpm.read_page(page_id, AccessType::Index);
+        let (page, _) = decode_page(BplusTreePage::peek_header(guard.as_ref()), page_id);
+        if page.is_leaf_page() {
+            TreeNode::Leaf(decode_page(BplusTreeLeafPage::decode(guard.as_ref()), page_id))
+        } else {
+            TreeNode::Internal(decode_page(BplusTreeInternalPage::decode(guard.as_ref()), page_id))
+        }
+    }
+
+    // Writes the page and everything below it, returns the name of its node
+    fn dot_page(&self, page_id: PageId, out: &mut String) -> String {
+        match self.read_node(page_id) {
+            TreeNode::Leaf(leaf) => {
+                let name = format!("LEAF_{}", page_id);
+                let cells: Vec<String> = (0..leaf.base_page.get_size()).map(|index| format!("{:?}", leaf.key_at(index))).collect();
+                write_dot_node(out, &name, "#A2E0A2", &leaf.base_page, &cells, |_| String::new());
+                let next_page_id = leaf.get_next_page_id();
+                if next_page_id != INVALID_PAGE_ID {
+                    let _ = writeln!(out, "  {} -> LEAF_{};", name, next_page_id);
+                    let _ = writeln!(out, "  {{rank=same {} LEAF_{}}};", name, next_page_id);
+                }
+                name
+            }
+            TreeNode::Internal(internal) => {
+                let name = format!("INT_{}", page_id);
+                let size = internal.base_page.get_size();
+                // The first child has no separator in front of it
+                let cells: Vec<String> = (0..=size)
+                    .map(|child| if child == 0 { String::from(" ") } else { separator_label(&internal, child - 1) })
+                    .collect();
+                write_dot_node(out, &name, "#F2B8C6", &internal.base_page, &cells, |child| {
+                    format!(" PORT=\"p{}\"", internal.page_id_value_at(child as i32))
+                });
+                for child in 0..=size {
+                    let child_page_id = internal.page_id_value_at(child);
+                    let child_name = self.dot_page(child_page_id, out);
+                    let _ = writeln!(out, "  {}:p{} -> {};", name, child_page_id, child_name);
+                }
+                name
+            }
+        }
+    }
+
+    fn print_page(&self, page_id: PageId, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        match self.read_node(page_id) {
+            TreeNode::Leaf(leaf) => {
+                let page = &leaf.base_page;
+                let _ = writeln!(out, "{}Leaf {} size={}/{} next={} {}",
+                    indent, page_id, page.get_size(), page.get_max_size(), leaf.get_next_page_id(), leaf.to_string());
+            }
+            TreeNode::Internal(internal) => {
+                let page = &internal.base_page;
+                let separators: Vec<String> = (0..page.get_size()).map(|index| separator_label(&internal, index)).collect();
+                let _ = writeln!(out, "{}Internal {} size={}/{} ({})",
+                    indent, page_id, page.get_size(), page.get_max_size(), separators.join(","));
+                for child in 0..=page.get_size() {
+                    self.print_page(internal.page_id_value_at(child), depth + 1, out);
+                }
+            }
+        }
+    }
+}
+
+// The separator key, with its rid in a non-unique index
+fn separator_label<K: IndexKey>(internal: &BplusTreeInternalPage<K>, index: i32) -> String {
+    if internal.separator_rids {
+        format!("{:?}@{}", internal.key_at(index), internal.rid_array[index as usize])
+    } else {
+        format!("{:?}", internal.key_at(index))
+    }
+}
+
+// A node with the page id and sizes above a row of cells, port gives the
+// attributes of each cell
+fn write_dot_node(out: &mut String, name: &str, color: &str, page: &BplusTreePage, cells: &[String], port: impl Fn(usize) -> String) {
+    let columns = cells.len().max(1);
+    let _ = writeln!(out, "  {}[label=<<TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\" BGCOLOR=\"{}\">", name, color);
+    let _ = writeln!(out, "  <TR><TD COLSPAN=\"{}\">P={}</TD></TR>", columns, page.page_id);
+    let _ = writeln!(out, "  <TR><TD COLSPAN=\"{}\">max_size={},min_size={},size={}</TD></TR>",
+        columns, page.get_max_size(), page.get_min_size(), page.get_size());
+    out.push_str("  <TR>");
+    for (index, cell) in cells.iter().enumerate() {
+        let _ = write!(out, "<TD{}>{}</TD>", port(index), escape_html(cell));
+    }
+    if cells.is_empty() {
+        out.push_str("<TD></TD>");
+    }
+    out.push_str("</TR></TABLE>>];\n");
+}
+
+// Keys are printed with Debug, which may use characters Graphviz labels reserve
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
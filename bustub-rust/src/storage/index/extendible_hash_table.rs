@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::io;
+use std::marker::PhantomData;
+
+use crate::buffer::bufferpool_manager::BufferPoolManager;
+use crate::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use crate::include::common::config::{AccessType, PageId, INVALID_PAGE_ID};
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::b_plus_tree::DuplicateKey;
+use crate::include::storage::index::extendible_hash_table::{ExtendibleHashTable, ExtendibleHashTableImpl};
+use crate::include::storage::index::generic_key::{DefaultKeyHasher, IndexKey, KeyComparator, KeyHasher, OrdComparator};
+use crate::include::storage::page::extendible_htable_bucket_page::{ExtendibleHTableBucketPage, ExtendibleHTableBucketPageImpl};
+use crate::include::storage::page::extendible_htable_directory_page::{
+    ExtendibleHTableDirectoryPage, ExtendibleHTableDirectoryPageImpl, HTABLE_DIRECTORY_MAX_DEPTH,
+};
+use crate::include::storage::page::extendible_htable_header_page::{ExtendibleHTableHeaderPage, ExtendibleHTableHeaderPageImpl, HTABLE_HEADER_MAX_DEPTH};
+use crate::include::storage::page::page_guard::{ReadPageGuardImpl, WritePageGuardImpl};
+use crate::storage::page::index_page::decode_page;
+use crate::storage::page::page_guard::WritePageGuard;
+
+impl ExtendibleHashTable {
+    pub fn new(
+        index_name: String,
+        bpm: &BufferPoolManager,
+        header_max_depth: u32,
+        directory_max_depth: u32,
+        bucket_max_size: u32,
+        header_page_id: PageId,
+    ) -> io::Result<Self> {
+        Self::with_hasher(index_name, bpm, OrdComparator, DefaultKeyHasher, header_max_depth, directory_max_depth, bucket_max_size, header_page_id)
+    }
+}
+
+impl<K: IndexKey, C: KeyComparator<K>, H: KeyHasher<K>> ExtendibleHashTable<K, C, H> {
+    // An invalid header page id allocates the header of a new, empty table, which
+    // routes to 2^header_max_depth directories. The depths and bucket size aren't
+    // checked against an existing table, reopen it with the ones it was created with.
+    // Fails with InvalidInput on depths or a bucket size out of range, and if no
+    // page can be allocated for the header.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_hasher(
+        index_name: String,
+        bpm: &BufferPoolManager,
+        comparator: C,
+        hasher: H,
+        header_max_depth: u32,
+        directory_max_depth: u32,
+        bucket_max_size: u32,
+        header_page_id: PageId,
+    ) -> io::Result<Self> {
+        // fail here rather than on the first insert
+        let invalid = |msg: String| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        if header_max_depth > HTABLE_HEADER_MAX_DEPTH {
+            return invalid(format!("Header max depth {} exceeds {}", header_max_depth, HTABLE_HEADER_MAX_DEPTH));
+        }
+        if directory_max_depth > HTABLE_DIRECTORY_MAX_DEPTH {
+            return invalid(format!("Directory max depth {} exceeds {}", directory_max_depth, HTABLE_DIRECTORY_MAX_DEPTH));
+        }
+        let slot_cnt = ExtendibleHTableBucketPage::<K>::SLOT_CNT;
+        if bucket_max_size == 0 || bucket_max_size as usize > slot_cnt {
+            return invalid(format!("Bucket max size {} is outside the 1..={} slots of a bucket with {} byte keys", bucket_max_size, slot_cnt, K::SIZE));
+        }
+        let header_page_id = if header_page_id == INVALID_PAGE_ID {
+            let new_page_id = bpm.new_page()?;
+            ExtendibleHTableHeaderPage::new(header_max_depth).encode(bpm.write_page(new_page_id, AccessType::Index).as_mut());
+            new_page_id
+        } else {
+            header_page_id
+        };
+        Ok(ExtendibleHashTable {
+            index_name,
+            bpm: bpm.clone(),
+            header_page_id,
+            comparator,
+            hasher,
+            directory_max_depth,
+            bucket_max_size,
+            key_type: PhantomData,
+        })
+    }
+
+    // Checks every directory and that each entry sits in the bucket its hash
+    // routes to. Holds the header read latch throughout and reads the other pages
+    // one at a time, so check a table nobody writes to.
+    pub fn verify_integrity(&self) -> io::Result<()> {
+        let invalid = |msg: String| Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        let header_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        let header = ExtendibleHTableHeaderPage::decode(header_guard.as_ref(), self.header_page_id)?;
+        let mut seen = HashSet::new();
+        for directory_index in 0..header.max_size() {
+            let directory_page_id = header.get_directory_page_id(directory_index);
+            if directory_page_id == INVALID_PAGE_ID {
+                continue;
+            }
+            let directory_guard = self.bpm.read_page(directory_page_id, AccessType::Index);
+            let directory = ExtendibleHTableDirectoryPage::decode(directory_guard.as_ref(), directory_page_id)?;
+            directory.verify_integrity()?;
+            for bucket_index in 0..directory.size() {
+                let local_mask = directory.get_local_depth_mask(bucket_index);
+                // every bucket once, at the lowest of its slots
+                if bucket_index as u32 & local_mask != bucket_index as u32 {
+                    continue;
+                }
+                let bucket_page_id = directory.get_bucket_page_id(bucket_index);
+                if !seen.insert(bucket_page_id) {
+                    return invalid(format!("Bucket page {} is reached from more than one directory", bucket_page_id));
+                }
+                let bucket_guard = self.bpm.read_page(bucket_page_id, AccessType::Index);
+                let bucket = ExtendibleHTableBucketPage::<K>::decode(bucket_guard.as_ref(), bucket_page_id)?;
+                for (index, (key, _)) in bucket.entries.iter().enumerate() {
+                    let hash = self.hasher.hash(key);
+                    if header.hash_to_directory_index(hash) != directory_index || hash & local_mask != bucket_index as u32 {
+                        return invalid(format!("Bucket page {} holds key {:?}, which hashes to another bucket", bucket_page_id, key));
+                    }
+                    if bucket.entries[..index].iter().any(|(other, _)| self.comparator.compare(other, key).is_eq()) {
+                        return invalid(format!("Bucket page {} holds key {:?} twice", bucket_page_id, key));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The directory the hash belongs to, INVALID_PAGE_ID if it doesn't exist yet
+    fn find_directory(&self, hash: u32) -> PageId {
+        let header_guard = self.bpm.read_page(self.header_page_id, AccessType::Index);
+        let header = decode_page(ExtendibleHTableHeaderPage::decode(header_guard.as_ref(), self.header_page_id), self.header_page_id);
+        header.get_directory_page_id(header.hash_to_directory_index(hash))
+    }
+
+    // The directory the hash belongs to, created with an empty bucket if need be.
+    // Directories are never freed, so the page id stays good after the header
+    // latch is released.
+    fn find_or_create_directory(&self, hash: u32) -> io::Result<PageId> {
+        let directory_page_id = self.find_directory(hash);
+        if directory_page_id != INVALID_PAGE_ID {
+            return Ok(directory_page_id);
+        }
+        let mut header_guard = self.bpm.write_page(self.header_page_id, AccessType::Index);
+        let mut header = decode_page(ExtendibleHTableHeaderPage::decode(header_guard.as_ref(), self.header_page_id), self.header_page_id);
+        let directory_index = header.hash_to_directory_index(hash);
+        // another thread may have created it while no latch was held
+        let directory_page_id = header.get_directory_page_id(directory_index);
+        if directory_page_id != INVALID_PAGE_ID {
+            return Ok(directory_page_id);
+        }
+        let bucket_page_id = self.bpm.new_page()?;
+        let bucket = ExtendibleHTableBucketPage::<K>::new(self.bucket_max_size, bucket_page_id);
+        bucket.encode(self.bpm.write_page(bucket_page_id, AccessType::Index).as_mut());
+        let directory_page_id = match self.bpm.new_page() {
+            Ok(page_id) => page_id,
+            Err(e) => {
+                self.bpm.delete_page(bucket_page_id);
+                return Err(e);
+            }
+        };
+        let directory = ExtendibleHTableDirectoryPage::new(self.directory_max_depth, bucket_page_id);
+        directory.encode(self.bpm.write_page(directory_page_id, AccessType::Index).as_mut());
+        header.set_directory_page_id(directory_index, directory_page_id);
+        header.encode(header_guard.as_mut());
+        Ok(directory_page_id)
+    }
+
+    fn read_bucket(&self, page_id: PageId) -> ExtendibleHTableBucketPage<K> {
+        let guard = self.bpm.read_page(page_id, AccessType::Index);
+        decode_page(ExtendibleHTableBucketPage::decode(guard.as_ref(), page_id), page_id)
+    }
+
+    // Splits the full bucket at bucket_index in two, the keys whose next hash bit is
+    // set move to a new bucket. The directory has to have a bit left to tell them apart.
+    // Nothing changes if no page can be allocated for the new bucket.
+    fn split_bucket(&self, directory: &mut ExtendibleHTableDirectoryPage, bucket_index: usize, mut bucket: ExtendibleHTableBucketPage<K>, mut bucket_guard: WritePageGuard) -> io::Result<()> {
+        let local_depth = directory.get_local_depth(bucket_index) + 1;
+        let high_bit = 1 << (local_depth - 1);
+        let image_page_id = self.bpm.new_page()?;
+        // split halves keep the size of the bucket they came from
+        let mut image = ExtendibleHTableBucketPage::new(bucket.max_size, image_page_id);
+        let (moved, kept): (Vec<_>, Vec<_>) = bucket.entries.drain(..).partition(|(key, _)| self.hasher.hash(key) & high_bit != 0);
+        (image.entries, bucket.entries) = (moved, kept);
+        for slot in 0..directory.size() {
+            if directory.get_bucket_page_id(slot) == bucket.page_id {
+                directory.set_local_depth(slot, local_depth);
+                if slot as u32 & high_bit != 0 {
+                    directory.set_bucket_page_id(slot, image_page_id);
+                }
+            }
+        }
+        bucket.encode(bucket_guard.as_mut());
+        image.encode(self.bpm.write_page(image_page_id, AccessType::Index).as_mut());
+        Ok(())
+    }
+
+    // Merges the bucket at bucket_index into its split image while one of the two
+    // is empty and both have the same local depth, then shrinks the directory as
+    // far as it goes
+    fn merge_buckets(&self, directory: &mut ExtendibleHTableDirectoryPage, mut bucket_index: usize) {
+        loop {
+            let local_depth = directory.get_local_depth(bucket_index);
+            let image_index = directory.get_split_image_index(bucket_index);
+            if local_depth == 0 || directory.get_local_depth(image_index) != local_depth {
+                break;
+            }
+            let (page_id, image_page_id) = (directory.get_bucket_page_id(bucket_index), directory.get_bucket_page_id(image_index));
+            let (kept, freed) = if self.read_bucket(page_id).is_empty() {
+                (image_page_id, page_id)
+            } else if self.read_bucket(image_page_id).is_empty() {
+                (page_id, image_page_id)
+            } else {
+                break;
+            };
+            for slot in 0..directory.size() {
+                let slot_page_id = directory.get_bucket_page_id(slot);
+                if slot_page_id == page_id || slot_page_id == image_page_id {
+                    directory.set_bucket_page_id(slot, kept);
+                    directory.set_local_depth(slot, local_depth - 1);
+                }
+            }
+            // a reader may still have the page pinned, the pool then frees it once the reader lets go
+            self.bpm.delete_page_deferred(freed);
+            // the merged bucket may now have an empty image of its own
+            bucket_index &= (1 << (local_depth - 1)) - 1;
+        }
+        while directory.can_shrink() {
+            directory.decr_global_depth();
+        }
+    }
+}
+
+impl<K: IndexKey, C: KeyComparator<K>, H: KeyHasher<K>> ExtendibleHashTableImpl<K> for ExtendibleHashTable<K, C, H> {
+    fn insert(&self, key: K, rid: Rid) -> io::Result<()> {
+        let hash = self.hasher.hash(&key);
+        let directory_page_id = self.find_or_create_directory(hash)?;
+        // Splits change the directory, so every insert holds its write latch
+        let mut directory_guard = self.bpm.write_page(directory_page_id, AccessType::Index);
+        let mut directory = decode_page(ExtendibleHTableDirectoryPage::decode(directory_guard.as_ref(), directory_page_id), directory_page_id);
+        loop {
+            let bucket_index = directory.hash_to_bucket_index(hash);
+            let bucket_page_id = directory.get_bucket_page_id(bucket_index);
+            let (local_depth, global_depth, max_depth) = (directory.get_local_depth(bucket_index), directory.get_global_depth(), directory.get_max_depth());
+            let mut bucket_guard = self.bpm.write_page(bucket_page_id, AccessType::Index);
+            let mut bucket = decode_page(ExtendibleHTableBucketPage::decode(bucket_guard.as_ref(), bucket_page_id), bucket_page_id);
+            if let Some(existing) = bucket.lookup(&key, &self.comparator) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, DuplicateKey { existing }));
+            }
+            if bucket.insert(key, rid) {
+                bucket.encode(bucket_guard.as_mut());
+                return Ok(());
+            }
+            // The bucket is full: split it, doubling the directory first if the
+            // bucket already uses every bit of it. Keys can all land in one half,
+            // so try again until the key fits.
+            if local_depth == global_depth && global_depth == max_depth {
+                return Err(io::Error::new(
+                    io::ErrorKind::StorageFull,
+                    format!("Bucket page {} of {} is full and its directory is at its max depth {}", bucket_page_id, self.index_name, max_depth),
+                ));
+            }
+            // the directory is only written back once the split went through
+            if local_depth == global_depth {
+                directory.incr_global_depth();
+            }
+            self.split_bucket(&mut directory, bucket_index, bucket, bucket_guard)?;
+            directory.encode(directory_guard.as_mut());
+        }
+    }
+
+    fn remove(&self, key: K, rid: Rid) -> bool {
+        let hash = self.hasher.hash(&key);
+        let directory_page_id = self.find_directory(hash);
+        if directory_page_id == INVALID_PAGE_ID {
+            return false;
+        }
+        // Merges change the directory, so every remove holds its write latch
+        let mut directory_guard = self.bpm.write_page(directory_page_id, AccessType::Index);
+        let mut directory = decode_page(ExtendibleHTableDirectoryPage::decode(directory_guard.as_ref(), directory_page_id), directory_page_id);
+        let bucket_index = directory.hash_to_bucket_index(hash);
+        let bucket_page_id = directory.get_bucket_page_id(bucket_index);
+        let mut bucket_guard = self.bpm.write_page(bucket_page_id, AccessType::Index);
+        let mut bucket = decode_page(ExtendibleHTableBucketPage::decode(bucket_guard.as_ref(), bucket_page_id), bucket_page_id);
+        if !bucket.remove(&key, rid, &self.comparator) {
+            return false;
+        }
+        bucket.encode(bucket_guard.as_mut());
+        drop(bucket_guard);
+        if bucket.is_empty() {
+            self.merge_buckets(&mut directory, bucket_index);
+            directory.encode(directory_guard.as_mut());
+        }
+        true
+    }
+
+    fn get_value(&self, key: K) -> Vec<Rid> {
+        let hash = self.hasher.hash(&key);
+        let directory_page_id = self.find_directory(hash);
+        if directory_page_id == INVALID_PAGE_ID {
+            return Vec::new();
+        }
+        let directory_guard = self.bpm.read_page(directory_page_id, AccessType::Index);
+        let directory = decode_page(ExtendibleHTableDirectoryPage::decode(directory_guard.as_ref(), directory_page_id), directory_page_id);
+        let bucket_page_id = directory.get_bucket_page_id(directory.hash_to_bucket_index(hash));
+        // latch the bucket before letting go of the directory, a merge may free it
+        let bucket_guard = self.bpm.read_page(bucket_page_id, AccessType::Index);
+        drop(directory_guard);
+        let bucket = decode_page(ExtendibleHTableBucketPage::decode(bucket_guard.as_ref(), bucket_page_id), bucket_page_id);
+        bucket.lookup(&key, &self.comparator).into_iter().collect()
+    }
+}
@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+use std::io;
+
+use crate::include::storage::index::generic_key::{
+    DefaultKeyHasher, GenericComparator, GenericKey, IndexKey, KeyColumn, KeyComparator, KeyHasher, KeySchema, KeyValue, OrdComparator,
+    VarcharKey,
+};
+use crate::include::storage::page::b_plus_tree_page::MAX_SLOTTED_KEY_SIZE;
+use crate::storage::page::b_plus_tree_page::{read_i32, read_i64};
+
+impl IndexKey for i64 {
+    const SIZE: usize = 8;
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(buf: &[u8]) -> Self {
+        read_i64(buf, 0)
+    }
+}
+
+impl<K: Ord> KeyComparator<K> for OrdComparator {
+    fn compare(&self, lhs: &K, rhs: &K) -> Ordering {
+        lhs.cmp(rhs)
+    }
+}
+
+impl<K: IndexKey> KeyHasher<K> for DefaultKeyHasher {
+    fn hash(&self, key: &K) -> u32 {
+        let mut buf = vec![0; key.encoded_len()];
+        key.encode(&mut buf);
+        let mut hash = buf.iter().fold(0x811c_9dc5u32, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x85eb_ca6b);
+        hash ^= hash >> 13;
+        hash = hash.wrapping_mul(0xc2b2_ae35);
+        hash ^ (hash >> 16)
+    }
+}
+
+impl<const N: usize> GenericKey<N> {
+    /// Wraps raw key bytes, shorter input is zero padded
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() > N {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} bytes don't fit a {} byte key", bytes.len(), N)));
+        }
+        let mut data = [0; N];
+        data[..bytes.len()].copy_from_slice(bytes);
+        Ok(GenericKey { data })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<const N: usize> IndexKey for GenericKey<N> {
+    // Fails to compile for any other width
+    const SIZE: usize = {
+        assert!(matches!(N, 4 | 8 | 16 | 32 | 64), "Generic keys are 4, 8, 16, 32 or 64 bytes wide");
+        N
+    };
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.data);
+    }
+
+    fn decode(buf: &[u8]) -> Self {
+        GenericKey { data: buf.try_into().unwrap() }
+    }
+}
+
+impl<const N: usize> VarcharKey<N> {
+    /// The key of a string, fails if it has more than N bytes
+    pub fn new(s: &str) -> io::Result<Self> {
+        Self::from_bytes(s.as_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() > N {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} bytes don't fit a {} byte key", bytes.len(), N)));
+        }
+        let mut data = [0; N];
+        data[..bytes.len()].copy_from_slice(bytes);
+        Ok(VarcharKey { len: bytes.len() as u16, data })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl<const N: usize> IndexKey for VarcharKey<N> {
+    // Fails to compile for keys too wide for a slotted page
+    const SIZE: usize = {
+        assert!(N <= MAX_SLOTTED_KEY_SIZE, "Varchar keys take at most MAX_SLOTTED_KEY_SIZE bytes");
+        N
+    };
+    const VARIABLE_LENGTH: bool = true;
+
+    fn encoded_len(&self) -> usize {
+        self.len as usize
+    }
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(self.as_bytes());
+    }
+
+    fn decode(buf: &[u8]) -> Self {
+        // Slotted pages never hold more than SIZE bytes for a key
+        VarcharKey::from_bytes(buf).unwrap()
+    }
+
+    fn common_prefix_len(&self, other: &Self) -> usize {
+        let (lhs, rhs) = (self.as_bytes(), other.as_bytes());
+        let max_len = lhs.len().min(rhs.len());
+        // whole chunks first, leaves share long prefixes
+        let mut len = 0;
+        while len + 16 <= max_len && lhs[len..len + 16] == rhs[len..len + 16] {
+            len += 16;
+        }
+        len + lhs[len..max_len].iter().zip(&rhs[len..max_len]).take_while(|(lhs, rhs)| lhs == rhs).count()
+    }
+
+    // upper up to and including the first byte it differs from lower in
+    fn shortest_separator(lower: &Self, upper: &Self) -> Self {
+        let len = (lower.common_prefix_len(upper) + 1).min(upper.len as usize);
+        VarcharKey::from_bytes(&upper.as_bytes()[..len]).unwrap()
+    }
+}
+
+impl<const N: usize> PartialEq for VarcharKey<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for VarcharKey<N> {}
+
+impl<const N: usize> PartialOrd for VarcharKey<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for VarcharKey<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+// Prints like the string it holds
+impl<const N: usize> std::fmt::Debug for VarcharKey<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+impl KeyColumn {
+    /// Bytes the column takes in a key
+    pub fn width(&self) -> usize {
+        match self {
+            KeyColumn::Integer => 4,
+            KeyColumn::BigInt => 8,
+            KeyColumn::Varchar(len) => *len,
+        }
+    }
+
+    fn compare(&self, lhs: &[u8], rhs: &[u8]) -> Ordering {
+        match self {
+            KeyColumn::Integer => read_i32(lhs, 0).cmp(&read_i32(rhs, 0)),
+            KeyColumn::BigInt => read_i64(lhs, 0).cmp(&read_i64(rhs, 0)),
+            // Zero padding sorts a string before every longer string it prefixes
+            KeyColumn::Varchar(_) => lhs.cmp(rhs),
+        }
+    }
+}
+
+impl KeySchema {
+    pub fn new(columns: Vec<KeyColumn>) -> Self {
+        KeySchema { columns }
+    }
+
+    pub fn columns(&self) -> &[KeyColumn] {
+        &self.columns
+    }
+
+    /// Bytes a key of this schema needs
+    pub fn width(&self) -> usize {
+        self.columns.iter().map(KeyColumn::width).sum()
+    }
+
+    /// Builds the key holding one value per column, integers are stored little-endian
+    pub fn key<const N: usize>(&self, values: &[KeyValue]) -> io::Result<GenericKey<N>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        if self.width() > N {
+            return Err(invalid(format!("A {} byte schema doesn't fit a {} byte key", self.width(), N)));
+        }
+        if values.len() != self.columns.len() {
+            return Err(invalid(format!("Expected {} key values, got {}", self.columns.len(), values.len())));
+        }
+        let mut data = [0; N];
+        let mut pos = 0;
+        for (column, value) in self.columns.iter().zip(values) {
+            let field = &mut data[pos..pos + column.width()];
+            match (column, value) {
+                (KeyColumn::Integer, KeyValue::Integer(v)) => field.copy_from_slice(&v.to_le_bytes()),
+                (KeyColumn::BigInt, KeyValue::BigInt(v)) => field.copy_from_slice(&v.to_le_bytes()),
+                (KeyColumn::Varchar(len), KeyValue::Varchar(s)) => {
+                    if s.len() > *len {
+                        return Err(invalid(format!("{:?} is longer than {} bytes", s, len)));
+                    }
+                    // The padding would make a trailing zero byte ambiguous
+                    if s.contains('\0') {
+                        return Err(invalid(format!("{:?} contains a zero byte", s)));
+                    }
+                    field[..s.len()].copy_from_slice(s.as_bytes());
+                }
+                _ => return Err(invalid(format!("{:?} doesn't match a {:?} column", value, column))),
+            }
+            pos += column.width();
+        }
+        Ok(GenericKey { data })
+    }
+
+    /// Reads the column values back out of a key built by `key`
+    pub fn values<const N: usize>(&self, key: &GenericKey<N>) -> Vec<KeyValue> {
+        let mut pos = 0;
+        let mut values = Vec::with_capacity(self.columns.len());
+        for column in self.columns.iter() {
+            let field = &key.data[pos..pos + column.width()];
+            values.push(match column {
+                KeyColumn::Integer => KeyValue::Integer(read_i32(field, 0)),
+                KeyColumn::BigInt => KeyValue::BigInt(read_i64(field, 0)),
+                KeyColumn::Varchar(_) => {
+                    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+                    KeyValue::Varchar(String::from_utf8_lossy(&field[..len]).into_owned())
+                }
+            });
+            pos += column.width();
+        }
+        values
+    }
+}
+
+impl<const N: usize> GenericComparator<N> {
+    /// Panics if keys of the schema don't fit N bytes
+    pub fn new(schema: KeySchema) -> Self {
+        assert!(schema.width() <= N, "A {} byte schema doesn't fit a {} byte key", schema.width(), N);
+        GenericComparator { schema }
+    }
+
+    pub fn schema(&self) -> &KeySchema {
+        &self.schema
+    }
+}
+
+impl<const N: usize> KeyComparator<GenericKey<N>> for GenericComparator<N> {
+    fn compare(&self, lhs: &GenericKey<N>, rhs: &GenericKey<N>) -> Ordering {
+        let mut pos = 0;
+        for column in self.schema.columns.iter() {
+            let end = pos + column.width();
+            let ordering = column.compare(&lhs.data[pos..end], &rhs.data[pos..end]);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            pos = end;
+        }
+        Ordering::Equal
+    }
+}
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use crate::buffer::bufferpool_manager::BufferPoolManager;
+use crate::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use crate::include::common::config::{AccessType, INVALID_PAGE_ID};
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator};
+use crate::include::storage::index::index_iterator::IndexIterator;
+use crate::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use crate::include::storage::page::page_guard::{PageguardImpl, ReadPageGuardImpl};
+use crate::storage::page::index_page::decode_page;
+use crate::storage::page::page_guard::ReadPageGuard;
+
+fn below<K>(key: &K, lower: &Bound<K>, comparator: &dyn KeyComparator<K>) -> bool {
+    match lower {
+        Bound::Included(lo) => comparator.compare(key, lo) == Ordering::Less,
+        Bound::Excluded(lo) => comparator.compare(key, lo) != Ordering::Greater,
+        Bound::Unbounded => false,
+    }
+}
+
+fn above<K>(key: &K, upper: &Bound<K>, comparator: &dyn KeyComparator<K>) -> bool {
+    match upper {
+        Bound::Included(hi) => comparator.compare(key, hi) == Ordering::Greater,
+        Bound::Excluded(hi) => comparator.compare(key, hi) != Ordering::Less,
+        Bound::Unbounded => false,
+    }
+}
+
+fn decode_leaf<K: IndexKey>(guard: ReadPageGuard) -> (ReadPageGuard, Box<BplusTreeLeafPage<K>>) {
+    let leaf = decode_page(BplusTreeLeafPage::decode(guard.as_ref()), guard.get_page_id());
+    (guard, Box::new(leaf))
+}
+
+impl<'a, K: IndexKey> IndexIterator<'a, K> {
+    /// Starts at the first key of `leaf_guard` within the bounds, None gives the end iterator.
+    /// The leaf must be the one the lower bound routes to.
+    pub(crate) fn new(
+        bpm: &'a BufferPoolManager,
+        comparator: &'a dyn KeyComparator<K>,
+        leaf_guard: Option<ReadPageGuard>,
+        lower: Bound<K>,
+        upper: Bound<K>,
+    ) -> Self {
+        let leaf = leaf_guard.map(decode_leaf);
+        let index = leaf.as_ref().map_or(0, |(_, leaf)| {
+            let size = leaf.base_page.size_ as usize;
+            leaf.key_array[..size].partition_point(|key| below(key, &lower, comparator))
+        });
+        let mut iterator = IndexIterator { bpm, comparator, leaf, index, upper };
+        iterator.settle();
+        iterator
+    }
+
+    /// True once every entry in range has been returned
+    pub fn is_end(&self) -> bool {
+        self.leaf.is_none()
+    }
+
+    // Moves onto the next entry to return, following the leaf chain past exhausted
+    // leaves. Releases the last leaf once the chain or the range runs out.
+    fn settle(&mut self) {
+        while let Some((_, leaf)) = self.leaf.as_ref() {
+            if self.index < leaf.base_page.size_ as usize {
+                if above(&leaf.key_array[self.index], &self.upper, self.comparator) {
+                    self.leaf = None;
+                }
+                return;
+            }
+            let next_page_id = leaf.next_page_id;
+            self.index = 0;
+            // Latch coupling, the current leaf is released once the next one is latched
+            self.leaf = (next_page_id != INVALID_PAGE_ID)
+                .then(|| decode_leaf(self.bpm.read_page(next_page_id, AccessType::Scan)));
+        }
+    }
+}
+
+impl<K: IndexKey> Iterator for IndexIterator<'_, K> {
+    type Item = (K, Rid);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, leaf) = self.leaf.as_ref()?;
+        let entry = (leaf.key_array[self.index], leaf.rid_array[self.index]);
+        self.index += 1;
+        self.settle();
+        Some(entry)
+    }
+}
+
+// Iterators are equal when they point at the same entry, all finished iterators are equal
+impl<K> PartialEq for IndexIterator<'_, K> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.leaf, &other.leaf) {
+            (None, None) => true,
+            (Some((guard, _)), Some((other_guard, _))) => {
+                guard.get_page_id() == other_guard.get_page_id() && self.index == other.index
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,9 +1,95 @@
-use crate::include::{common::config::{IndexPageType, PageId, INVALID_PAGE_ID}, storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, INTERNAL_PAGE_SLOT_CNT, KeyType}};
-use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl, BplusTreePageTrait};
+use crate::include::{common::config::{IndexPageType, PageId, INVALID_PAGE_ID, PAGE_SIZE}, storage::page::b_plus_tree_internal_page::{internal_page_slot_cnt, BplusTreeInternalPage, BplusTreeInternalPageImpl}};
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator};
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl, BplusTreePageTrait, BPLUS_TREE_PAGE_HEADER_SIZE, SLOT_SIZE};
+use crate::include::common::rid::Rid;
+use crate::storage::page::b_plus_tree_page::{filler_key, read_i32, read_rid, read_slot, read_u16, write_rid, write_slot, SLOTTED};
+use crate::storage::page::index_page::seal;
+use std::cmp::Ordering;
+use std::io;
 
-impl BplusTreeInternalPageImpl for BplusTreeInternalPage {
-    /// Init method after creating a new internal page.
-    /// Writes the necessary header info to a newly created page.
+// Header flag of pages whose separators carry rids
+const SEPARATOR_RIDS: u16 = 1;
+const RID_SIZE: usize = 8;
+const CHILD_SIZE: usize = 4;
+// Slotted pages keep their first child right after the header
+const SLOTS_OFFSET: usize = BPLUS_TREE_PAGE_HEADER_SIZE + CHILD_SIZE;
+
+impl<K: IndexKey> BplusTreeInternalPage<K> {
+    // used_bytes of a slotted page without separators
+    pub const SLOTTED_HEADER_SIZE: usize = SLOTS_OFFSET;
+    // A slotted page holds the most entries when their keys are empty,
+    // with one more child than entries
+    pub const SLOT_CNT: usize = if K::VARIABLE_LENGTH {
+        (PAGE_SIZE - SLOTS_OFFSET) / (SLOT_SIZE + CHILD_SIZE) + 1
+    } else {
+        internal_page_slot_cnt(K::SIZE)
+    };
+    // Slots of a page with separator rids, as in a non-unique index
+    pub const NON_UNIQUE_SLOT_CNT: usize = if K::VARIABLE_LENGTH {
+        (PAGE_SIZE - SLOTS_OFFSET) / (SLOT_SIZE + RID_SIZE + CHILD_SIZE) + 1
+    } else {
+        internal_page_slot_cnt(K::SIZE + RID_SIZE)
+    };
+
+    // Slots in the on-disk layout of this page
+    pub fn slot_cnt(&self) -> usize {
+        if self.separator_rids { Self::NON_UNIQUE_SLOT_CNT } else { Self::SLOT_CNT }
+    }
+
+    // Child page ids come first, the keys start after a full array of them
+    // and the rids, if any, after a full array of keys
+    fn keys_offset(slot_cnt: usize) -> usize {
+        BPLUS_TREE_PAGE_HEADER_SIZE + 4 * slot_cnt
+    }
+
+    // Bytes a separator takes in a slotted page: its slot, the key, its rid if the
+    // page has separator rids and the child right of it
+    pub fn slotted_entry_size(key: &K, separator_rids: bool) -> usize {
+        let rid_size = if separator_rids { RID_SIZE } else { 0 };
+        SLOT_SIZE + key.encoded_len() + rid_size + CHILD_SIZE
+    }
+
+    fn encode_slotted(&self, page: &mut [u8]) {
+        let used = self.used_bytes();
+        assert!(used <= PAGE_SIZE, "Internal page {} needs {} bytes", self.base_page.page_id, used);
+        page[BPLUS_TREE_PAGE_HEADER_SIZE..SLOTS_OFFSET].copy_from_slice(&self.page_id_array[0].to_le_bytes());
+        let mut end = PAGE_SIZE;
+        for index in 0..self.base_page.get_size() as usize {
+            let key = &self.key_array[index];
+            let len = key.encoded_len();
+            let pos = end - (Self::slotted_entry_size(key, self.separator_rids) - SLOT_SIZE);
+            key.encode(&mut page[pos..pos + len]);
+            let mut tail = pos + len;
+            if self.separator_rids {
+                write_rid(page, tail, self.rid_array[index]);
+                tail += RID_SIZE;
+            }
+            page[tail..tail + CHILD_SIZE].copy_from_slice(&self.page_id_array[index + 1].to_le_bytes());
+            write_slot(page, SLOTS_OFFSET + index * SLOT_SIZE, pos, len);
+            end = pos;
+        }
+    }
+
+    fn decode_slotted(&mut self, page: &[u8]) -> io::Result<()> {
+        let size = self.base_page.get_size() as usize;
+        let data_start = SLOTS_OFFSET + size * SLOT_SIZE;
+        let rid_size = if self.separator_rids { RID_SIZE } else { 0 };
+        self.page_id_array[0] = read_i32(page, BPLUS_TREE_PAGE_HEADER_SIZE);
+        for index in 0..size {
+            let (pos, len) = read_slot(page, SLOTS_OFFSET + index * SLOT_SIZE, data_start, K::SIZE, rid_size + CHILD_SIZE)?;
+            self.key_array[index] = K::decode(&page[pos..pos + len]);
+            if self.separator_rids {
+                self.rid_array[index] = read_rid(page, pos + len);
+            }
+            self.page_id_array[index + 1] = read_i32(page, pos + len + rid_size);
+        }
+        Ok(())
+    }
+}
+
+impl<K: IndexKey> BplusTreeInternalPageImpl<K> for BplusTreeInternalPage<K> {
+    // Init method after creating a new internal page.
+    // Writes the necessary header info to a newly created page.
     // fn init(&mut self, max_size: i32) {
     //     self.base_page = BplusTreePage::new(IndexPageType::INTERNAL_PAGE, 0, max_size);                 
     //     // self.base_page.page_type = IndexPageType::INTERNAL_PAGE;
@@ -17,16 +103,20 @@ impl BplusTreeInternalPageImpl for BplusTreeInternalPage {
     fn new(max_size: i32, page_id: PageId) -> Self {
         let base_page = BplusTreePage::new(IndexPageType::INTERNAL_PAGE, 0, max_size, page_id);                 
         
-        let key_array = [-1;INTERNAL_PAGE_SLOT_CNT];
-        let page_id_array = [INVALID_PAGE_ID; INTERNAL_PAGE_SLOT_CNT as usize];
+        // unused separators read as all ones, -1 for integer keys
+        let key_array = vec![filler_key(0xff); Self::SLOT_CNT];
+        let page_id_array = vec![INVALID_PAGE_ID; Self::SLOT_CNT];
+        let rid_array = vec![Rid::new(INVALID_PAGE_ID, 0); Self::SLOT_CNT];
         BplusTreeInternalPage {
             base_page,
             key_array,
-            page_id_array
+            page_id_array,
+            rid_array,
+            separator_rids: false,
         }
     }    
 
-    fn key_at(&self, index: i32) -> KeyType {
+    fn key_at(&self, index: i32) -> K {
         self.key_array[index as usize]
     }
 
@@ -36,7 +126,47 @@ impl BplusTreeInternalPageImpl for BplusTreeInternalPage {
 
 
 
-    fn set_key_at(&mut self, index: i32, key: KeyType) {
+    // Keys equal to a separator live in the subtree right of it
+    fn lookup_index<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> i32 {
+        let size = self.base_page.get_size() as usize;
+        self.key_array[..size].partition_point(|separator| comparator.compare(separator, &key) != Ordering::Greater) as i32
+    }
+
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> PageId {
+        self.page_id_array[self.lookup_index(key, comparator) as usize]
+    }
+
+    fn lookup_entry_index<C: KeyComparator<K> + ?Sized>(&self, key: K, rid: Option<Rid>, comparator: &C) -> i32 {
+        if !self.separator_rids {
+            return self.lookup_index(key, comparator);
+        }
+        // the number of separators at or before (key, rid)
+        let (mut low, mut high) = (0, self.base_page.get_size() as usize);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let ordering = comparator.compare(&self.key_array[mid], &key).then_with(|| match rid {
+                Some(rid) => self.rid_array[mid].cmp(&rid),
+                None => Ordering::Greater,
+            });
+            if ordering == Ordering::Greater {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        low as i32
+    }
+
+    fn remove_at(&mut self, index: i32) {
+        let (index, size) = (index as usize, self.base_page.get_size() as usize);
+        assert!(index > 0 && index <= size, "Can't remove child {} of an internal page with {} keys", index, size);
+        self.key_array.copy_within(index..size, index - 1);
+        self.rid_array.copy_within(index..size, index - 1);
+        self.page_id_array.copy_within(index + 1..size + 1, index);
+        self.base_page.size_ -= 1;
+    }
+
+    fn set_key_at(&mut self, index: i32, key: K) {
         self.key_array[index as usize] = key
     }
 
@@ -57,14 +187,14 @@ impl BplusTreeInternalPageImpl for BplusTreeInternalPage {
         let mut kstr = String::from("(");
         let mut first = true;
 
-        for i in 1..self.base_page.get_size() {
+        for i in 0..self.base_page.get_size() {
             let key = self.key_at(i);
             if first {
                 first = false;
             } else {
-                kstr.push_str(",");
+                kstr.push(',');
             }
-            kstr.push_str(&key.to_string()); // Convert i64 to String
+            kstr.push_str(&format!("{:?}", key));
         }
         kstr.push(')');
         kstr
@@ -74,9 +204,79 @@ impl BplusTreeInternalPageImpl for BplusTreeInternalPage {
         false
     }
 
+    fn encode(&self, page: &mut [u8]) {
+        let mut flags = if K::VARIABLE_LENGTH { SLOTTED } else { 0 };
+        if self.separator_rids {
+            flags |= SEPARATOR_RIDS;
+        }
+        self.base_page.encode_header(page, INVALID_PAGE_ID, K::SIZE, flags);
+        if K::VARIABLE_LENGTH {
+            self.encode_slotted(page);
+            seal(page);
+            return;
+        }
+        let size = self.base_page.get_size() as usize;
+        let keys_offset = Self::keys_offset(self.slot_cnt());
+        for index in 0..=size {
+            let pos = BPLUS_TREE_PAGE_HEADER_SIZE + index * 4;
+            page[pos..pos + 4].copy_from_slice(&self.page_id_array[index].to_le_bytes());
+        }
+        for index in 0..size {
+            let pos = keys_offset + index * K::SIZE;
+            self.key_array[index].encode(&mut page[pos..pos + K::SIZE]);
+        }
+        if self.separator_rids {
+            let rids_offset = keys_offset + self.slot_cnt() * K::SIZE;
+            for index in 0..size {
+                let pos = rids_offset + index * RID_SIZE;
+                let rid = self.rid_array[index];
+                page[pos..pos + 4].copy_from_slice(&rid.get_page_id().to_le_bytes());
+                page[pos + 4..pos + 8].copy_from_slice(&rid.get_slot_num().to_le_bytes());
+            }
+        }
+        seal(page);
+    }
+
+    fn decode(page: &[u8]) -> io::Result<Self> {
+        let (base_page, _) = BplusTreePage::decode(page)?;
+        if base_page.page_type != IndexPageType::INTERNAL_PAGE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Page {} is not an internal page", base_page.page_id)));
+        }
+        base_page.check_key_size(page, K::SIZE, K::VARIABLE_LENGTH)?;
+        let mut internal = Self::new(base_page.get_max_size(), base_page.page_id);
+        internal.separator_rids = read_u16(page, 30) & SEPARATOR_RIDS != 0;
+        // size keys and size + 1 children
+        let size = base_page.get_size() as usize;
+        let slot_cnt = internal.slot_cnt();
+        if size >= slot_cnt {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Internal page {} claims {} entries", base_page.page_id, size)));
+        }
+        internal.base_page = base_page;
+        if K::VARIABLE_LENGTH {
+            internal.decode_slotted(page)?;
+            return Ok(internal);
+        }
+        let keys_offset = Self::keys_offset(slot_cnt);
+        for index in 0..=size {
+            internal.page_id_array[index] = read_i32(page, BPLUS_TREE_PAGE_HEADER_SIZE + index * 4);
+        }
+        for index in 0..size {
+            let pos = keys_offset + index * K::SIZE;
+            internal.key_array[index] = K::decode(&page[pos..pos + K::SIZE]);
+        }
+        if internal.separator_rids {
+            let rids_offset = keys_offset + slot_cnt * K::SIZE;
+            for index in 0..size {
+                let pos = rids_offset + index * RID_SIZE;
+                internal.rid_array[index] = Rid::new(read_i32(page, pos), read_i32(page, pos + 4) as u32);
+            }
+        }
+        Ok(internal)
+    }
+
 }
 
-impl BplusTreePageTrait for BplusTreeInternalPage {
+impl<K: IndexKey> BplusTreePageTrait for BplusTreeInternalPage<K> {
     fn is_leaf(&self) -> bool {
         false 
     }    
@@ -88,4 +288,18 @@ impl BplusTreePageTrait for BplusTreeInternalPage {
     fn get_size(&self) -> i32 {
         self.base_page.get_size()
     }
-}
\ No newline at end of file
+
+    fn min_size(&self) -> i32 {
+        self.base_page.get_min_size()
+    }
+
+    fn used_bytes(&self) -> usize {
+        let separators = &self.key_array[..self.base_page.get_size() as usize];
+        SLOTS_OFFSET + separators.iter().map(|key| Self::slotted_entry_size(key, self.separator_rids)).sum::<usize>()
+    }
+
+    fn max_entry_bytes(&self) -> usize {
+        let rid_size = if self.separator_rids { RID_SIZE } else { 0 };
+        SLOT_SIZE + K::SIZE + rid_size + CHILD_SIZE
+    }
+}
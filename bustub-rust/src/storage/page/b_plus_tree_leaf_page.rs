@@ -1,26 +1,134 @@
-use crate::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl, LEAF_PAGE_HEADER_SIZE, LEAF_PAGE_SLOT_CNT};
+use crate::include::storage::page::b_plus_tree_leaf_page::{leaf_page_slot_cnt, BplusTreeLeafPage, BplusTreeLeafPageImpl, RID_SIZE};
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator};
 use crate::include::storage::page::b_plus_tree_page::BplusTreePage;
-use crate::include::common::config::{IndexPageType, PageId, ValueType, INVALID_PAGE_ID};
+use crate::include::common::config::{IndexPageType, PageId, ValueType, INVALID_PAGE_ID, PAGE_SIZE};
 use crate::include::common::rid::Rid;
-use crate::include::storage::page::b_plus_tree_internal_page::KeyType;
-use crate::include::storage::page::b_plus_tree_page::{BplusTreePageImpl, BplusTreePageTrait};
-use crate::include::storage::page::page::Page;
+use crate::include::storage::page::b_plus_tree_page::{BplusTreePageImpl, BplusTreePageTrait, BPLUS_TREE_PAGE_HEADER_SIZE, SLOT_SIZE};
+use crate::storage::page::b_plus_tree_page::{filler_key, read_i32, read_rid, read_slot, read_u16, write_rid, write_slot, SLOTTED};
+use crate::storage::page::index_page::seal;
+use std::cmp::Ordering;
+use std::io;
 
+// Header flag of leaves storing the prefix their keys share once
+const PREFIX_COMPRESSED: u16 = 4;
 
+impl<K: IndexKey> BplusTreeLeafPage<K> {
+    // A slotted page holds the most entries when their keys are empty
+    pub const SLOT_CNT: usize = if K::VARIABLE_LENGTH {
+        (PAGE_SIZE - BPLUS_TREE_PAGE_HEADER_SIZE) / (SLOT_SIZE + RID_SIZE)
+    } else {
+        leaf_page_slot_cnt(K::SIZE)
+    };
+    // key, rid page id, rid slot
+    const ENTRY_SIZE: usize = K::SIZE + 8;
 
-impl BplusTreeLeafPageImpl for BplusTreeLeafPage {
+    // Bytes an entry of the key takes in a slotted page: its slot, the key and the rid
+    pub fn slotted_entry_size(key: &K) -> usize {
+        SLOT_SIZE + key.encoded_len() + RID_SIZE
+    }
+
+    // used_bytes of slotted pages holding the first 0, 1, 2, ... of the keys. With
+    // prefix compression the prefix they share is stored once.
+    pub fn slotted_sizes<'a>(keys: impl IntoIterator<Item = &'a K>, prefix_compression: bool) -> Vec<usize> {
+        let header_size = BPLUS_TREE_PAGE_HEADER_SIZE + if prefix_compression { 2 } else { 0 };
+        let mut sizes = vec![header_size];
+        let mut keys = keys.into_iter();
+        let Some(first) = keys.next() else {
+            return sizes;
+        };
+        let (mut prefix_len, mut entry_bytes) = (first.encoded_len(), Self::slotted_entry_size(first));
+        sizes.push(header_size + entry_bytes);
+        for key in keys {
+            prefix_len = prefix_len.min(first.common_prefix_len(key));
+            entry_bytes += Self::slotted_entry_size(key);
+            let saved = if prefix_compression { (sizes.len() - 1) * prefix_len } else { 0 };
+            sizes.push(header_size + entry_bytes - saved);
+        }
+        sizes
+    }
+
+    // used_bytes once the key is inserted too
+    pub fn used_bytes_with(&self, key: &K) -> usize {
+        let keys = &self.key_array[..self.base_page.get_size() as usize];
+        Self::slotted_sizes(keys.iter().chain(std::iter::once(key)), self.prefix_compression).pop().unwrap()
+    }
+
+    // Bytes of the prefix the keys share, 0 unless the page is prefix compressed
+    pub fn common_prefix_len(&self) -> usize {
+        let keys = &self.key_array[..self.base_page.get_size() as usize];
+        match keys.first() {
+            Some(first) if self.prefix_compression => keys.iter().map(|key| first.common_prefix_len(key)).min().unwrap(),
+            _ => 0,
+        }
+    }
+
+    fn encode_slotted(&self, page: &mut [u8]) {
+        let used = self.used_bytes();
+        assert!(used <= PAGE_SIZE, "Leaf page {} needs {} bytes", self.base_page.page_id, used);
+        let encoded = |key: &K| {
+            let mut bytes = vec![0; key.encoded_len()];
+            key.encode(&mut bytes);
+            bytes
+        };
+        let size = self.base_page.get_size() as usize;
+        let prefix_len = self.common_prefix_len();
+        let mut slots = BPLUS_TREE_PAGE_HEADER_SIZE;
+        if self.prefix_compression {
+            let first = if size > 0 { encoded(&self.key_array[0]) } else { Vec::new() };
+            page[slots..slots + 2].copy_from_slice(&(prefix_len as u16).to_le_bytes());
+            page[slots + 2..slots + 2 + prefix_len].copy_from_slice(&first[..prefix_len]);
+            slots += 2 + prefix_len;
+        }
+        let mut end = PAGE_SIZE;
+        for index in 0..size {
+            let bytes = encoded(&self.key_array[index]);
+            let suffix = &bytes[prefix_len..];
+            let pos = end - suffix.len() - RID_SIZE;
+            page[pos..pos + suffix.len()].copy_from_slice(suffix);
+            write_rid(page, pos + suffix.len(), self.rid_array[index]);
+            write_slot(page, slots + index * SLOT_SIZE, pos, suffix.len());
+            end = pos;
+        }
+    }
+
+    fn decode_slotted(&mut self, page: &[u8]) -> io::Result<()> {
+        let size = self.base_page.get_size() as usize;
+        let mut slots = BPLUS_TREE_PAGE_HEADER_SIZE;
+        let mut prefix: &[u8] = &[];
+        if self.prefix_compression {
+            let prefix_len = read_u16(page, slots) as usize;
+            if prefix_len > K::SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Leaf page {} claims a {} byte key prefix", self.base_page.page_id, prefix_len),
+                ));
+            }
+            prefix = &page[slots + 2..slots + 2 + prefix_len];
+            slots += 2 + prefix_len;
+        }
+        let data_start = slots + size * SLOT_SIZE;
+        for index in 0..size {
+            let (pos, len) = read_slot(page, slots + index * SLOT_SIZE, data_start, K::SIZE - prefix.len(), RID_SIZE)?;
+            self.key_array[index] = K::decode(&[prefix, &page[pos..pos + len]].concat());
+            self.rid_array[index] = read_rid(page, pos + len);
+        }
+        Ok(())
+    }
+}
+
+impl<K: IndexKey> BplusTreeLeafPageImpl<K> for BplusTreeLeafPage<K> {
     fn new(max_size: i32, page_id: PageId) -> Self {
         let base_page = BplusTreePage::new(IndexPageType::LEAF_PAGE, 0, max_size, page_id);                 
         let next_page_id = INVALID_PAGE_ID;
-        let key_array = [0;LEAF_PAGE_SLOT_CNT];
-        let leaf_slot_count = LEAF_PAGE_SLOT_CNT;
-        let rid_array = [Rid::new(INVALID_PAGE_ID, 0); LEAF_PAGE_SLOT_CNT as usize];
+        let key_array = vec![filler_key(0); Self::SLOT_CNT];
+        let rid_array = vec![Rid::new(INVALID_PAGE_ID, 0); Self::SLOT_CNT];
 
         BplusTreeLeafPage {
             base_page,
             next_page_id,
             key_array,
-            rid_array
+            rid_array,
+            prefix_compression: false,
         }
     }
 
@@ -32,7 +140,7 @@ impl BplusTreeLeafPageImpl for BplusTreeLeafPage {
         self.next_page_id = page_id
     }
 
-    fn key_at(&self, index: i32) -> KeyType {
+    fn key_at(&self, index: i32) -> K {
         self.key_array[index as usize]
     }
 
@@ -40,20 +148,20 @@ impl BplusTreeLeafPageImpl for BplusTreeLeafPage {
         let mut kstr = String::from("(");
         let mut first = true;
 
-        for i in 1..self.base_page.get_size() {
+        for i in 0..self.base_page.get_size() {
             let key = self.key_at(i);
             if first {
                 first = false;
             } else {
-                kstr.push_str(",");
+                kstr.push(',');
             }
-            kstr.push_str(&key.to_string()); // Convert i64 to String
+            kstr.push_str(&format!("{:?}", key));
         }
         kstr.push(')');
         kstr
     }
 
-    fn insert(&mut self, index: i32, key: KeyType, value: ValueType) -> bool {
+    fn insert(&mut self, index: i32, key: K, value: ValueType) -> bool {
 
         // check if the index isn't full 
         if index < 0 || index > self.base_page.get_size() || self.base_page.get_size() >= self.base_page.get_max_size() {
@@ -71,35 +179,120 @@ impl BplusTreeLeafPageImpl for BplusTreeLeafPage {
             ValueType::Rid(rid) => rid,
             _ => panic!("Invalid value type for leaf page"),
         };
-        println!("Setting the size");
         self.base_page.set_size(self.base_page.get_size() + 1);
-        println!("size is {}", self.base_page.get_size() );
         true           
 
     }
 
-    fn find_insert_position(&self, key: KeyType) -> i32 {
+    fn find_insert_position<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> i32 {
         // binary search
         let mut left = 0;
         let mut right = self.get_size() - 1; 
         while left <= right {
             // calcuate the mid point
             let mid = left + (right -left) / 2;
-            if key < self.key_array[mid as usize] {
+            if comparator.compare(&key, &self.key_array[mid as usize]) == Ordering::Less {
                 right = mid - 1;
             } else {
                 left = mid + 1;
             }
         }
-        left as i32
+        left
     }
 
     fn is_leaf(&self) -> bool {
         true
     }
+
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: K, comparator: &C) -> Option<Rid> {
+        let size = self.base_page.get_size() as usize;
+        let index = self.key_array[..size].partition_point(|k| comparator.compare(k, &key) == Ordering::Less);
+        (index < size && comparator.compare(&self.key_array[index], &key) == Ordering::Equal).then(|| self.rid_array[index])
+    }
+
+    fn remove<C: KeyComparator<K> + ?Sized>(&mut self, key: K, comparator: &C) -> bool {
+        let size = self.base_page.get_size() as usize;
+        let index = self.key_array[..size].partition_point(|k| comparator.compare(k, &key) == Ordering::Less);
+        if index == size || comparator.compare(&self.key_array[index], &key) != Ordering::Equal {
+            return false
+        }
+        self.remove_at(index as i32);
+        true
+    }
+
+    fn search_entry<C: KeyComparator<K> + ?Sized>(&self, key: K, rid: Rid, comparator: &C) -> Result<i32, i32> {
+        let (mut low, mut high) = (0, self.base_page.get_size() as usize);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match comparator.compare(&self.key_array[mid], &key).then(self.rid_array[mid].cmp(&rid)) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid as i32),
+            }
+        }
+        Err(low as i32)
+    }
+
+    fn remove_at(&mut self, index: i32) {
+        let (index, size) = (index as usize, self.base_page.get_size() as usize);
+        assert!(index < size, "Can't remove entry {} of a leaf with {} entries", index, size);
+        self.key_array.copy_within(index + 1..size, index);
+        self.rid_array.copy_within(index + 1..size, index);
+        self.base_page.size_ -= 1;
+    }
+
+    fn encode(&self, page: &mut [u8]) {
+        let mut flags = if K::VARIABLE_LENGTH { SLOTTED } else { 0 };
+        // only slotted leaves compress their keys
+        if K::VARIABLE_LENGTH && self.prefix_compression {
+            flags |= PREFIX_COMPRESSED;
+        }
+        self.base_page.encode_header(page, self.next_page_id, K::SIZE, flags);
+        if K::VARIABLE_LENGTH {
+            self.encode_slotted(page);
+            seal(page);
+            return;
+        }
+        for index in 0..self.base_page.get_size() as usize {
+            let pos = BPLUS_TREE_PAGE_HEADER_SIZE + index * Self::ENTRY_SIZE;
+            let rid = self.rid_array[index];
+            let rid_pos = pos + K::SIZE;
+            self.key_array[index].encode(&mut page[pos..rid_pos]);
+            page[rid_pos..rid_pos + 4].copy_from_slice(&rid.get_page_id().to_le_bytes());
+            page[rid_pos + 4..rid_pos + 8].copy_from_slice(&rid.get_slot_num().to_le_bytes());
+        }
+        seal(page);
+    }
+
+    fn decode(page: &[u8]) -> io::Result<Self> {
+        let (base_page, next_page_id) = BplusTreePage::decode(page)?;
+        if base_page.page_type != IndexPageType::LEAF_PAGE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Page {} is not a leaf page", base_page.page_id)));
+        }
+        base_page.check_key_size(page, K::SIZE, K::VARIABLE_LENGTH)?;
+        let size = base_page.get_size() as usize;
+        if size > Self::SLOT_CNT {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Leaf page {} claims {} entries", base_page.page_id, size)));
+        }
+        let mut leaf = Self::new(base_page.get_max_size(), base_page.page_id);
+        leaf.base_page = base_page;
+        leaf.next_page_id = next_page_id;
+        if K::VARIABLE_LENGTH {
+            leaf.prefix_compression = read_u16(page, 30) & PREFIX_COMPRESSED != 0;
+            leaf.decode_slotted(page)?;
+            return Ok(leaf);
+        }
+        for index in 0..size {
+            let pos = BPLUS_TREE_PAGE_HEADER_SIZE + index * Self::ENTRY_SIZE;
+            let rid_pos = pos + K::SIZE;
+            leaf.key_array[index] = K::decode(&page[pos..rid_pos]);
+            leaf.rid_array[index] = Rid::new(read_i32(page, rid_pos), read_i32(page, rid_pos + 4) as u32);
+        }
+        Ok(leaf)
+    }
 }
 
-impl BplusTreePageTrait for BplusTreeLeafPage {
+impl<K: IndexKey> BplusTreePageTrait for BplusTreeLeafPage<K> {
     fn is_leaf(&self) -> bool {
         true
     }
@@ -111,4 +304,19 @@ impl BplusTreePageTrait for BplusTreeLeafPage {
     fn get_size(&self) -> i32 {
         self.base_page.get_size()
     }
+
+    fn min_size(&self) -> i32 {
+        self.base_page.get_min_size()
+    }
+
+    fn used_bytes(&self) -> usize {
+        let keys = &self.key_array[..self.base_page.get_size() as usize];
+        Self::slotted_sizes(keys, self.prefix_compression).pop().unwrap()
+    }
+
+    // A key that shares none of the prefix has every other key store it again
+    fn max_entry_bytes(&self) -> usize {
+        let prefix_bytes = self.common_prefix_len() * self.base_page.get_size() as usize;
+        SLOT_SIZE + K::SIZE + RID_SIZE + prefix_bytes
+    }
 }
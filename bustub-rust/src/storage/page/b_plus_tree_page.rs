@@ -1,10 +1,63 @@
 use crate::include::{common::config::IndexPageType, storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl}};
-use crate::include::common::config::{PageId, INVALID_PAGE_ID};
+use crate::include::common::config::{PageId, INVALID_PAGE_ID, PAGE_SIZE};
+use crate::include::storage::page::b_plus_tree_page::{BPLUS_TREE_PAGE_MAGIC, BPLUS_TREE_PAGE_VERSION, SLOT_SIZE};
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::IndexKey;
+use crate::storage::page::index_page::{check_checksum, seal, write_page_header};
+use std::io;
+
+// Header flag of slotted pages
+pub(crate) const SLOTTED: u16 = 2;
+
+pub(crate) fn read_u16(page: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes(page[pos..pos + 2].try_into().unwrap())
+}
+
+pub(crate) fn read_i32(page: &[u8], pos: usize) -> i32 {
+    i32::from_le_bytes(page[pos..pos + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_i64(page: &[u8], pos: usize) -> i64 {
+    i64::from_le_bytes(page[pos..pos + 8].try_into().unwrap())
+}
+
+pub(crate) fn write_rid(page: &mut [u8], pos: usize, rid: Rid) {
+    page[pos..pos + 4].copy_from_slice(&rid.get_page_id().to_le_bytes());
+    page[pos + 4..pos + 8].copy_from_slice(&rid.get_slot_num().to_le_bytes());
+}
+
+pub(crate) fn read_rid(page: &[u8], pos: usize) -> Rid {
+    Rid::new(read_i32(page, pos), read_i32(page, pos + 4) as u32)
+}
+
+// Points the slot at slot_pos to an entry at pos whose key has len bytes
+pub(crate) fn write_slot(page: &mut [u8], slot_pos: usize, pos: usize, len: usize) {
+    page[slot_pos..slot_pos + 2].copy_from_slice(&(pos as u16).to_le_bytes());
+    page[slot_pos + 2..slot_pos + SLOT_SIZE].copy_from_slice(&(len as u16).to_le_bytes());
+}
+
+// Reads the (entry offset, key length) of a slot. The key may have at most
+// max_len bytes, and it and the tail bytes after it have to lie between
+// data_start, the end of the slot directory, and the end of the page.
+pub(crate) fn read_slot(page: &[u8], slot_pos: usize, data_start: usize, max_len: usize, tail: usize) -> io::Result<(usize, usize)> {
+    let (pos, len) = (read_u16(page, slot_pos) as usize, read_u16(page, slot_pos + 2) as usize);
+    if len > max_len || pos < data_start || pos + len + tail > PAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Slot at {} points at a {} byte key at {}, outside the entries of the page", slot_pos, len, pos),
+        ));
+    }
+    Ok((pos, len))
+}
+
+// A key whose bytes are all `byte`, fills the unused slots of decoded pages
+pub(crate) fn filler_key<K: IndexKey>(byte: u8) -> K {
+    K::decode(&vec![byte; K::SIZE])
+}
 
 impl BplusTreePage {
     pub fn new(page_type: IndexPageType, size: i32, max_size: i32, page_id: PageId)-> Self {
         let size_ = size;
-        let max_size = max_size;
         BplusTreePage {
             page_type,
             size_,
@@ -12,6 +65,81 @@ impl BplusTreePage {
             page_id
         }
     }
+
+    /// Clears `page` and writes the common header. The checksum is left zero,
+    /// `seal` fills it in once the entries are written.
+    pub(crate) fn encode_header(&self, page: &mut [u8], next_page_id: PageId, key_size: usize, flags: u16) {
+        let page_type = match self.page_type {
+            IndexPageType::INVALID_INDEX_PAGE => 0u16,
+            IndexPageType::LEAF_PAGE => 1,
+            IndexPageType::INTERNAL_PAGE => 2,
+            IndexPageType::HEADER_PAGE => 3,
+        };
+        write_page_header(page, BPLUS_TREE_PAGE_MAGIC, BPLUS_TREE_PAGE_VERSION, page_type);
+        page[12..16].copy_from_slice(&self.size_.to_le_bytes());
+        page[16..20].copy_from_slice(&self.max_size.to_le_bytes());
+        page[20..24].copy_from_slice(&self.page_id.to_le_bytes());
+        page[24..28].copy_from_slice(&next_page_id.to_le_bytes());
+        page[28..30].copy_from_slice(&(key_size as u16).to_le_bytes());
+        page[30..32].copy_from_slice(&flags.to_le_bytes());
+    }
+
+    /// Fails unless the entries of an encoded page hold keys of `key_size` bytes,
+    /// in the slotted format for variable-length keys
+    pub(crate) fn check_key_size(&self, page: &[u8], key_size: usize, slotted: bool) -> io::Result<()> {
+        if (read_u16(page, 30) & SLOTTED != 0) != slotted {
+            let (found, expected) = if slotted { ("fixed-width", "variable-length") } else { ("variable-length", "fixed-width") };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("B+ tree page {} holds {} keys, expected {} keys", self.page_id, found, expected),
+            ));
+        }
+        let stored = read_u16(page, 28) as usize;
+        if stored != key_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("B+ tree page {} holds {} byte keys, expected {} byte keys", self.page_id, stored, key_size),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads the common header of an encoded page after checking its magic,
+    /// version and checksum. Returns the header and the next page id.
+    pub fn decode(page: &[u8]) -> io::Result<(Self, PageId)> {
+        let header = Self::peek_header(page)?;
+        check_checksum(page, "B+ tree")?;
+        Ok(header)
+    }
+
+    /// Like `decode` but skips the checksum, for callers that only route on
+    /// the page type and decode the page in full before trusting its entries
+    pub fn peek_header(page: &[u8]) -> io::Result<(Self, PageId)> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        if page.len() != PAGE_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Expected a {} byte page, got {} bytes", PAGE_SIZE, page.len())));
+        }
+        let magic = u32::from_le_bytes(page[0..4].try_into().unwrap());
+        if magic != BPLUS_TREE_PAGE_MAGIC {
+            return Err(invalid(format!("Not a B+ tree page, magic is {:#010x}", magic)));
+        }
+        let version = read_u16(page, 4);
+        if version != BPLUS_TREE_PAGE_VERSION {
+            return Err(invalid(format!("Unsupported B+ tree page version {}", version)));
+        }
+        let page_type = match read_u16(page, 6) {
+            1 => IndexPageType::LEAF_PAGE,
+            2 => IndexPageType::INTERNAL_PAGE,
+            3 => IndexPageType::HEADER_PAGE,
+            other => return Err(invalid(format!("Unknown B+ tree page type {}", other))),
+        };
+        let (size, max_size, page_id) = (read_i32(page, 12), read_i32(page, 16), read_i32(page, 20));
+        if size < 0 || max_size < 0 {
+            return Err(invalid(format!("B+ tree page {} has size {} and max size {}", page_id, size, max_size)));
+        }
+        let header = BplusTreePage::new(page_type, size, max_size, page_id);
+        Ok((header, read_i32(page, 24)))
+    }
 }
 
 impl BplusTreePageImpl for BplusTreePage {
@@ -47,8 +175,13 @@ impl BplusTreePageImpl for BplusTreePage {
     }
 
     fn get_min_size(&self) -> i32 {
-        // minimum size should atleast the half. 
-        (self.max_size as f64 / 2.0 ).ceil() as i32
+        // a leaf keeps at least half its entries, an internal page half its
+        // max size + 1 children, which is half its keys rounded down
+        if self.is_leaf_page() {
+            (self.max_size as f64 / 2.0 ).ceil() as i32
+        } else {
+            self.max_size / 2
+        }
     }
 
     fn set_max_size(&mut self, max_size: i32) {
@@ -57,16 +190,42 @@ impl BplusTreePageImpl for BplusTreePage {
 }
 
 
-#[repr(C)]
 #[derive(Debug, PartialEq)]
 pub struct BPlusTreeHeaderPage {
+    pub page_id: PageId,
     pub root_page_id: PageId,
 }
 
 impl BPlusTreeHeaderPage {
-    pub fn new() -> Self {
+    const ROOT_PAGE_ID_OFFSET: usize = 32;
+
+    // The header of an empty tree
+    pub fn new(page_id: PageId) -> Self {
         BPlusTreeHeaderPage {
+            page_id,
             root_page_id: INVALID_PAGE_ID,
         }
     }
-}
\ No newline at end of file
+
+    pub fn encode(&self, page: &mut [u8]) {
+        let header = BplusTreePage::new(IndexPageType::HEADER_PAGE, 0, 0, self.page_id);
+        header.encode_header(page, INVALID_PAGE_ID, 0, 0);
+        let pos = Self::ROOT_PAGE_ID_OFFSET;
+        page[pos..pos + 4].copy_from_slice(&self.root_page_id.to_le_bytes());
+        seal(page);
+    }
+
+    pub fn decode(page: &[u8]) -> io::Result<Self> {
+        let (header, _) = BplusTreePage::decode(page)?;
+        if header.page_type != IndexPageType::HEADER_PAGE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Page {} is a {:?}, not a B+ tree header page", header.page_id, header.page_type),
+            ));
+        }
+        Ok(BPlusTreeHeaderPage {
+            page_id: header.page_id,
+            root_page_id: read_i32(page, Self::ROOT_PAGE_ID_OFFSET),
+        })
+    }
+}
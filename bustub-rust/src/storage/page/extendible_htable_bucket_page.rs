@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+use std::io;
+
+use crate::include::common::config::PageId;
+use crate::include::common::rid::Rid;
+use crate::include::storage::index::generic_key::{IndexKey, KeyComparator};
+use crate::include::storage::page::b_plus_tree_leaf_page::RID_SIZE;
+use crate::include::storage::page::extendible_htable_bucket_page::{
+    bucket_page_slot_cnt, ExtendibleHTableBucketPage, ExtendibleHTableBucketPageImpl, HTABLE_BUCKET_PAGE_HEADER_SIZE, HTABLE_BUCKET_PAGE_TYPE,
+};
+use crate::include::storage::page::extendible_htable_header_page::{HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION};
+use crate::storage::page::b_plus_tree_page::{read_rid, read_u16, write_rid};
+use crate::storage::page::index_page::{check_page_header, seal, write_page_header};
+
+impl<K: IndexKey> ExtendibleHTableBucketPage<K> {
+    pub const SLOT_CNT: usize = bucket_page_slot_cnt(K::SIZE);
+    // key length, key, rid page id, rid slot
+    const ENTRY_SIZE: usize = 2 + K::SIZE + RID_SIZE;
+}
+
+impl<K: IndexKey> ExtendibleHTableBucketPageImpl<K> for ExtendibleHTableBucketPage<K> {
+    fn new(max_size: u32, page_id: PageId) -> Self {
+        assert!(max_size >= 1 && max_size as usize <= Self::SLOT_CNT,
+            "Bucket max size {} is outside the 1..={} slots of a bucket with {} byte keys", max_size, Self::SLOT_CNT, K::SIZE);
+        ExtendibleHTableBucketPage { page_id, max_size, entries: Vec::new() }
+    }
+
+    fn lookup<C: KeyComparator<K> + ?Sized>(&self, key: &K, comparator: &C) -> Option<Rid> {
+        self.entries.iter().find(|(stored, _)| comparator.compare(stored, key) == Ordering::Equal).map(|&(_, rid)| rid)
+    }
+
+    fn insert(&mut self, key: K, rid: Rid) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.entries.push((key, rid));
+        true
+    }
+
+    fn remove<C: KeyComparator<K> + ?Sized>(&mut self, key: &K, rid: Rid, comparator: &C) -> bool {
+        let position = self.entries.iter().position(|(stored, stored_rid)| comparator.compare(stored, key) == Ordering::Equal && *stored_rid == rid);
+        match position {
+            // order doesn't matter, the last entry fills the gap
+            Some(index) => {
+                self.entries.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn size(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    fn is_full(&self) -> bool {
+        self.size() >= self.max_size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn encode(&self, page: &mut [u8]) {
+        write_page_header(page, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_BUCKET_PAGE_TYPE);
+        page[12..16].copy_from_slice(&self.size().to_le_bytes());
+        page[16..20].copy_from_slice(&self.max_size.to_le_bytes());
+        page[20..22].copy_from_slice(&(K::SIZE as u16).to_le_bytes());
+        for (index, (key, rid)) in self.entries.iter().enumerate() {
+            let pos = HTABLE_BUCKET_PAGE_HEADER_SIZE + index * Self::ENTRY_SIZE;
+            let len = key.encoded_len();
+            page[pos..pos + 2].copy_from_slice(&(len as u16).to_le_bytes());
+            key.encode(&mut page[pos + 2..pos + 2 + len]);
+            write_rid(page, pos + 2 + K::SIZE, *rid);
+        }
+        seal(page);
+    }
+
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        check_page_header(page, page_id, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_BUCKET_PAGE_TYPE, "hash table bucket")?;
+        let read_u32 = |pos: usize| u32::from_le_bytes(page[pos..pos + 4].try_into().unwrap());
+        let key_size = read_u16(page, 20) as usize;
+        if key_size != K::SIZE {
+            return Err(invalid(format!("Bucket page {} holds {} byte keys, expected {} byte keys", page_id, key_size, K::SIZE)));
+        }
+        let (size, max_size) = (read_u32(12), read_u32(16));
+        if max_size == 0 || max_size as usize > Self::SLOT_CNT || size > max_size {
+            return Err(invalid(format!("Bucket page {} has size {} and max size {}", page_id, size, max_size)));
+        }
+        let mut entries = Vec::with_capacity(size as usize);
+        for index in 0..size as usize {
+            let pos = HTABLE_BUCKET_PAGE_HEADER_SIZE + index * Self::ENTRY_SIZE;
+            let len = read_u16(page, pos) as usize;
+            // fixed-width keys always take their whole slot
+            if len > K::SIZE || (!K::VARIABLE_LENGTH && len != K::SIZE) {
+                return Err(invalid(format!("Entry {} of bucket page {} claims a {} byte key", index, page_id, len)));
+            }
+            entries.push((K::decode(&page[pos + 2..pos + 2 + len]), read_rid(page, pos + 2 + K::SIZE)));
+        }
+        Ok(ExtendibleHTableBucketPage { page_id, max_size, entries })
+    }
+}
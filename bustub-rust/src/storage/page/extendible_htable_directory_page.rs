@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::include::common::config::{PageId, INVALID_PAGE_ID};
+use crate::include::storage::page::extendible_htable_directory_page::{
+    ExtendibleHTableDirectoryPage, ExtendibleHTableDirectoryPageImpl, HTABLE_DIRECTORY_ARRAY_SIZE, HTABLE_DIRECTORY_MAX_DEPTH,
+    HTABLE_DIRECTORY_PAGE_TYPE,
+};
+use crate::include::storage::page::extendible_htable_header_page::{HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION};
+use crate::storage::page::b_plus_tree_page::read_i32;
+use crate::storage::page::index_page::{check_page_header, seal, write_page_header};
+
+// Offsets of the local depths and the bucket page ids
+const LOCAL_DEPTHS_OFFSET: usize = 20;
+const BUCKET_PAGE_IDS_OFFSET: usize = LOCAL_DEPTHS_OFFSET + HTABLE_DIRECTORY_ARRAY_SIZE;
+
+impl ExtendibleHTableDirectoryPage {
+    // A directory of global depth 0 whose single slot points at the bucket,
+    // max_depth is at most HTABLE_DIRECTORY_MAX_DEPTH
+    pub fn new(max_depth: u32, bucket_page_id: PageId) -> Self {
+        assert!(max_depth <= HTABLE_DIRECTORY_MAX_DEPTH, "Directory max depth {} exceeds {}", max_depth, HTABLE_DIRECTORY_MAX_DEPTH);
+        let mut bucket_page_ids = [INVALID_PAGE_ID; HTABLE_DIRECTORY_ARRAY_SIZE];
+        bucket_page_ids[0] = bucket_page_id;
+        ExtendibleHTableDirectoryPage {
+            page_type: HTABLE_DIRECTORY_PAGE_TYPE,
+            max_depth,
+            global_depth: 0,
+            local_depths: [0; HTABLE_DIRECTORY_ARRAY_SIZE],
+            bucket_page_ids,
+        }
+    }
+}
+
+impl ExtendibleHTableDirectoryPageImpl for ExtendibleHTableDirectoryPage {
+    fn hash_to_bucket_index(&self, hash: u32) -> usize {
+        (hash & self.get_global_depth_mask()) as usize
+    }
+
+    fn get_bucket_page_id(&self, bucket_index: usize) -> PageId {
+        self.bucket_page_ids[bucket_index]
+    }
+
+    fn set_bucket_page_id(&mut self, bucket_index: usize, page_id: PageId) {
+        self.bucket_page_ids[bucket_index] = page_id;
+    }
+
+    fn get_split_image_index(&self, bucket_index: usize) -> usize {
+        // the highest of the bits the bucket's keys share tells it from its image
+        match self.get_local_depth(bucket_index) {
+            0 => bucket_index,
+            local_depth => bucket_index ^ (1 << (local_depth - 1)),
+        }
+    }
+
+    fn get_global_depth(&self) -> u32 {
+        self.global_depth
+    }
+
+    fn get_max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    fn get_global_depth_mask(&self) -> u32 {
+        (1 << self.global_depth) - 1
+    }
+
+    fn get_local_depth_mask(&self, bucket_index: usize) -> u32 {
+        (1 << self.get_local_depth(bucket_index)) - 1
+    }
+
+    fn get_local_depth(&self, bucket_index: usize) -> u32 {
+        self.local_depths[bucket_index] as u32
+    }
+
+    fn set_local_depth(&mut self, bucket_index: usize, local_depth: u32) {
+        self.local_depths[bucket_index] = local_depth as u8;
+    }
+
+    fn incr_global_depth(&mut self) {
+        assert!(self.global_depth < self.max_depth, "Directory is already at its max depth {}", self.max_depth);
+        let size = self.size();
+        self.local_depths.copy_within(..size, size);
+        self.bucket_page_ids.copy_within(..size, size);
+        self.global_depth += 1;
+    }
+
+    fn decr_global_depth(&mut self) {
+        assert!(self.can_shrink(), "Directory of global depth {} can't shrink", self.global_depth);
+        self.global_depth -= 1;
+        let size = self.size();
+        self.local_depths[size..size * 2].fill(0);
+        self.bucket_page_ids[size..size * 2].fill(INVALID_PAGE_ID);
+    }
+
+    fn can_shrink(&self) -> bool {
+        self.global_depth > 0 && self.local_depths[..self.size()].iter().all(|&local_depth| (local_depth as u32) < self.global_depth)
+    }
+
+    fn size(&self) -> usize {
+        1 << self.global_depth
+    }
+
+    fn max_size(&self) -> usize {
+        1 << self.max_depth
+    }
+
+    fn verify_integrity(&self) -> io::Result<()> {
+        let invalid = |msg: String| Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        if self.max_depth > HTABLE_DIRECTORY_MAX_DEPTH || self.global_depth > self.max_depth {
+            return invalid(format!("Directory has global depth {} and max depth {}", self.global_depth, self.max_depth));
+        }
+        // (local depth, slots) of every bucket
+        let mut buckets: HashMap<PageId, (u32, Vec<usize>)> = HashMap::new();
+        for bucket_index in 0..self.size() {
+            let (page_id, local_depth) = (self.bucket_page_ids[bucket_index], self.get_local_depth(bucket_index));
+            if page_id == INVALID_PAGE_ID || local_depth > self.global_depth {
+                return invalid(format!("Slot {} points at page {} with local depth {}", bucket_index, page_id, local_depth));
+            }
+            let (depth, slots) = buckets.entry(page_id).or_insert((local_depth, Vec::new()));
+            if *depth != local_depth {
+                return invalid(format!("Slots of bucket page {} disagree on its local depth", page_id));
+            }
+            slots.push(bucket_index);
+        }
+        for (page_id, (local_depth, slots)) in buckets {
+            let mask = (1 << local_depth) - 1;
+            if slots.len() != 1 << (self.global_depth - local_depth) || slots.iter().any(|&slot| slot & mask != slots[0] & mask) {
+                return invalid(format!("Bucket page {} of local depth {} is pointed at by slots {:?}", page_id, local_depth, slots));
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&self, page: &mut [u8]) {
+        write_page_header(page, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_DIRECTORY_PAGE_TYPE);
+        page[12..16].copy_from_slice(&self.max_depth.to_le_bytes());
+        page[16..20].copy_from_slice(&self.global_depth.to_le_bytes());
+        page[LOCAL_DEPTHS_OFFSET..BUCKET_PAGE_IDS_OFFSET].copy_from_slice(&self.local_depths);
+        for (index, page_id) in self.bucket_page_ids.iter().enumerate() {
+            let pos = BUCKET_PAGE_IDS_OFFSET + index * 4;
+            page[pos..pos + 4].copy_from_slice(&page_id.to_le_bytes());
+        }
+        seal(page);
+    }
+
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> {
+        check_page_header(page, page_id, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_DIRECTORY_PAGE_TYPE, "hash table directory")?;
+        let read_u32 = |pos: usize| u32::from_le_bytes(page[pos..pos + 4].try_into().unwrap());
+        let (max_depth, global_depth) = (read_u32(12), read_u32(16));
+        if max_depth > HTABLE_DIRECTORY_MAX_DEPTH || global_depth > max_depth {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Directory page {} has global depth {} and max depth {}", page_id, global_depth, max_depth),
+            ));
+        }
+        let mut local_depths = [0; HTABLE_DIRECTORY_ARRAY_SIZE];
+        local_depths.copy_from_slice(&page[LOCAL_DEPTHS_OFFSET..BUCKET_PAGE_IDS_OFFSET]);
+        let mut bucket_page_ids = [INVALID_PAGE_ID; HTABLE_DIRECTORY_ARRAY_SIZE];
+        for (index, bucket_page_id) in bucket_page_ids.iter_mut().enumerate() {
+            *bucket_page_id = read_i32(page, BUCKET_PAGE_IDS_OFFSET + index * 4);
+        }
+        Ok(ExtendibleHTableDirectoryPage { page_type: HTABLE_DIRECTORY_PAGE_TYPE, max_depth, global_depth, local_depths, bucket_page_ids })
+    }
+}
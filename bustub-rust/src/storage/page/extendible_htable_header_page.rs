@@ -0,0 +1,73 @@
+use std::io;
+
+use crate::include::common::config::{PageId, INVALID_PAGE_ID};
+use crate::include::storage::page::extendible_htable_header_page::{
+    ExtendibleHTableHeaderPage, ExtendibleHTableHeaderPageImpl, HTABLE_HEADER_ARRAY_SIZE, HTABLE_HEADER_MAX_DEPTH, HTABLE_HEADER_PAGE_TYPE,
+    HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION,
+};
+use crate::storage::page::b_plus_tree_page::read_i32;
+use crate::storage::page::index_page::{check_page_header, seal, write_page_header};
+
+// Offset of the directory page ids
+const DIRECTORY_PAGE_IDS_OFFSET: usize = 16;
+
+impl ExtendibleHTableHeaderPage {
+    // A header without directories, max_depth is at most HTABLE_HEADER_MAX_DEPTH
+    pub fn new(max_depth: u32) -> Self {
+        assert!(max_depth <= HTABLE_HEADER_MAX_DEPTH, "Header max depth {} exceeds {}", max_depth, HTABLE_HEADER_MAX_DEPTH);
+        ExtendibleHTableHeaderPage {
+            page_type: HTABLE_HEADER_PAGE_TYPE,
+            max_depth,
+            directory_page_ids: [INVALID_PAGE_ID; HTABLE_HEADER_ARRAY_SIZE],
+        }
+    }
+}
+
+impl ExtendibleHTableHeaderPageImpl for ExtendibleHTableHeaderPage {
+    fn hash_to_directory_index(&self, hash: u32) -> usize {
+        // a shift by all 32 bits would overflow
+        if self.max_depth == 0 {
+            0
+        } else {
+            (hash >> (32 - self.max_depth)) as usize
+        }
+    }
+
+    fn get_directory_page_id(&self, directory_index: usize) -> PageId {
+        self.directory_page_ids[directory_index]
+    }
+
+    fn set_directory_page_id(&mut self, directory_index: usize, page_id: PageId) {
+        self.directory_page_ids[directory_index] = page_id;
+    }
+
+    fn max_size(&self) -> usize {
+        1 << self.max_depth
+    }
+
+    fn encode(&self, page: &mut [u8]) {
+        write_page_header(page, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_HEADER_PAGE_TYPE);
+        page[12..16].copy_from_slice(&self.max_depth.to_le_bytes());
+        for (index, page_id) in self.directory_page_ids.iter().enumerate() {
+            let pos = DIRECTORY_PAGE_IDS_OFFSET + index * 4;
+            page[pos..pos + 4].copy_from_slice(&page_id.to_le_bytes());
+        }
+        seal(page);
+    }
+
+    fn decode(page: &[u8], page_id: PageId) -> io::Result<Self> {
+        check_page_header(page, page_id, HTABLE_PAGE_MAGIC, HTABLE_PAGE_VERSION, HTABLE_HEADER_PAGE_TYPE, "hash table header")?;
+        let max_depth = u32::from_le_bytes(page[12..16].try_into().unwrap());
+        if max_depth > HTABLE_HEADER_MAX_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Header page {} has max depth {}, over {}", page_id, max_depth, HTABLE_HEADER_MAX_DEPTH),
+            ));
+        }
+        let mut directory_page_ids = [INVALID_PAGE_ID; HTABLE_HEADER_ARRAY_SIZE];
+        for (index, directory_page_id) in directory_page_ids.iter_mut().enumerate() {
+            *directory_page_id = read_i32(page, DIRECTORY_PAGE_IDS_OFFSET + index * 4);
+        }
+        Ok(ExtendibleHTableHeaderPage { page_type: HTABLE_HEADER_PAGE_TYPE, max_depth, directory_page_ids })
+    }
+}
@@ -0,0 +1,75 @@
+use std::io;
+
+use crate::common::checksum::Crc32;
+use crate::include::common::config::{PageId, PAGE_SIZE};
+
+// Every index page, B+ tree and hash table alike, starts with the same header.
+// Every integer is little-endian:
+//    0  magic      u32, the kind of index the page belongs to
+//    4  version    u16
+//    6  page type  u16
+//    8  checksum   u32, CRC-32 of the whole page with this field zeroed
+//   12  the fields of the page type
+const CHECKSUM_OFFSET: usize = 8;
+
+// CRC-32 of the page, the stored checksum counts as zero
+fn page_checksum(page: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(&page[..CHECKSUM_OFFSET]);
+    crc.update(&[0; 4]);
+    crc.update(&page[CHECKSUM_OFFSET + 4..]);
+    crc.finish()
+}
+
+/// Clears `page` and writes the common header. The checksum is left zero,
+/// `seal` fills it in once the rest of the page is written.
+pub(crate) fn write_page_header(page: &mut [u8], magic: u32, version: u16, page_type: u16) {
+    assert_eq!(page.len(), PAGE_SIZE, "Index pages are encoded into whole pages");
+    page.fill(0);
+    page[0..4].copy_from_slice(&magic.to_le_bytes());
+    page[4..6].copy_from_slice(&version.to_le_bytes());
+    page[6..8].copy_from_slice(&page_type.to_le_bytes());
+}
+
+/// Stores the checksum of a fully encoded page
+pub(crate) fn seal(page: &mut [u8]) {
+    let checksum = page_checksum(page);
+    page[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Fails unless the stored checksum matches the bytes of the page
+pub(crate) fn check_checksum(page: &[u8], kind: &str) -> io::Result<()> {
+    let stored = u32::from_le_bytes(page[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4].try_into().unwrap());
+    let computed = page_checksum(page);
+    if stored != computed {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} page checksum mismatch, stored {:#010x} computed {:#010x}", kind, stored, computed),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the length, magic, version, page type and checksum of page `page_id`,
+/// `kind` names the page in the errors
+pub(crate) fn check_page_header(page: &[u8], page_id: PageId, magic: u32, version: u16, page_type: u16, kind: &str) -> io::Result<()> {
+    let invalid = |msg: String| Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    if page.len() != PAGE_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Expected a {} byte page, got {} bytes", PAGE_SIZE, page.len())));
+    }
+    let stored_magic = u32::from_le_bytes(page[0..4].try_into().unwrap());
+    let stored_type = u16::from_le_bytes(page[6..8].try_into().unwrap());
+    if stored_magic != magic || stored_type != page_type {
+        return invalid(format!("Page {} is not a {}, magic is {:#010x} and page type {}", page_id, kind, stored_magic, stored_type));
+    }
+    let stored_version = u16::from_le_bytes(page[4..6].try_into().unwrap());
+    if stored_version != version {
+        return invalid(format!("Unsupported {} page version {}", kind, stored_version));
+    }
+    check_checksum(page, kind)
+}
+
+// An index only follows page ids it wrote itself, so a page that fails to decode is corrupted
+pub(crate) fn decode_page<T>(page: io::Result<T>, page_id: PageId) -> T {
+    page.unwrap_or_else(|e| panic!("Index page {} is corrupted: {}", page_id, e))
+}
@@ -11,6 +11,10 @@ pub mod page_guard {
 //     include!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/storage_d/page_d/page_guard.rs"));
 // }
 
+pub mod index_page;
 pub mod b_plus_tree_page;
 pub mod b_plus_tree_internal_page;
-pub mod b_plus_tree_leaf_page;
\ No newline at end of file
+pub mod b_plus_tree_leaf_page;
+pub mod extendible_htable_header_page;
+pub mod extendible_htable_directory_page;
+pub mod extendible_htable_bucket_page;
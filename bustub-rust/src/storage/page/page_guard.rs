@@ -1,20 +1,14 @@
-use std::sync::{Arc, Mutex};
-use crate::buffer::bufferpool_manager::{BufferPoolManager, FrameHeader};
-use crate::include::buffer::bufferpool_manager::{BufferPoolManagerImpl, FrameHeaderImpl};
-use crate::include::common::config::{AccessType, FrameId, PageId};
+use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
+use crate::buffer::bufferpool_manager::{BufferPoolManager, FrameData, FrameHeader};
+use crate::include::buffer::bufferpool_manager::FrameHeaderImpl;
+use crate::include::common::config::{FrameId, PageId};
 use crate::include::storage::page::page_guard::{PageguardImpl,ReadPageGuardImpl,WritePageGuardImpl};
-use crate::buffer::lru_k_replacer::LRUKReplacerImpl;
-use crate::storage::disk::disk_scheduler::DiskScheduler;
-use crate::include::buffer::lru_k_replacer::LRUKReplacer;
-use crate::include::storage::disk::disk_scheduler::DiskRequest;
-use crate::include::storage::disk::disk_scheduler::DiskSchedulerTrait;
-use std::sync::mpsc::channel;
-use std::clone::Clone;
+use std::io;
 
 
-//use std::alloc::Global;
+// Keeps a frame pinned, the latch is held by the read/write guards wrapping it
 pub struct BasicPageGuard {
-    bpm: Arc<BufferPoolManager>,
+    bpm: BufferPoolManager,
     frame: Arc<FrameHeader>,
     frame_id: FrameId,
     pub page_id: PageId,
@@ -23,12 +17,12 @@ pub struct BasicPageGuard {
 
 impl BasicPageGuard {
     pub fn new (
-        bpm : Arc<BufferPoolManager>,
+        bpm : BufferPoolManager,
         frame: Arc<FrameHeader>,
         frame_id: FrameId,
         page_id: PageId,
     ) -> Self {
-        frame.increment_pin_count();// Why is this required here?
+        // The buffer pool pins the frame before handing it out, the guard only unpins it
         BasicPageGuard {
             bpm,
             frame,
@@ -41,6 +35,16 @@ impl BasicPageGuard {
     pub(crate) fn frame(&self) -> &Arc<FrameHeader> {
         &self.frame
     }
+
+    // Writes the page through the buffer pool if it has unflushed changes.
+    // `data` is the latched frame content, so the latch is not taken twice.
+    fn flush(&self, data: &[u8]) -> io::Result<()> {
+        if self.frame.is_dirty() {
+            self.bpm.write_page_data(self.page_id, data)?;
+            self.frame.set_is_dirty(false);
+        }
+        Ok(())
+    }
 }
 
 impl PageguardImpl for BasicPageGuard {
@@ -50,11 +54,11 @@ impl PageguardImpl for BasicPageGuard {
 
     fn drop_guard(&mut self) {
         if self.is_valid {
-            self.frame.decrement_pin_count();
+            self.bpm.unpin_frame(&self.frame);
             self.is_valid = false;
         }
     }
-    // not needed for now. 
+    // not needed for now.
     fn get_frame_id(&self) -> FrameId {
         self.frame_id
     }
@@ -66,48 +70,43 @@ impl Drop for BasicPageGuard {
     }
 }
 
-// impl Clone for BasicPageGuard {
-//     fn clone(&self) -> Self {
-//         BasicPageGuard {
-//             guard: self.guard.clone(), // Requires BasicPageGuard to implement Clone
-//             replacer: self.replacer.clone(), // Safe via Arc
-//             bpm_latch: self.bpm_latch.clone(), // Safe via Arc
-//             disk_scheduler: self.disk_scheduler.clone(), // Safe via Arc
-//             is_valid: self.is_valid, // Simple copy
-//         }
-//     }
-// }
+// The latch guards borrow from the frame, which lives in an Arc held by the
+// same page guard. Every page guard releases the latch before the Arc.
+unsafe fn extend_read_latch(latch: RwLockReadGuard<'_, FrameData>) -> RwLockReadGuard<'static, FrameData> {
+    std::mem::transmute(latch)
+}
+
+unsafe fn extend_write_latch(latch: RwLockWriteGuard<'_, FrameData>) -> RwLockWriteGuard<'static, FrameData> {
+    std::mem::transmute(latch)
+}
 
-// ReadPageGuard implementation 
+// ReadPageGuard implementation
+// Holds the frame's read latch until dropped, any number of readers can share a page.
 pub struct ReadPageGuard {
+    latch: Option<RwLockReadGuard<'static, FrameData>>,
     guard: BasicPageGuard,
-    replacer: Arc<LRUKReplacerImpl>,
-    bpm_latch: Arc<Mutex<()>>,// What is the purpose of this?
-    disk_scheduler: Arc<DiskScheduler>,
-    is_valid: bool,
-    //bpm:  Arc<BufferPoolManager>
 }
 
 impl ReadPageGuard {
+    /// Blocks until the read latch is available. The frame must already be pinned,
+    /// and the caller must not hold the buffer pool latch.
     pub fn new(
         page_id: PageId,
         frame_id: FrameId,
-        frame: Arc<FrameHeader>, 
-        replacer: Arc<LRUKReplacerImpl>,
-        bpm_latch: Arc<Mutex<()>>,
-        disk_scheduler: Arc<DiskScheduler>,
-        bpm: Arc<BufferPoolManager>,
+        frame: Arc<FrameHeader>,
+        bpm: BufferPoolManager,
     ) -> Self {
+        let latch = unsafe { extend_read_latch(frame.read_latch()) };
         let guard = BasicPageGuard::new(bpm, frame,frame_id, page_id);
-        replacer.record_access(guard.get_frame_id(), AccessType::Unknown);
         ReadPageGuard {
+            latch: Some(latch),
             guard,
-            replacer,
-            bpm_latch,
-            disk_scheduler,
-            is_valid: true,
         }
     }
+
+    fn data(&self) -> &FrameData {
+        self.latch.as_ref().expect("page guard used after drop_guard")
+    }
 }
 impl PageguardImpl for ReadPageGuard {
     fn get_page_id(&self) -> PageId {
@@ -119,74 +118,58 @@ impl PageguardImpl for ReadPageGuard {
     }
 
     fn drop_guard(&mut self) {
-        if self.is_valid {
-            self.guard.drop_guard();
-            self.is_valid = false;
-        }
+        // Latch first: once unpinned the frame may be handed to another page
+        self.latch.take();
+        self.guard.drop_guard();
     }
 }
 impl ReadPageGuardImpl for ReadPageGuard {
     fn as_ref(&self) -> &[u8] {
-        //add the lru k record access here. 
-        //let mut replacer= &self.replacer.lock().unwrap();
-        //replacer.record_access(&self.get_frame_id(), AccessType::Unknown);
-        self.guard.frame.get_data()
+        self.data()
     }
 
     fn is_dirty(&self) -> bool {
         self.guard.frame.is_dirty()
     }
 
-    fn flush(&self) {
-        // Placeholder: use disk scduler to flush the frame data to disk. 
-        if self.is_dirty() {
-            let data = self.guard.frame().get_data();
-            let request = DiskRequest {
-                is_write: true,
-                page_id: self.get_page_id(),
-                data: Arc::new(Mutex::new(data.to_vec())),
-                is_done: channel().0,
-            };
-            self.disk_scheduler.schedule(request);
-        }
-    }   
+    fn flush(&self) -> io::Result<()> {
+        self.guard.flush(self.data())
+    }
 }
 
 impl Drop for ReadPageGuard {
     fn drop(&mut self) {
         self.drop_guard()
     }
-    
+
 }
 
+// Holds the frame's write latch until dropped, so no other guard can see the page meanwhile.
 pub struct WritePageGuard {
-    pub guard: BasicPageGuard,
-    replacer: Arc<LRUKReplacerImpl>,
-    bpm_latch: Arc<Mutex<()>>,
-    disk_scheduler: Arc<DiskScheduler>,
-    is_valid: bool,
+    latch: Option<RwLockWriteGuard<'static, FrameData>>,
+    guard: BasicPageGuard,
 }
 
 impl WritePageGuard {
+    /// Blocks until the write latch is available. The frame must already be pinned,
+    /// and the caller must not hold the buffer pool latch.
     pub fn new(
         page_id: PageId,
         frame_id: FrameId,
         frame: Arc<FrameHeader>,
-        replacer: Arc<LRUKReplacerImpl>,
-        bpm_latch: Arc<Mutex<()>>,
-        disk_scheduler: Arc<DiskScheduler>,
-        bpm: Arc<BufferPoolManager>,
+        bpm: BufferPoolManager,
     ) -> Self {
+        let latch = unsafe { extend_write_latch(frame.write_latch()) };
         let guard = BasicPageGuard::new(bpm, frame,frame_id, page_id);
-        replacer.record_access(guard.get_frame_id(), AccessType::Unknown);
         WritePageGuard {
+            latch: Some(latch),
             guard,
-            replacer,
-            bpm_latch,
-            disk_scheduler,
-            is_valid: true,
         }
     }
+
+    fn data(&self) -> &FrameData {
+        self.latch.as_ref().expect("page guard used after drop_guard")
+    }
 }
 
 impl PageguardImpl for WritePageGuard {
@@ -199,38 +182,31 @@ impl PageguardImpl for WritePageGuard {
     }
 
     fn drop_guard(&mut self) {
-        if self.is_valid {
-            self.guard.drop_guard();
-            self.is_valid = false;
-        }
+        self.latch.take();
+        self.guard.drop_guard();
     }
 }
 
 impl WritePageGuardImpl for WritePageGuard {
     fn as_ref(&self) -> &[u8] {
-        self.guard.frame().get_data()
+        self.data()
     }
 
+    // Any mutable access marks the page dirty, whether or not bytes change
     fn as_mut(&mut self) -> &mut [u8] {
-        self.guard.frame().get_data_mut()
+        let frame = self.guard.frame();
+        if !frame.is_dirty() {
+            frame.set_is_dirty(true);
+        }
+        self.latch.as_mut().expect("page guard used after drop_guard")
     }
 
     fn is_dirty(&self) -> bool {
         self.guard.frame().is_dirty()
     }
 
-    fn flush(&self) {
-        // Placeholder: Use disk_scheduler to flush frame data
-        if self.is_dirty() {
-            let data = self.guard.frame().get_data();
-            let request = DiskRequest {
-                is_write: true,
-                page_id: self.get_page_id(),
-                data: Arc::new(Mutex::new(data.to_vec())),
-                is_done: channel().0,
-            };
-            self.disk_scheduler.schedule(request);
-        }
+    fn flush(&self) -> io::Result<()> {
+        self.guard.flush(self.data())
     }
 }
 
@@ -239,17 +215,3 @@ impl Drop for WritePageGuard {
         self.drop_guard();
     }
 }
-
-
-// impl Clone for WritePageGuard {
-//     fn clone(&self) -> Self {
-//         WritePageGuard {
-//             guard: self.guard.clone(), // Requires BasicPageGuard to implement Clone
-//             replacer: self.replacer.clone(), // Safe via Arc
-//             bpm_latch: self.bpm_latch.clone(), // Safe via Arc
-//             disk_scheduler: self.disk_scheduler.clone(), // Safe via Arc
-//             is_valid: self.is_valid, // Simple copy
-//         }
-//     }
-// }
-
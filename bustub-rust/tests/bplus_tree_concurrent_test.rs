@@ -0,0 +1,152 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl};
+use bustub_rust::include::storage::index::generic_key::{GenericComparator, GenericKey};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::KeyType;
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use std::sync::Arc;
+use std::thread;
+
+const THREADS: i64 = 8;
+
+// Every thread holds at most a descent path and a couple of siblings
+fn setup_bpm(db_file: &str) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(256, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(256, 2)))
+}
+
+fn rid(key: KeyType) -> Rid {
+    Rid::new((key >> 32) as i32, key as u32)
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_tree_is_send_and_sync() {
+    assert_send_sync::<BplusTree>();
+    assert_send_sync::<BplusTree<GenericKey<16>, GenericComparator<16>>>();
+}
+
+#[test]
+fn test_concurrent_inserts() {
+    let db_file = "bplus_tree_concurrent_insert_test.db";
+    let bpm = setup_bpm(db_file);
+    // Small pages, so the threads keep splitting the same pages and the root
+    let tree = BplusTree::new(String::from("concurrent_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    let count = 2000;
+
+    thread::scope(|scope| {
+        for thread_id in 0..THREADS {
+            let tree = &tree;
+            scope.spawn(move || {
+                let mut keys: Vec<KeyType> = (0..count).filter(|key| key % THREADS == thread_id).collect();
+                keys.shuffle(&mut rand::thread_rng());
+                for key in keys {
+                    assert!(tree.insert(key, ValueType::Rid(rid(key))).is_ok());
+                }
+            });
+        }
+    });
+
+    for key in 0..count {
+        assert_eq!(tree.get_value(key), vec![rid(key)], "key {}", key);
+    }
+    let scanned: Vec<KeyType> = tree.begin().map(|(key, _)| key).collect();
+    assert_eq!(scanned, (0..count).collect::<Vec<_>>());
+    tree.check_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_concurrent_insert_remove_and_lookup() {
+    let db_file = "bplus_tree_concurrent_mixed_test.db";
+    let bpm = setup_bpm(db_file);
+    let tree = Arc::new(BplusTree::new(String::from("mixed_index"), &bpm, 4, 4, INVALID_PAGE_ID));
+    let count = 3000;
+    // Multiples of 3 stay put, the other even keys are removed and the odd keys inserted
+    for key in (0..count).filter(|key| key % 3 == 0 || key % 2 == 0) {
+        tree.insert(key, ValueType::Rid(rid(key))).unwrap();
+    }
+
+    let mut handles = Vec::new();
+    for thread_id in 0..THREADS {
+        let tree = Arc::clone(&tree);
+        handles.push(thread::spawn(move || {
+            let mine = |key: &KeyType| key % 3 != 0 && key % THREADS == thread_id;
+            let mut removes: Vec<KeyType> = (0..count).filter(|key| key % 2 == 0).filter(mine).collect();
+            let mut inserts: Vec<KeyType> = (0..count).filter(|key| key % 2 == 1).filter(mine).collect();
+            removes.shuffle(&mut rand::thread_rng());
+            inserts.shuffle(&mut rand::thread_rng());
+            for (step, (remove, insert)) in removes.iter().zip(inserts.iter()).enumerate() {
+                tree.remove(*remove, rid(*remove));
+                assert!(tree.insert(*insert, ValueType::Rid(rid(*insert))).is_ok());
+                // The stable keys are found whatever the other threads split or merge
+                let stable = (step as KeyType * 3 + thread_id * 3) % count;
+                assert_eq!(tree.get_value(stable), vec![rid(stable)], "key {}", stable);
+                assert!(tree.get_value(*remove).is_empty());
+                if step % 50 == 0 {
+                    let scanned: Vec<KeyType> = tree.begin().map(|(key, _)| key).collect();
+                    assert!(scanned.windows(2).all(|pair| pair[0] < pair[1]), "a scan sees its keys in order");
+                    assert!((0..count).step_by(3).all(|key| scanned.binary_search(&key).is_ok()));
+                }
+            }
+            for remove in removes.iter().skip(inserts.len()) {
+                tree.remove(*remove, rid(*remove));
+            }
+            for insert in inserts.iter().skip(removes.len()) {
+                assert!(tree.insert(*insert, ValueType::Rid(rid(*insert))).is_ok());
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let expected: Vec<KeyType> = (0..count).filter(|key| key % 3 == 0 || key % 2 == 1).collect();
+    let scanned: Vec<KeyType> = tree.begin().map(|(key, _)| key).collect();
+    assert_eq!(scanned, expected);
+    tree.check_integrity().unwrap();
+    for key in 0..count {
+        let value = if expected.binary_search(&key).is_ok() { vec![rid(key)] } else { vec![] };
+        assert_eq!(tree.get_value(key), value, "key {}", key);
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_concurrent_removes_empty_the_tree() {
+    let db_file = "bplus_tree_concurrent_remove_test.db";
+    let bpm = setup_bpm(db_file);
+    let tree = BplusTree::new(String::from("shrinking_index"), &bpm, 3, 3, INVALID_PAGE_ID);
+    let count = 1500;
+    for key in 0..count {
+        tree.insert(key, ValueType::Rid(rid(key))).unwrap();
+    }
+
+    thread::scope(|scope| {
+        for thread_id in 0..THREADS {
+            let tree = &tree;
+            scope.spawn(move || {
+                let mut keys: Vec<KeyType> = (0..count).filter(|key| key % THREADS == thread_id).collect();
+                keys.shuffle(&mut rand::thread_rng());
+                for key in keys {
+                    tree.remove(key, rid(key));
+                }
+            });
+        }
+    });
+
+    assert!(tree.is_empty());
+    assert_eq!(tree.get_root_page_id(), INVALID_PAGE_ID);
+    assert!(tree.begin().next().is_none());
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -0,0 +1,276 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{AccessType, PageId, ValueType, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, IndexMode, IntegrityViolation};
+use bustub_rust::include::storage::index::generic_key::OrdComparator;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, KeyType};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl};
+use bustub_rust::include::storage::page::page_guard::{ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn rid_for(key: KeyType) -> Rid {
+    Rid::new(key as i32, 3)
+}
+
+// A two level tree over keys 0..30 with leaves of at most 4 entries
+fn small_tree(bpm: &BufferPoolManager) -> BplusTree {
+    let tree = BplusTree::new(String::from("checked_index"), bpm, 4, 16, INVALID_PAGE_ID);
+    for key in 0..30 {
+        tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+    }
+    tree.check_integrity().unwrap();
+    tree
+}
+
+// Page ids of the leaves along the leaf chain
+fn leaf_ids(bpm: &BufferPoolManager, tree: &BplusTree) -> Vec<PageId> {
+    // Down the leftmost children to the first leaf
+    let mut page_id = tree.get_root_page_id();
+    loop {
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        if BplusTreePage::peek_header(guard.as_ref()).unwrap().0.is_leaf_page() {
+            break;
+        }
+        page_id = BplusTreeInternalPage::<KeyType>::decode(guard.as_ref()).unwrap().page_id_value_at(0);
+    }
+    let mut leaves = Vec::new();
+    while page_id != INVALID_PAGE_ID {
+        leaves.push(page_id);
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        page_id = BplusTreeLeafPage::<KeyType>::decode(guard.as_ref()).unwrap().get_next_page_id();
+    }
+    leaves
+}
+
+// Decodes the leaf, lets `change` edit it and encodes it again
+fn edit_leaf(bpm: &BufferPoolManager, page_id: PageId, change: impl FnOnce(&mut BplusTreeLeafPage)) {
+    let mut guard = bpm.write_page(page_id, AccessType::Index);
+    let mut leaf = BplusTreeLeafPage::decode(guard.as_ref()).unwrap();
+    change(&mut leaf);
+    leaf.encode(guard.as_mut());
+}
+
+#[test]
+fn test_trees_stay_valid_through_inserts_and_removes() {
+    let db_file = "bplus_tree_integrity_model_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let mut rng = rand::thread_rng();
+    // Odd max sizes leave split halves and merges one entry apart
+    for (leaf_max_size, internal_max_size) in [(3, 3), (4, 4), (5, 3), (2, 5), (7, 6)] {
+        let tree = BplusTree::new(String::from("model_index"), &bpm, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+        let mut keys: Vec<KeyType> = (0..600).collect();
+        keys.shuffle(&mut rng);
+        for (step, &key) in keys.iter().enumerate() {
+            tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+            if step % 50 == 0 {
+                tree.check_integrity().unwrap();
+            }
+        }
+        tree.check_integrity().unwrap();
+        keys.shuffle(&mut rng);
+        for (step, &key) in keys.iter().enumerate() {
+            tree.remove(key, rid_for(key));
+            if step % 50 == 0 {
+                assert_eq!(tree.check_integrity(), Ok(()), "sizes {} {} after {} removes", leaf_max_size, internal_max_size, step);
+            }
+        }
+        assert!(tree.is_empty());
+        tree.check_integrity().unwrap();
+    }
+
+    // Equal keys spread over several pages in a non-unique index
+    let tree = BplusTree::with_mode(String::from("secondary_index"), &bpm, OrdComparator, IndexMode::NonUnique, 3, 3, INVALID_PAGE_ID);
+    let mut model = BTreeSet::new();
+    for step in 0..3000 {
+        let entry: (KeyType, Rid) = (rng.gen_range(0..8), Rid::new(rng.gen_range(0..4), rng.gen_range(0..40)));
+        if rng.gen_bool(0.6) {
+            assert_eq!(tree.insert(entry.0, ValueType::Rid(entry.1)).is_ok(), model.insert(entry));
+        } else {
+            tree.remove(entry.0, entry.1);
+            model.remove(&entry);
+        }
+        if step % 100 == 0 {
+            tree.check_integrity().unwrap();
+        }
+    }
+    tree.check_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_removes_keep_odd_internal_pages_at_min_size() {
+    let db_file = "bplus_tree_integrity_odd_remove_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    // A merge of two internal pages at min size must fit in one page
+    for internal_max_size in [3, 5, 7] {
+        let tree = BplusTree::new(String::from("odd_index"), &bpm, 2, internal_max_size, INVALID_PAGE_ID);
+        for key in 0..200 {
+            tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+        }
+        for key in (0..200).step_by(2).chain((1..200).step_by(2)) {
+            tree.remove(key, rid_for(key));
+            assert_eq!(tree.check_integrity(), Ok(()), "internal max size {} after removing {}", internal_max_size, key);
+        }
+        assert!(tree.is_empty());
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_leaf_splits_keep_both_halves_at_min_size() {
+    let db_file = "bplus_tree_integrity_leaf_split_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    for leaf_max_size in 2..=7 {
+        // A split of the only leaf, with the new key on either side of the middle
+        for key in [0, 100] {
+            let tree = BplusTree::new(String::from("split_index"), &bpm, leaf_max_size, 8, INVALID_PAGE_ID);
+            for other in 1..=leaf_max_size as KeyType {
+                tree.insert(other, ValueType::Rid(rid_for(other))).unwrap();
+            }
+            tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+            assert_eq!(tree.check_integrity(), Ok(()), "leaf max size {} after inserting {}", leaf_max_size, key);
+        }
+        // Sorted inserts leave every leaf but the last one at exactly its min size
+        let tree = BplusTree::new(String::from("sorted_index"), &bpm, leaf_max_size, 8, INVALID_PAGE_ID);
+        for key in 0..100 {
+            tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+        }
+        tree.check_integrity().unwrap();
+        let min_size = (leaf_max_size + 1) / 2;
+        let leaves = leaf_ids(&bpm, &tree);
+        for &page_id in &leaves[..leaves.len() - 1] {
+            let guard = bpm.read_page(page_id, AccessType::Index);
+            assert_eq!(BplusTreeLeafPage::<KeyType>::decode(guard.as_ref()).unwrap().base_page.size_, min_size);
+        }
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_internal_splits_keep_both_halves_at_min_size() {
+    let db_file = "bplus_tree_integrity_internal_split_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    // Sorted inserts always split the last or the first internal page
+    for internal_max_size in [3, 4, 5] {
+        for descending in [false, true] {
+            let tree = BplusTree::new(String::from("split_index"), &bpm, 2, internal_max_size, INVALID_PAGE_ID);
+            for step in 0..200 {
+                let key = if descending { 199 - step } else { step };
+                tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+                assert_eq!(tree.check_integrity(), Ok(()), "internal max size {} after inserting {}", internal_max_size, key);
+            }
+        }
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_loaded_tree_is_valid() {
+    let db_file = "bplus_tree_integrity_bulk_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    for fill_factor in [0.1, 0.5, 0.9, 1.0] {
+        let tree = BplusTree::new(String::from("loaded_index"), &bpm, 5, 5, INVALID_PAGE_ID);
+        tree.bulk_load((0..1000).map(|key| (key, rid_for(key))), fill_factor).unwrap();
+        tree.check_integrity().unwrap();
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_reports_entries_out_of_order_and_range() {
+    let db_file = "bplus_tree_integrity_order_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = small_tree(&bpm);
+    let leaves = leaf_ids(&bpm, &tree);
+
+    edit_leaf(&bpm, leaves[1], |leaf| leaf.key_array.swap(0, 1));
+    // A key that belongs to the next leaf
+    edit_leaf(&bpm, leaves[2], |leaf| {
+        let last = leaf.base_page.size_ as usize - 1;
+        leaf.key_array[last] = 29;
+    });
+    let violations = tree.check_integrity().unwrap_err();
+    assert!(violations.contains(&IntegrityViolation::KeysOutOfOrder { page_id: leaves[1], index: 1 }), "{:?}", violations);
+    assert!(violations.contains(&IntegrityViolation::KeyOutOfRange { page_id: leaves[2], index: 1 }), "{:?}", violations);
+    assert!(violations.iter().all(|violation| matches!(violation, IntegrityViolation::KeysOutOfOrder { .. } | IntegrityViolation::KeyOutOfRange { .. })));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_reports_broken_pages() {
+    let db_file = "bplus_tree_integrity_pages_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = small_tree(&bpm);
+    let leaves = leaf_ids(&bpm, &tree);
+
+    edit_leaf(&bpm, leaves[0], |leaf| leaf.next_page_id = leaves[2]);
+    edit_leaf(&bpm, leaves[3], |leaf| leaf.base_page.page_id = 999);
+    edit_leaf(&bpm, leaves[4], |leaf| leaf.base_page.size_ = 1);
+    bpm.write_page(leaves[5], AccessType::Index).as_mut().fill(0);
+    let violations = tree.check_integrity().unwrap_err();
+    assert_eq!(violations[..2], [
+        IntegrityViolation::PageIdMismatch { page_id: leaves[3], stored: 999 },
+        IntegrityViolation::Underflow { page_id: leaves[4], size: 1, min_size: 2 },
+    ]);
+    assert!(matches!(violations[2], IntegrityViolation::Corrupted { page_id, .. } if page_id == leaves[5]), "{:?}", violations);
+    // The leaf chain is checked last, the zeroed leaf is missing from it
+    assert_eq!(violations[3..], [
+        IntegrityViolation::BrokenLeafChain { page_id: leaves[0], next_page_id: leaves[2], expected: leaves[1] },
+        IntegrityViolation::BrokenLeafChain { page_id: leaves[4], next_page_id: leaves[5], expected: leaves[6] },
+    ]);
+    assert!(violations[0].to_string().contains("stores page id 999"));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_reports_uneven_leaf_depth() {
+    let db_file = "bplus_tree_integrity_depth_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = small_tree(&bpm);
+    let leaves = leaf_ids(&bpm, &tree);
+    let last_leaf = *leaves.last().unwrap();
+
+    // Hang the last leaf one level deeper, below an internal page of its own
+    let page_id = bpm.new_page().unwrap();
+    let mut internal = BplusTreeInternalPage::<KeyType>::new(16, page_id);
+    internal.page_id_array[0] = last_leaf;
+    internal.encode(bpm.write_page(page_id, AccessType::Index).as_mut());
+    let root_page_id = tree.get_root_page_id();
+    let mut guard = bpm.write_page(root_page_id, AccessType::Index);
+    let mut root = BplusTreeInternalPage::<KeyType>::decode(guard.as_ref()).unwrap();
+    let size = root.base_page.size_ as usize;
+    assert_eq!(root.page_id_array[size], last_leaf);
+    root.page_id_array[size] = page_id;
+    root.encode(guard.as_mut());
+    drop(guard);
+
+    let violations = tree.check_integrity().unwrap_err();
+    assert_eq!(violations, [
+        IntegrityViolation::Underflow { page_id, size: 0, min_size: 8 },
+        IntegrityViolation::UnevenLeafDepth { page_id: last_leaf, depth: 2, expected: 1 },
+    ]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -1,6 +1,6 @@
 use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, INTERNAL_PAGE_SLOT_CNT};
-use bustub_rust::include::common::config::{IndexPageType, PAGE_SIZE, INVALID_PAGE_ID};
-use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl};
+use bustub_rust::include::common::config::{IndexPageType, INVALID_PAGE_ID};
+use bustub_rust::include::storage::page::b_plus_tree_page::BplusTreePageImpl;
 
 // Test new internal page creation.  
 // Test the maximum insert insert Key count. 
@@ -11,21 +11,21 @@ fn test_new_internal_page() {
     let max_size = 3;
     let page_id = 100;
     // 1. Create a new internal page
-    let internal_page = BplusTreeInternalPage::new(max_size, page_id);
+    let internal_page: BplusTreeInternalPage = BplusTreeInternalPage::new(max_size, page_id);
 
     // 2. Check the attributes
     assert_eq!(internal_page.base_page.page_type, IndexPageType::INTERNAL_PAGE);
     assert!(!internal_page.is_leaf());
     assert_eq!(internal_page.key_array.len(), internal_page.page_id_array.len(), "Both the values should match");
-    assert_eq!(INTERNAL_PAGE_SLOT_CNT, 340, "match");
+    assert_eq!(INTERNAL_PAGE_SLOT_CNT, 338, "32 byte page header, then 12 bytes per slot");
 
     assert_eq!(internal_page.base_page.get_size(), 0);
     assert_eq!(internal_page.base_page.max_size, max_size);
     assert_eq!(internal_page.base_page.page_id, page_id);
-    assert_eq!(internal_page.key_array.len(), INTERNAL_PAGE_SLOT_CNT as usize);
-    assert_eq!(internal_page.page_id_array.len(), INTERNAL_PAGE_SLOT_CNT as usize);
+    assert_eq!(internal_page.key_array.len(), INTERNAL_PAGE_SLOT_CNT);
+    assert_eq!(internal_page.page_id_array.len(), INTERNAL_PAGE_SLOT_CNT);
     assert_eq!(internal_page.key_array, [-1; INTERNAL_PAGE_SLOT_CNT]);
-    assert_eq!(internal_page.page_id_array, [INVALID_PAGE_ID; INTERNAL_PAGE_SLOT_CNT as usize]);
+    assert_eq!(internal_page.page_id_array, [INVALID_PAGE_ID; INTERNAL_PAGE_SLOT_CNT]);
 }
 
 #[test]
@@ -34,7 +34,7 @@ fn test_key_at(){
     let max_size = 3;
     let page_id = 100;
     // 1. Create a new internal page
-    let mut internal_page = BplusTreeInternalPage::new(max_size, page_id);
+    let mut internal_page: BplusTreeInternalPage = BplusTreeInternalPage::new(max_size, page_id);
     let index  = 0;
     let key = 45;
     internal_page.set_key_at(0, key);
@@ -49,3 +49,12 @@ fn test_max_key_values() {
     // 1. Generate keyvalues up to maxkey count in an internal page. 
 
 }
+
+#[test]
+fn test_min_size() {
+    // An internal page needs half its max size + 1 children, so half its keys rounded down
+    for (max_size, min_keys) in [(2, 1), (3, 1), (4, 2), (5, 2), (16, 8), (17, 8)] {
+        let internal_page: BplusTreeInternalPage = BplusTreeInternalPage::new(max_size, 100);
+        assert_eq!(internal_page.base_page.get_min_size(), min_keys, "max size {}", max_size);
+    }
+}
@@ -1,5 +1,5 @@
-use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl, LEAF_PAGE_SLOT_CNT};
-use bustub_rust::include::common::config::{IndexPageType, PAGE_SIZE, INVALID_PAGE_ID,ValueType};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl, LEAF_PAGE_HEADER_SIZE, LEAF_PAGE_SLOT_CNT};
+use bustub_rust::include::common::config::{IndexPageType, INVALID_PAGE_ID, PAGE_SIZE, ValueType};
 use bustub_rust::include::common::rid::Rid;
 
 // Test new leaf page creation.  
@@ -11,14 +11,15 @@ fn test_new_leaf_page() {
     // 1. Create a new leaf page
     let max_size = 100;
     let page_id = 100;
-    let leaf_page = BplusTreeLeafPage::new(max_size, page_id);
+    let leaf_page: BplusTreeLeafPage = BplusTreeLeafPage::new(max_size, page_id);
 
     // 2. Check the attributes
     assert_eq!(leaf_page.base_page.page_type, IndexPageType::LEAF_PAGE);
     assert!(leaf_page.is_leaf());
     assert_eq!(leaf_page.next_page_id, INVALID_PAGE_ID);
     assert_eq!(leaf_page.key_array.len(), leaf_page.rid_array.len(), "Both the length must match"); 
-    assert_eq!(LEAF_PAGE_SLOT_CNT, 255, "values should match");
+    assert_eq!(LEAF_PAGE_SLOT_CNT, 254, "values should match");
+    assert!(std::mem::size_of::<BplusTreeLeafPage>() <= PAGE_SIZE, "A leaf page must fit in a frame");
 }
 
 
@@ -31,10 +32,9 @@ fn test_max_key_values() {
     // 1. Create a new leaf page
     let max_size = 3;
     let page_id = 100;
-    let mut leaf_page = BplusTreeLeafPage::new(max_size, page_id);
+    let mut leaf_page: BplusTreeLeafPage = BplusTreeLeafPage::new(max_size, page_id);
 
     let key = 42;
-    let rid = Rid::new(1, 0);
     let value = ValueType::Rid(Rid::new(1, 0)); // ValueType is Rid
 
 
@@ -47,3 +47,22 @@ fn test_max_key_values() {
     assert!(!leaf_page.insert(max_size, 100, value));
 
 }
+
+#[test]
+fn test_full_leaf_fits_its_page() {
+    // Every slot of a full leaf lies between the header and the end of the page
+    let entry_size = 8 + 8;
+    assert!(LEAF_PAGE_HEADER_SIZE + LEAF_PAGE_SLOT_CNT * entry_size <= PAGE_SIZE);
+    assert!(LEAF_PAGE_HEADER_SIZE + (LEAF_PAGE_SLOT_CNT + 1) * entry_size > PAGE_SIZE, "No slot is left unused");
+
+    let max_size = LEAF_PAGE_SLOT_CNT as i32;
+    let mut leaf_page: BplusTreeLeafPage = BplusTreeLeafPage::new(max_size, 7);
+    for index in 0..max_size {
+        assert!(leaf_page.insert(index, index as i64, ValueType::Rid(Rid::new(index, index as u32))));
+    }
+    let mut page = vec![0u8; PAGE_SIZE];
+    leaf_page.encode(&mut page);
+    let decoded: BplusTreeLeafPage = BplusTreeLeafPage::decode(&page).unwrap();
+    assert_eq!(decoded.key_at(max_size - 1), max_size as i64 - 1, "The last slot must survive the round trip");
+    assert_eq!(decoded, leaf_page);
+}
@@ -0,0 +1,146 @@
+use bustub_rust::common::checksum::crc32;
+use bustub_rust::include::common::config::{IndexPageType, ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, INTERNAL_PAGE_SLOT_CNT};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl, LEAF_PAGE_SLOT_CNT};
+use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePage, BPLUS_TREE_PAGE_MAGIC, BPLUS_TREE_PAGE_VERSION};
+use bustub_rust::storage::page::b_plus_tree_page::BPlusTreeHeaderPage;
+use std::io::ErrorKind;
+
+fn sample_leaf() -> BplusTreeLeafPage {
+    let mut leaf = BplusTreeLeafPage::new(100, 7);
+    leaf.next_page_id = 9;
+    for (index, key) in [-5i64, 3, 1 << 40].into_iter().enumerate() {
+        assert!(leaf.insert(index as i32, key, ValueType::Rid(Rid::new(key as i32, index as u32))));
+    }
+    leaf
+}
+
+fn encoded_leaf() -> Vec<u8> {
+    let mut page = vec![0u8; PAGE_SIZE];
+    sample_leaf().encode(&mut page);
+    page
+}
+
+#[test]
+fn test_crc32_matches_the_reference_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn test_leaf_round_trip() {
+    let leaf = sample_leaf();
+    let decoded = BplusTreeLeafPage::decode(&encoded_leaf()).unwrap();
+    assert_eq!(decoded, leaf);
+}
+
+#[test]
+fn test_full_leaf_round_trip() {
+    let mut leaf = BplusTreeLeafPage::new(LEAF_PAGE_SLOT_CNT as i32, 1);
+    for index in 0..LEAF_PAGE_SLOT_CNT {
+        assert!(leaf.insert(index as i32, index as i64 * 3, ValueType::Rid(Rid::new(index as i32, 1))));
+    }
+    let mut page = vec![0u8; PAGE_SIZE];
+    leaf.encode(&mut page);
+    assert_eq!(BplusTreeLeafPage::decode(&page).unwrap(), leaf);
+}
+
+#[test]
+fn test_internal_round_trip() {
+    let mut internal = BplusTreeInternalPage::new(INTERNAL_PAGE_SLOT_CNT as i32, 4);
+    let size = INTERNAL_PAGE_SLOT_CNT - 1;
+    for index in 0..size {
+        internal.key_array[index] = index as i64 * 10 - 7;
+        internal.page_id_array[index] = index as i32 + 100;
+    }
+    internal.page_id_array[size] = 99;
+    internal.base_page.size_ = size as i32;
+    let mut page = vec![0u8; PAGE_SIZE];
+    internal.encode(&mut page);
+    assert_eq!(BplusTreeInternalPage::decode(&page).unwrap(), internal);
+}
+
+#[test]
+fn test_layout_is_little_endian() {
+    let page = encoded_leaf();
+    assert_eq!(page[0..4], BPLUS_TREE_PAGE_MAGIC.to_le_bytes());
+    assert_eq!(page[4..6], BPLUS_TREE_PAGE_VERSION.to_le_bytes());
+    assert_eq!(page[6..8], 1u16.to_le_bytes(), "leaf page type");
+    assert_eq!(page[12..16], 3i32.to_le_bytes(), "size");
+    assert_eq!(page[16..20], 100i32.to_le_bytes(), "max size");
+    assert_eq!(page[20..24], 7i32.to_le_bytes(), "page id");
+    assert_eq!(page[24..28], 9i32.to_le_bytes(), "next page id");
+    // First entry: key, rid page id, rid slot
+    assert_eq!(page[32..40], (-5i64).to_le_bytes());
+    assert_eq!(page[40..44], (-5i32).to_le_bytes());
+    assert_eq!(page[44..48], 0u32.to_le_bytes());
+    // Unused slots are zeroed, so the bytes only depend on the live entries
+    assert!(page[32 + 3 * 16..].iter().all(|&b| b == 0));
+
+    let (header, next_page_id) = BplusTreePage::decode(&page).unwrap();
+    assert_eq!(header, BplusTreePage::new(IndexPageType::LEAF_PAGE, 3, 100, 7));
+    assert_eq!(next_page_id, 9);
+}
+
+#[test]
+fn test_internal_pages_have_no_next_page() {
+    let mut page = vec![0u8; PAGE_SIZE];
+    <BplusTreeInternalPage>::new(10, 4).encode(&mut page);
+    assert_eq!(page[6..8], 2u16.to_le_bytes(), "internal page type");
+    assert_eq!(BplusTreePage::decode(&page).unwrap().1, INVALID_PAGE_ID);
+}
+
+#[test]
+fn test_corruption_is_detected() {
+    // Any flipped bit, header or entries, fails the checksum
+    for pos in [12, 33, 32 + 3 * 16 + 1, PAGE_SIZE - 1] {
+        let mut page = encoded_leaf();
+        page[pos] ^= 0x10;
+        let err = <BplusTreeLeafPage>::decode(&page).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("checksum"), "{}", err);
+        // Peeking only looks at the page type, it doesn't verify the page
+        assert!(BplusTreePage::peek_header(&page).is_ok());
+    }
+}
+
+#[test]
+fn test_rejects_foreign_pages() {
+    let err = <BplusTreeLeafPage>::decode(&[0u8; PAGE_SIZE]).unwrap_err();
+    assert!(err.to_string().contains("Not a B+ tree page"), "{}", err);
+
+    let mut page = encoded_leaf();
+    page[4..6].copy_from_slice(&(BPLUS_TREE_PAGE_VERSION + 1).to_le_bytes());
+    let err = <BplusTreeLeafPage>::decode(&page).unwrap_err();
+    assert!(err.to_string().contains("version"), "{}", err);
+
+    let err = <BplusTreeInternalPage>::decode(&encoded_leaf()).unwrap_err();
+    assert!(err.to_string().contains("not an internal page"), "{}", err);
+
+    let err = <BplusTreeLeafPage>::decode(&encoded_leaf()[..100]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_header_page_round_trip() {
+    let mut header = BPlusTreeHeaderPage::new(3);
+    header.root_page_id = 12;
+    let mut page = vec![0u8; PAGE_SIZE];
+    header.encode(&mut page);
+    assert_eq!(page[0..4], BPLUS_TREE_PAGE_MAGIC.to_le_bytes());
+    assert_eq!(page[6..8], 3u16.to_le_bytes(), "header page type");
+    assert_eq!(page[32..36], 12i32.to_le_bytes(), "root page id");
+    assert_eq!(BPlusTreeHeaderPage::decode(&page).unwrap(), header);
+
+    // The header page is checksummed like the tree pages
+    page[33] ^= 0x01;
+    let err = BPlusTreeHeaderPage::decode(&page).unwrap_err();
+    assert!(err.to_string().contains("checksum"), "{}", err);
+
+    // Tree pages and zeroed pages aren't header pages, nor the other way around
+    assert!(BPlusTreeHeaderPage::decode(&encoded_leaf()).is_err());
+    assert!(BPlusTreeHeaderPage::decode(&[0u8; PAGE_SIZE]).is_err());
+    header.encode(&mut page);
+    assert!(<BplusTreeLeafPage>::decode(&page).is_err());
+}
@@ -0,0 +1,105 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, IndexMode};
+use bustub_rust::include::storage::index::generic_key::OrdComparator;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, KeyType};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn insert_keys(tree: &BplusTree, keys: impl IntoIterator<Item = KeyType>) {
+    for key in keys {
+        tree.insert(key, ValueType::Rid(Rid::new(key as i32, 0))).unwrap();
+    }
+}
+
+#[test]
+fn test_page_to_string_lists_every_key() {
+    let mut leaf: BplusTreeLeafPage = BplusTreeLeafPage::new(4, 1);
+    for (index, key) in [3, 5, 8].into_iter().enumerate() {
+        leaf.insert(index as i32, key, ValueType::Rid(Rid::new(0, 0)));
+    }
+    assert_eq!(leaf.to_string(), "(3,5,8)");
+
+    let mut internal: BplusTreeInternalPage = BplusTreeInternalPage::new(4, 2);
+    internal.key_array[..2].copy_from_slice(&[10, 20]);
+    internal.base_page.size_ = 2;
+    assert_eq!(internal.to_string(), "(10,20)");
+}
+
+#[test]
+fn test_print_indents_children_below_their_parent() {
+    let db_file = "bplus_tree_print_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("printed_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    assert_eq!(tree.draw_bplus_tree(), "Empty tree\n");
+
+    insert_keys(&tree, 1..=10);
+    // The header is page 1, the first leaf page 2 and its split off right half page 3
+    let expected = "\
+Internal 4 size=3/4 (3,5,7)
+  Leaf 2 size=2/4 next=3 (1,2)
+  Leaf 3 size=2/4 next=5 (3,4)
+  Leaf 5 size=2/4 next=6 (5,6)
+  Leaf 6 size=4/4 next=-1 (7,8,9,10)
+";
+    assert_eq!(tree.draw_bplus_tree(), expected);
+    tree.print();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_print_shows_separator_rids_of_a_non_unique_index() {
+    let db_file = "bplus_tree_print_non_unique_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::with_mode(String::from("secondary_index"), &bpm, OrdComparator, IndexMode::NonUnique, 4, 4, INVALID_PAGE_ID);
+    for slot in 0..5 {
+        tree.insert(7, ValueType::Rid(Rid::new(1, slot))).unwrap();
+    }
+    let printed = tree.draw_bplus_tree();
+    assert!(printed.starts_with("Internal 4 size=1/4 (7@(1, 2))\n"), "{}", printed);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_draw_writes_a_graphviz_file() {
+    let db_file = "bplus_tree_draw_test.db";
+    let dot_file = "bplus_tree_draw_test.dot";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("drawn_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    assert_eq!(tree.to_dot(), "digraph G {\n  node [shape=plaintext];\n}\n");
+
+    insert_keys(&tree, 1..=10);
+    tree.draw(dot_file).unwrap();
+    let dot = std::fs::read_to_string(dot_file).unwrap();
+    assert!(dot.starts_with("digraph G {\n") && dot.ends_with("}\n"));
+    // Every page once, with its sizes
+    for node in ["INT_4[", "LEAF_2[", "LEAF_3[", "LEAF_5[", "LEAF_6["] {
+        assert_eq!(dot.matches(node).count(), 1, "{}", node);
+    }
+    assert!(dot.contains("<TD COLSPAN=\"4\">max_size=4,min_size=2,size=3</TD>"), "{}", dot);
+    assert!(dot.contains("<TD PORT=\"p2\"> </TD><TD PORT=\"p3\">3</TD><TD PORT=\"p5\">5</TD><TD PORT=\"p6\">7</TD>"), "{}", dot);
+    // Separators point at their children, leaves at their right sibling
+    for edge in [
+        "INT_4:p2 -> LEAF_2;", "INT_4:p3 -> LEAF_3;", "INT_4:p5 -> LEAF_5;", "INT_4:p6 -> LEAF_6;",
+        "LEAF_2 -> LEAF_3;", "LEAF_3 -> LEAF_5;", "LEAF_5 -> LEAF_6;",
+    ] {
+        assert!(dot.contains(edge), "{}", edge);
+    }
+    assert!(!dot.contains("LEAF_6 ->"), "the last leaf has no sibling");
+
+    std::fs::remove_file(dot_file).unwrap();
+    std::fs::remove_file(db_file).unwrap();
+}
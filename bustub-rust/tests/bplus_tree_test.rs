@@ -1,34 +1,34 @@
-
-use bustub_rust::buffer::bufferpool_manager::{FrameHeader,BufferPoolManager};
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
 use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
-use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, INTERNAL_PAGE_SLOT_CNT};
-use bustub_rust::include::storage::page::page_guard::{PageguardImpl, ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl};
 use bustub_rust::storage::disk::disk_manager::DiskManager;
-use bustub_rust::include::storage::disk::disk_scheduler::DiskRequest;
 use bustub_rust::{
     storage::disk::disk_scheduler::DiskScheduler,
-    storage::page::page_guard::ReadPageGuard,
     include::buffer::lru_k_replacer::LRUKReplacer,
     buffer::lru_k_replacer::{LRUKReplacerImpl},
 };
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::btree_map::{BTreeMap, Entry};
 use std::sync::Arc;
-use bustub_rust::include::common::config::AccessType::{Index,Unknown,Lookup, Scan};
-use bustub_rust::include::common::config::{IndexPageType, PAGE_SIZE, INVALID_PAGE_ID};
+use bustub_rust::include::common::config::AccessType::Index;
+use bustub_rust::include::common::config::INVALID_PAGE_ID;
 use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
-use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl};
+use bustub_rust::include::storage::page::page_guard::ReadPageGuardImpl;
+use bustub_rust::include::storage::page::b_plus_tree_page::BplusTreePageImpl;
 use bustub_rust::include::common::rid::Rid;
 use bustub_rust::include::common::config::ValueType;
 
-use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{LEAF_PAGE_SLOT_CNT};
 use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl};
 use bustub_rust::include::storage::page::b_plus_tree_internal_page::{KeyType};
 //use bustub_rust::storage::index::b_plus_tree::{InsertablePage, LeafPageGuard, InternalPageGuard};
 
-fn setup_bplus_tree() -> (&'static BufferPoolManager, BplusTree<'static>) {
+fn setup_bplus_tree(db_file: &str) -> (&'static BufferPoolManager, BplusTree) {
             
     // Allocate a header page
-    
-    let dm = DiskManager::new("test.db");
+    // Every test gets its own file, page allocation is persisted in it
+    let _ = std::fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file);
     let scheduler = DiskScheduler::new( dm.unwrap());
     let lru_k_replacer_impl = LRUKReplacerImpl::new(100, 3);
     let bpm = BufferPoolManager::new
@@ -42,16 +42,16 @@ fn setup_bplus_tree() -> (&'static BufferPoolManager, BplusTree<'static>) {
     let bplus_tree = BplusTree::new(
         String::from("test_index"),
         bpm_ref, 
-        255, 10, header_page_id); // 255, 340
+        254, 10, header_page_id); // 254, 340
     (bpm_ref, bplus_tree)
 }
 
 #[test]
 fn test_simple_insert() {
-    let (bpm, mut tree) = setup_bplus_tree();
+    let (bpm, tree) = setup_bplus_tree("bplus_tree_simple_insert_test.db");
 
     // Step 1: Verify the tree is empty
-    assert_eq!(true, tree.is_empty(), "Tree should be empty initially");
+    assert!(tree.is_empty(), "Tree should be empty initially");
     println!("header page id - {}", tree.header_page_id);
     println!("root page id - {}", tree.get_root_page_id());
     
@@ -63,8 +63,9 @@ fn test_simple_insert() {
     let insert_result1 = tree.insert(key1, value);
     let insert_result2 = tree.insert(key2, value);
     let insert_result3 = tree.insert(key3, value);
-    assert_eq!(true, insert_result1, "Insertion should succeed");
-    assert_eq!(true, insert_result2, "Insertion should succeed");
+    assert!(insert_result1.is_ok(), "Insertion should succeed");
+    assert!(insert_result2.is_ok(), "Insertion should succeed");
+    assert!(insert_result3.is_ok(), "Insertion should succeed");
     
     // Step 3: Verify the root page ID is set
     let root_page_id = tree.get_root_page_id();
@@ -73,11 +74,7 @@ fn test_simple_insert() {
 
     // Step 4: Verify the key-value pair exists in the root (leaf) node
     let root_guard = bpm.read_page(root_page_id, Index);
-    let root_page = unsafe {
-        let data = root_guard.as_ref();
-        //println!("data reff {:?}", data);
-        &*(data.as_ptr() as *const BplusTreeLeafPage)
-    };
+    let root_page: BplusTreeLeafPage = BplusTreeLeafPage::decode(root_guard.as_ref()).unwrap();
     assert_eq!(root_page.base_page.get_size(), 3, "Root should contain one key");
     assert_eq!(root_page.key_array[0], key3, "Inserted key should match");
     assert_eq!(root_page.key_array[1], key1, "Inserted key should match");
@@ -87,7 +84,7 @@ fn test_simple_insert() {
 
 // #[test]
 // fn test_leaf_guard() {
-//     let (bpm, mut tree) = setup_bplus_tree();
+//     let (bpm, tree) = setup_bplus_tree();
     
 //     let mut leaf_guard = LeafPageGuard::new(bpm.write_page(1000, Index));
 //     leaf_guard.initialize(1000, 100);
@@ -119,7 +116,7 @@ fn test_simple_insert() {
 
 // //#[test]
 // fn test_initialize_with_root() {
-//     let (bpm, mut tree) = setup_bplus_tree();
+//     let (bpm, tree) = setup_bplus_tree();
 
 //     // Step 1: Create a HeaderPageGuard
 //     let mut header = tree.acquire_header_guard();
@@ -237,15 +234,15 @@ fn test_simple_insert() {
 
 #[test]
 fn test_insertion_with_split() {
-    let (bpm, mut tree) = setup_bplus_tree();
+    let (bpm, tree) = setup_bplus_tree("bplus_tree_split_test.db");
 
     // Create a loop which sequentially inserts the keys into the tree
     for i in 0..10000{
         let key: KeyType = i as i64;
-        let value= ValueType::Rid(Rid::new(i as i32, 0)); //ValueType::Rid(Rid::new(1, 0))
+        let value= ValueType::Rid(Rid::new(i, 0)); //ValueType::Rid(Rid::new(1, 0))
         let result = tree.insert(key, value);
-        println!("Key {}",result);
-        assert!(result, "Insert succeded for the key {}", key);
+        println!("Key {:?}",result);
+        assert!(result.is_ok(), "Insert succeded for the key {}", key);
     }
 
     
@@ -257,11 +254,7 @@ fn test_insertion_with_split() {
     assert_ne!(root_page_id, INVALID_PAGE_ID, "Root page id should be set");
 
     let root_guard = bpm.read_page(root_page_id, Index);
-    let root_page = unsafe {
-        let data = root_guard.as_ref();
-        let page = &*(data.as_ptr() as *const BplusTreeInternalPage);
-        page
-    };
+    let root_page: BplusTreeInternalPage = BplusTreeInternalPage::decode(root_guard.as_ref()).unwrap();
 //     assert_eq!(root_page.page_type, IndexPageType::INTERNAL_PAGE, "Root should be an internal page after split");
 
 //     // Optionally, verify the structure (e.g., two child pages)
@@ -281,4 +274,219 @@ fn test_insertion_with_split() {
 //     };
 
 //     println!("{:?}", leaf_page.key_array);
-}
\ No newline at end of file
+}
+fn rid_for(key: KeyType) -> Rid {
+    Rid::new(key as i32, (key % 7) as u32)
+}
+
+#[test]
+fn test_tree_survives_pool_pressure_and_restart() {
+    let db_file = "bplus_tree_restart_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let num_keys = 5000;
+    let header_page_id = {
+        // Far fewer frames than tree pages, so pages keep getting evicted and read back
+        let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+        let bpm = BufferPoolManager::new(8, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(8, 2)));
+        let tree = BplusTree::new(String::from("restart_index"), &bpm, 254, 337, INVALID_PAGE_ID);
+        for key in 0..num_keys {
+            assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+        }
+        for key in 0..num_keys {
+            assert_eq!(tree.get_value(key), vec![rid_for(key)], "Key {} lost under pool pressure", key);
+        }
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm = BufferPoolManager::new(8, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(8, 2)));
+    let tree = BplusTree::new(String::from("restart_index"), &bpm, 254, 337, header_page_id);
+    assert!(!tree.is_empty());
+    for key in 0..num_keys {
+        assert_eq!(tree.get_value(key), vec![rid_for(key)], "Key {} lost after restart", key);
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_reopened_tree_keeps_splitting() {
+    let db_file = "bplus_tree_reopen_insert_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let mut keys: Vec<KeyType> = (0..3000).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    let (before, after) = keys.split_at(50);
+    let header_page_id = {
+        let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+        let bpm = BufferPoolManager::new(16, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(16, 2)));
+        let tree = BplusTree::new(String::from("reopen_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+        for &key in before {
+            assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+        }
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    // Nothing about the shape of the tree is known up front, splits of the
+    // leaves and of every internal level have to find their parents on disk
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm = BufferPoolManager::new(16, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(16, 2)));
+    let tree = BplusTree::new(String::from("reopen_index"), &bpm, 4, 4, header_page_id);
+    let old_root = tree.get_root_page_id();
+    for &key in after {
+        assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+    }
+    assert_ne!(tree.get_root_page_id(), old_root, "The root split after the restart");
+    for key in 0..3000 {
+        assert_eq!(tree.get_value(key), vec![rid_for(key)], "Key {} lost after the restart", key);
+    }
+    assert_eq!(tree.begin().map(|(key, _)| key).collect::<Vec<_>>(), (0..3000).collect::<Vec<_>>());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_get_value_on_empty_tree() {
+    let (_bpm, tree) = setup_bplus_tree("bplus_tree_get_empty_test.db");
+    // No header page yet
+    assert!(tree.get_value(1).is_empty());
+    // A header without a root
+    assert!(tree.is_empty());
+    assert!(tree.get_value(1).is_empty());
+    assert!(tree.insert(1, ValueType::Rid(rid_for(1))).is_ok());
+    assert_eq!(tree.get_value(1), vec![rid_for(1)]);
+    assert!(tree.get_value(2).is_empty());
+
+    std::fs::remove_file("bplus_tree_get_empty_test.db").unwrap();
+}
+
+#[test]
+fn test_get_value_across_splits() {
+    let db_file = "bplus_tree_get_value_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm = BufferPoolManager::new(32, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(32, 2)));
+    // Tiny pages give a tree several levels deep
+    let tree = BplusTree::new(String::from("get_value_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    let mut keys: Vec<KeyType> = (0..2000).map(|key| key * 2).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    for &key in keys.iter() {
+        assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+    }
+
+    for key in -2..4002 {
+        let expected = (key % 2 == 0 && (0..4000).contains(&key)).then(|| rid_for(key));
+        assert_eq!(tree.get_value(key), Vec::from_iter(expected), "Lookup of key {}", key);
+    }
+    // Lookups only take read latches, every page is unpinned afterwards
+    let root_page_id = tree.get_root_page_id();
+    assert_eq!(bpm.get_pin_count(root_page_id), Some(0));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+fn setup_small_tree(db_file: &str, leaf_max_size: i32, internal_max_size: i32) -> (&'static BufferPoolManager, BplusTree) {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm: &'static BufferPoolManager =
+        Box::leak(Box::new(BufferPoolManager::new(32, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(32, 2)))));
+    let tree = BplusTree::new(String::from("small_index"), bpm, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+    (bpm, tree)
+}
+
+#[test]
+fn test_remove_from_root_leaf() {
+    let db_file = "bplus_tree_remove_root_test.db";
+    let (_bpm, tree) = setup_small_tree(db_file, 4, 4);
+    // Removing from a tree without a root is a no-op
+    tree.remove(1, rid_for(1));
+    for key in [3, 1, 2] {
+        assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+    }
+    tree.remove(5, rid_for(5));
+    tree.remove(2, rid_for(2));
+    assert!(tree.get_value(2).is_empty());
+    assert_eq!(tree.get_value(1), vec![rid_for(1)]);
+    tree.remove(1, rid_for(1));
+    tree.remove(3, rid_for(3));
+    assert!(tree.is_empty(), "The last remove frees the root");
+    assert_eq!(tree.get_root_page_id(), INVALID_PAGE_ID);
+    assert!(tree.get_value(3).is_empty());
+    // The tree grows again from scratch
+    assert!(tree.insert(7, ValueType::Rid(rid_for(7))).is_ok());
+    assert_eq!(tree.get_value(7), vec![rid_for(7)]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_remove_shrinks_the_tree() {
+    let db_file = "bplus_tree_remove_shrink_test.db";
+    let (bpm, tree) = setup_small_tree(db_file, 4, 4);
+    let num_keys = 500;
+    for key in 0..num_keys {
+        assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+    }
+    let tall_root = tree.get_root_page_id();
+
+    let mut keys: Vec<KeyType> = (0..num_keys).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    let (removed, kept) = keys.split_at(num_keys as usize - 3);
+    for &key in removed {
+        tree.remove(key, rid_for(key));
+    }
+    for key in 0..num_keys {
+        let expected = kept.contains(&key).then(|| rid_for(key));
+        assert_eq!(tree.get_value(key), Vec::from_iter(expected), "Lookup of key {}", key);
+    }
+    // Three keys fit in a single leaf, which is the root again
+    let root_page_id = tree.get_root_page_id();
+    assert_ne!(root_page_id, tall_root);
+    let root_guard = bpm.read_page(root_page_id, Index);
+    let root: BplusTreeLeafPage = BplusTreeLeafPage::decode(root_guard.as_ref()).unwrap();
+    assert_eq!(root.base_page.get_size(), 3);
+    drop(root_guard);
+
+    for &key in kept {
+        tree.remove(key, rid_for(key));
+    }
+    assert!(tree.is_empty());
+    // Nobody else pinned the pages, so every delete went through
+    assert_eq!((bpm.pending_delete_count(), bpm.leaked_page_count()), (0, 0));
+    // Freed pages are handed out again instead of growing the file
+    let reused = bpm.new_page().unwrap();
+    assert!(reused < tall_root + 10, "Page {} was not recycled", reused);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_random_inserts_and_removes_match_a_model() {
+    let db_file = "bplus_tree_remove_model_test.db";
+    let (_bpm, tree) = setup_small_tree(db_file, 3, 3);
+    let mut model = BTreeMap::new();
+    let mut rng = rand::thread_rng();
+    for _ in 0..4000 {
+        let key: KeyType = rng.gen_range(0..300);
+        if rng.gen_bool(0.6) {
+            if let Entry::Vacant(entry) = model.entry(key) {
+                assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+                entry.insert(rid_for(key));
+            }
+        } else {
+            tree.remove(key, rid_for(key));
+            model.remove(&key);
+        }
+    }
+    for key in 0..300 {
+        assert_eq!(tree.get_value(key), Vec::from_iter(model.get(&key).copied()), "Lookup of key {}", key);
+    }
+    for key in model.keys().copied().collect::<Vec<_>>() {
+        tree.remove(key, rid_for(key));
+        assert!(tree.get_value(key).is_empty());
+    }
+    assert!(tree.is_empty());
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -1,22 +1,19 @@
-use bustub_rust::buffer::bufferpool_manager::{FrameHeader,BufferPoolManager};
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
 use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
 use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, KeyType, INTERNAL_PAGE_SLOT_CNT};
 use bustub_rust::include::storage::page::page_guard::{PageguardImpl, ReadPageGuardImpl, WritePageGuardImpl};
 use bustub_rust::storage::disk::disk_manager::DiskManager;
-use bustub_rust::include::storage::disk::disk_scheduler::DiskRequest;
 use bustub_rust::{
     storage::disk::disk_scheduler::DiskScheduler,
-    storage::page::page_guard::ReadPageGuard,
     include::buffer::lru_k_replacer::LRUKReplacer,
     buffer::lru_k_replacer::{LRUKReplacerImpl},
 };
 use std::sync::Arc;
-use bustub_rust::include::common::config::AccessType::{Index,Unknown,Lookup, Scan};
-use bustub_rust::include::common::config::{IndexPageType, ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::config::AccessType::Index;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID, PAGE_SIZE};
 use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
-use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePage, BplusTreePageImpl};
+use bustub_rust::include::storage::page::b_plus_tree_page::BplusTreePageImpl;
 use bustub_rust::include::common::rid::Rid;
-use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{LEAF_PAGE_SLOT_CNT};
 
 #[test]
 fn test_basic_bpm() {
@@ -25,7 +22,9 @@ fn test_basic_bpm() {
 
 #[test]
 fn test_bpm_initialization(){
-    let dm = DiskManager::new("test.db");
+    let db_file = "bpm_initialization_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file);
     let scheduler = DiskScheduler::new( dm.unwrap());
     let lru_k_replacer_impl = LRUKReplacerImpl::new(100, 3);
     let bpm = BufferPoolManager::new
@@ -35,11 +34,14 @@ fn test_bpm_initialization(){
             Arc::new(lru_k_replacer_impl),
         );
     assert_eq!(bpm.size(),10, "Test failed. Values must match");
+    std::fs::remove_file(db_file).unwrap();
 }
 
 #[test]
 fn test_bpm_read_page(){
-    let dm = DiskManager::new("test.db");
+    let db_file = "bpm_read_page_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file);
     let scheduler = DiskScheduler::new( dm.unwrap());
     let lru_k_replacer_impl = LRUKReplacerImpl::new(100, 3);
     let bpm = BufferPoolManager::new
@@ -58,18 +60,19 @@ fn test_bpm_read_page(){
         assert_eq!(page_data_ref.len(), PAGE_SIZE);
         let new_value = [1u8; PAGE_SIZE];
         page_data_ref.copy_from_slice(&new_value);
-        write_page_guard.flush();
+        write_page_guard.flush().unwrap();
     }
     // Create a read request
     {
         let expected_value = [1u8; PAGE_SIZE];
         let read_page_guard = bpm.read_page(100, Index);
         let read_page_ref = read_page_guard.as_ref();
-        read_page_guard.flush();
+        read_page_guard.flush().unwrap();
         assert_eq!(read_page_ref.len(), PAGE_SIZE, "length doesnt match");
         assert_eq!(read_page_ref, expected_value, "Values are equal");
         assert_eq!(read_page_ref[0], 1,"Value at index 0 is 1");
     }
+    std::fs::remove_file(db_file).unwrap();
 }
 
 #[test]
@@ -77,7 +80,9 @@ fn test_bplustree_index_leaf_page(){
     // Create bplus tree leaf page 
     //let bplus_tree_page = BplusTreePage::new(IndexPageType::LEAF_PAGE, 0, 1000);
     //let rid = Rid::new(0, 1);
-    let dm = DiskManager::new("test.db");
+    let db_file = "bpm_leaf_page_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file);
     let scheduler = DiskScheduler::new( dm.unwrap());
     let lru_k_replacer_impl = LRUKReplacerImpl::new(100, 3);
     let bpm = BufferPoolManager::new
@@ -95,25 +100,20 @@ fn test_bplustree_index_leaf_page(){
     let value = ValueType::Rid(Rid::new(10, 0));
     let key1: KeyType = 43;
     let value1 = ValueType::Rid(Rid::new(10, 1));
-    let result1 = bplus_tree_leaf_page.insert(0, key, value);
-    let result2 = bplus_tree_leaf_page.insert(1, key1, value1);
+    assert!(bplus_tree_leaf_page.insert(0, key, value));
+    assert!(bplus_tree_leaf_page.insert(1, key1, value1));
     
     /////////////////////
-    let mut write_page_guard= bpm.write_page(100, Index);
-    let page_data_ref=write_page_guard.as_mut();
-    unsafe {
-        let data_ptr = page_data_ref.as_mut_ptr() as *mut BplusTreeLeafPage;
-        std::ptr::write(data_ptr, bplus_tree_leaf_page);
+    {
+        let mut write_page_guard= bpm.write_page(100, Index);
+        bplus_tree_leaf_page.encode(write_page_guard.as_mut());
+        write_page_guard.flush().unwrap();
     }
-    write_page_guard.flush();
 
     let read_page_guard = bpm.read_page(100, Index);
     let read_data = read_page_guard.as_ref();
     assert_eq!(read_data.len(), PAGE_SIZE, "Read page size mismatch");
-    let read_leaf = unsafe {
-        let data_ptr = read_data.as_ptr() as *const BplusTreeLeafPage;
-        &*data_ptr
-    };
+    let read_leaf: BplusTreeLeafPage = BplusTreeLeafPage::decode(read_data).unwrap();
 
     assert_eq!(read_leaf.next_page_id, INVALID_PAGE_ID, "Read back failed");
     assert_eq!(read_leaf.base_page.get_max_size(), 1000, "Max size mismatch");
@@ -125,6 +125,7 @@ fn test_bplustree_index_leaf_page(){
     let page_table = bpm.page_table.lock().unwrap(); // Assuming page_table is a Mutex<HashMap<PageId, FrameId>>
     println!("last page table {:?}", page_table);
     assert!(page_table.contains_key(&100), "Page 1 should be in page_table");
+    std::fs::remove_file(db_file).unwrap();
 }
 
 
@@ -133,7 +134,9 @@ fn test_bplustree_index_leaf_page(){
 
 #[test]
 fn test_bplustree_index_internal_page() {
-    let dm = DiskManager::new("test.db");
+    let db_file = "bpm_internal_page_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file);
     let scheduler = DiskScheduler::new( dm.unwrap());
     let lru_k_replacer_impl = LRUKReplacerImpl::new(100, 3);
     let bpm = BufferPoolManager::new
@@ -142,23 +145,303 @@ fn test_bplustree_index_internal_page() {
             Arc::new(scheduler),
             Arc::new(lru_k_replacer_impl),
         );
-    let bplus_tree_internal_page = BplusTreeInternalPage::new(1000, 100);
+    let bplus_tree_internal_page: BplusTreeInternalPage = BplusTreeInternalPage::new(1000, 100);
     assert_eq!(bplus_tree_internal_page.base_page.max_size, 1000, "Invalid page size");
-    let mut write_page_guard= bpm.write_page(100, Index);
-    let page_data_ref=write_page_guard.as_mut();
-    unsafe {
-        let data_ptr = page_data_ref.as_mut_ptr() as *mut BplusTreeInternalPage;
-        std::ptr::write(data_ptr, bplus_tree_internal_page);
+    {
+        let mut write_page_guard= bpm.write_page(100, Index);
+        bplus_tree_internal_page.encode(write_page_guard.as_mut());
+        write_page_guard.flush().unwrap();
     }
-    write_page_guard.flush();
     //lru_k_replacer_impl.set_evictable(123, true);
 
     let read_page_guard = bpm.read_page(100, Index);
     let read_data = read_page_guard.as_ref();
     assert_eq!(read_data.len(), PAGE_SIZE, "Read page size mismatch");
-    let read_internal = unsafe {
-        let data_ptr = read_data.as_ptr() as *const BplusTreeInternalPage;
-        &*data_ptr
+    let read_internal: BplusTreeInternalPage = BplusTreeInternalPage::decode(read_data).unwrap();
+    assert_eq!(read_internal.key_array, [-1;INTERNAL_PAGE_SLOT_CNT], " ");
+    std::fs::remove_file(db_file).unwrap();
+}
+#[test]
+fn test_delete_page_reuses_page_id() {
+    let db_file = "bpm_delete_page_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let new_bpm = || {
+        let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+        BufferPoolManager::new(10, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(10, 2)))
     };
-    assert_eq!(read_internal.key_array, [-1;INTERNAL_PAGE_SLOT_CNT], " ")
-}
\ No newline at end of file
+    let bpm = new_bpm();
+
+    let page_ids: Vec<_> = (0..3).map(|_| bpm.new_page().unwrap()).collect();
+    {
+        // A pinned page can't be deleted
+        let _guard = bpm.write_page(page_ids[1], Index);
+        assert!(!bpm.delete_page(page_ids[1]));
+    }
+    assert!(bpm.delete_page(page_ids[1]));
+    assert_eq!(bpm.get_pin_count(page_ids[1]), None, "Deleted page should leave the pool");
+    assert!(!bpm.delete_page(page_ids[1]), "Page is already free");
+    assert_eq!(bpm.new_page().unwrap(), page_ids[1], "Freed page should be reused");
+
+    // The id sequence and free list are recovered from the db file
+    assert!(bpm.delete_page(page_ids[0]));
+    drop(bpm);
+    let bpm = new_bpm();
+    assert_eq!(bpm.new_page().unwrap(), page_ids[0]);
+    assert_eq!(bpm.new_page().unwrap(), page_ids[2] + 1);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_deferred_delete_waits_for_the_last_pin() {
+    let db_file = "bpm_deferred_delete_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let page_ids: Vec<_> = (0..2).map(|_| bpm.new_page().unwrap()).collect();
+
+    // An unpinned page goes right away
+    bpm.delete_page_deferred(page_ids[0]);
+    assert_eq!(bpm.pending_delete_count(), 0);
+    assert!(!bpm.delete_page(page_ids[0]), "Page is already free");
+
+    // A pinned page waits until both readers are done
+    let first = bpm.read_page(page_ids[1], Index);
+    let second = bpm.read_page(page_ids[1], Index);
+    bpm.delete_page_deferred(page_ids[1]);
+    assert_eq!(bpm.pending_delete_count(), 1);
+    drop(first);
+    assert_eq!(bpm.get_pin_count(page_ids[1]), Some(1), "Still held by the second reader");
+    drop(second);
+    assert_eq!(bpm.pending_delete_count(), 0);
+    assert_eq!(bpm.get_pin_count(page_ids[1]), None, "Deleted page should leave the pool");
+    assert_eq!(bpm.new_page().unwrap(), page_ids[1], "Freed page should be reused");
+
+    // A page the disk manager can't free is counted, not lost silently
+    assert_eq!(bpm.leaked_page_count(), 0);
+    bpm.delete_page_deferred(1000);
+    assert_eq!(bpm.leaked_page_count(), 1);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+#[test]
+fn test_eviction_writes_back_dirty_pages() {
+    let db_file = "bpm_eviction_test.db";
+    let bpm = setup_bpm(db_file, 3);
+
+    // Twice as many pages as frames, each with its own content
+    let page_ids: Vec<_> = (0..6).map(|_| bpm.new_page().unwrap()).collect();
+    for (i, &page_id) in page_ids.iter().enumerate() {
+        let mut guard = bpm.write_page(page_id, Index);
+        guard.as_mut().copy_from_slice(&[i as u8 + 1; PAGE_SIZE]);
+    }
+    assert!(bpm.page_table.lock().unwrap().len() <= bpm.size());
+
+    for (i, &page_id) in page_ids.iter().enumerate() {
+        let guard = bpm.read_page(page_id, Index);
+        assert_eq!(guard.as_ref(), &[i as u8 + 1; PAGE_SIZE], "Page {} lost its contents", page_id);
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_no_eviction_while_pinned() {
+    let db_file = "bpm_pinned_test.db";
+    let bpm = setup_bpm(db_file, 2);
+    let page_ids: Vec<_> = (0..3).map(|_| bpm.new_page().unwrap()).collect();
+
+    let guard0 = bpm.read_page(page_ids[0], Index);
+    let guard1 = bpm.write_page(page_ids[1], Index);
+    assert_eq!(bpm.get_pin_count(page_ids[0]), Some(1));
+    assert!(bpm.checked_read_page(page_ids[2], Index).is_none(), "Every frame is pinned");
+
+    // Unpinning a frame makes it the victim
+    drop(guard0);
+    assert_eq!(bpm.get_pin_count(page_ids[0]), Some(0));
+    let guard2 = bpm.checked_read_page(page_ids[2], Index).expect("Frame should have been evicted");
+    assert_eq!(guard2.get_frame_id(), 0);
+    assert_eq!(bpm.get_pin_count(page_ids[0]), None, "Evicted page leaves the page table");
+    assert_eq!(bpm.get_pin_count(page_ids[1]), Some(1));
+    drop(guard1);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_flush_page_persists_to_disk() {
+    let db_file = "bpm_flush_test.db";
+    let bpm = setup_bpm(db_file, 4).with_fsync_on_flush(true);
+
+    let page_a = bpm.new_page().unwrap();
+    let page_b = bpm.new_page().unwrap();
+    {
+        let mut guard = bpm.write_page(page_a, Index);
+        guard.as_mut().copy_from_slice(&[7u8; PAGE_SIZE]);
+        assert!(guard.is_dirty());
+        guard.flush().unwrap();
+        assert!(!guard.is_dirty(), "A flushed page should be clean");
+    }
+    {
+        let mut guard = bpm.write_page(page_b, Index);
+        guard.as_mut().copy_from_slice(&[9u8; PAGE_SIZE]);
+    }
+    assert!(bpm.flush_page(page_b).unwrap());
+    assert!(!bpm.flush_page(page_b + 100).unwrap(), "Pages outside the pool are not flushed");
+
+    // Read straight from the file, bypassing the buffer pool
+    let dm = DiskManager::new(db_file).unwrap();
+    let mut data = [0u8; PAGE_SIZE];
+    dm.read_page(page_a, &mut data).unwrap();
+    assert_eq!(data, [7u8; PAGE_SIZE]);
+    dm.read_page(page_b, &mut data).unwrap();
+    assert_eq!(data, [9u8; PAGE_SIZE]);
+
+    {
+        let mut guard = bpm.write_page(page_a, Index);
+        guard.as_mut().copy_from_slice(&[8u8; PAGE_SIZE]);
+    }
+    bpm.flush_all_pages().unwrap();
+    dm.read_page(page_a, &mut data).unwrap();
+    assert_eq!(data, [8u8; PAGE_SIZE]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_flush_surfaces_io_errors() {
+    let db_file = "bpm_flush_error_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let page_id = DiskManager::new(db_file).unwrap().allocate_page().unwrap();
+
+    // Writes through a read-only handle fail, reads still work
+    let mut dm = DiskManager::new(db_file).unwrap();
+    dm.file = Arc::new(std::fs::File::open(db_file).unwrap());
+    let bpm = BufferPoolManager::new(2, Arc::new(DiskScheduler::new(dm)), Arc::new(LRUKReplacerImpl::new(2, 2)));
+
+    let mut guard = bpm.write_page(page_id, Index);
+    guard.as_mut()[0] = 1;
+    assert!(guard.flush().is_err());
+    assert!(guard.is_dirty(), "A failed flush must leave the page dirty");
+    drop(guard);
+    assert!(bpm.flush_page(page_id).is_err());
+    assert!(bpm.flush_all_pages().is_err());
+    assert_eq!(bpm.read_page(page_id, Index).as_ref()[0], 1, "The page keeps its unflushed contents");
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_new_page_surfaces_io_errors() {
+    let db_file = "bpm_new_page_error_test.db";
+    let _ = std::fs::remove_file(db_file);
+    let page_id = DiskManager::new(db_file).unwrap().allocate_page().unwrap();
+
+    // Allocating writes the header page, which a read-only handle can't
+    let mut dm = DiskManager::new(db_file).unwrap();
+    dm.file = Arc::new(std::fs::File::open(db_file).unwrap());
+    let bpm = BufferPoolManager::new(2, Arc::new(DiskScheduler::new(dm)), Arc::new(LRUKReplacerImpl::new(2, 2)));
+    assert!(bpm.new_page().is_err());
+    assert_eq!(bpm.read_page(page_id, Index).as_ref()[0], 0, "The pool keeps working");
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_pages_are_read_back_after_restart() {
+    let db_file = "bpm_restart_test.db";
+    let page_ids: Vec<_> = {
+        let bpm = setup_bpm(db_file, 4);
+        let page_ids: Vec<_> = (0..8).map(|_| bpm.new_page().unwrap()).collect();
+        for (i, &page_id) in page_ids.iter().enumerate() {
+            let mut guard = bpm.write_page(page_id, Index);
+            guard.as_mut().copy_from_slice(&[i as u8 + 1; PAGE_SIZE]);
+        }
+        bpm.flush_all_pages().unwrap();
+        page_ids
+    };
+
+    // A fresh pool over the same file starts with every frame free
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm = BufferPoolManager::new(2, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(2, 2)));
+    for (i, &page_id) in page_ids.iter().enumerate() {
+        let guard = bpm.read_page(page_id, Index);
+        assert_eq!(guard.as_ref(), &[i as u8 + 1; PAGE_SIZE], "Page {} was not loaded from disk", page_id);
+    }
+    // Pages that can't be read are not handed out
+    assert!(bpm.checked_read_page(0, Index).is_none());
+    assert!(bpm.checked_read_page(-5, Index).is_none());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_clones_share_one_pool() {
+    let db_file = "bpm_clone_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let handle = bpm.clone();
+
+    // Both handles draw from the same page id sequence
+    let first = bpm.new_page().unwrap();
+    let second = handle.new_page().unwrap();
+    assert_eq!(second, first + 1);
+
+    {
+        let mut guard = handle.write_page(first, Index);
+        guard.as_mut()[0] = 42;
+        assert_eq!(bpm.get_pin_count(first), Some(1), "Pages fetched through a clone are pinned in the original");
+    }
+    assert_eq!(bpm.get_pin_count(first), Some(0));
+    assert!(bpm.page_table.lock().unwrap().contains_key(&first));
+    assert_eq!(bpm.read_page(first, Index).as_ref()[0], 42);
+
+    // Deleting through one handle is visible through the other
+    assert!(handle.delete_page(first));
+    assert_eq!(bpm.get_pin_count(first), None);
+    assert_eq!(bpm.new_page().unwrap(), first, "The freed page id is reused");
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_shared_pages_survive_concurrent_eviction() {
+    let db_file = "bpm_shared_eviction_test.db";
+    let bpm = setup_bpm(db_file, 3);
+    let page_ids: Vec<_> = (0..8).map(|_| bpm.new_page().unwrap()).collect();
+    let (num_threads, rounds) = (4, 50);
+
+    // Every thread bumps a counter on every page, so pages are fetched while another
+    // thread is still writing them back or reading them in
+    let handles: Vec<_> = (0..num_threads)
+        .map(|offset| {
+            let bpm = bpm.clone();
+            let page_ids = page_ids.clone();
+            std::thread::spawn(move || {
+                for round in 0..rounds {
+                    let page_id = page_ids[(round + offset) % page_ids.len()];
+                    let mut guard = loop {
+                        if let Some(guard) = bpm.checked_write_page(page_id, Index) {
+                            break guard;
+                        }
+                        std::thread::yield_now();
+                    };
+                    let count = u32::from_le_bytes(guard.as_ref()[..4].try_into().unwrap());
+                    guard.as_mut()[..4].copy_from_slice(&(count + 1).to_le_bytes());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let total: u32 = page_ids.iter().map(|&page_id| u32::from_le_bytes(bpm.read_page(page_id, Index).as_ref()[..4].try_into().unwrap())).sum();
+    assert_eq!(total as usize, num_threads * rounds, "Lost an update to an evicted page");
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -0,0 +1,189 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{AccessType, PageId, ValueType, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, DuplicateKey, IndexMode};
+use bustub_rust::include::storage::index::generic_key::OrdComparator;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, KeyType};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::include::storage::page::b_plus_tree_page::BplusTreePageImpl;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::BplusTreeInternalPageImpl;
+use bustub_rust::include::storage::page::page_guard::ReadPageGuardImpl;
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn rid_for(key: KeyType) -> Rid {
+    Rid::new(key as i32, 3)
+}
+
+// Leaf sizes along the leaf chain, starting at the leftmost leaf
+fn leaf_sizes(bpm: &BufferPoolManager, tree: &BplusTree) -> Vec<i32> {
+    let mut page_id = tree.get_root_page_id();
+    loop {
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        match BplusTreeInternalPage::<KeyType>::decode(guard.as_ref()) {
+            Ok(internal) => page_id = internal.page_id_value_at(0),
+            Err(_) => break,
+        }
+    }
+    let mut sizes = Vec::new();
+    while page_id != INVALID_PAGE_ID {
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        let leaf: BplusTreeLeafPage = BplusTreeLeafPage::decode(guard.as_ref()).unwrap();
+        sizes.push(leaf.base_page.get_size());
+        page_id = leaf.get_next_page_id();
+    }
+    sizes
+}
+
+#[test]
+fn test_bulk_load_fills_leaves_to_the_fill_factor() {
+    let db_file = "bulk_load_full_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let tree = BplusTree::new(String::from("loaded_index"), &bpm, 10, 10, INVALID_PAGE_ID);
+    tree.bulk_load((0..10_000).map(|key| (key, rid_for(key))), 1.0).unwrap();
+
+    assert_eq!(leaf_sizes(&bpm, &tree), vec![10; 1000]);
+    for key in (0..10_000).step_by(7) {
+        assert_eq!(tree.get_value(key), vec![rid_for(key)]);
+    }
+    assert!(tree.begin().map(|(key, _)| key).eq(0..10_000));
+
+    // The same keys inserted one by one leave the leaves about half full
+    let db_file_by_insert = "bulk_load_by_insert_test.db";
+    let bpm_by_insert = setup_bpm(db_file_by_insert, 64);
+    let tree_by_insert = BplusTree::new(String::from("inserted_index"), &bpm_by_insert, 10, 10, INVALID_PAGE_ID);
+    for key in 0..10_000 {
+        tree_by_insert.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+    }
+    assert!(leaf_sizes(&bpm_by_insert, &tree_by_insert).len() > 1900);
+
+    std::fs::remove_file(db_file).unwrap();
+    std::fs::remove_file(db_file_by_insert).unwrap();
+}
+
+#[test]
+fn test_bulk_load_sorts_and_keeps_pages_balanced() {
+    let db_file = "bulk_load_unsorted_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let tree = BplusTree::new(String::from("loaded_index"), &bpm, 8, 6, INVALID_PAGE_ID);
+    let mut keys: Vec<KeyType> = (0..2003).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    tree.bulk_load(keys.iter().map(|&key| (key, rid_for(key))), 0.3).unwrap();
+
+    // A low fill factor still leaves every leaf at least half full
+    let sizes = leaf_sizes(&bpm, &tree);
+    assert!(sizes.iter().all(|&size| size == 4 || size == 5), "{:?}", sizes);
+    assert!(tree.begin().map(|(key, _)| key).eq(0..2003));
+
+    // The loaded tree splits and merges like any other
+    for key in 2003..2500 {
+        tree.insert(key, ValueType::Rid(rid_for(key))).unwrap();
+    }
+    for &key in keys.iter() {
+        tree.remove(key, rid_for(key));
+    }
+    assert!(tree.begin().map(|(key, _)| key).eq(2003..2500));
+    tree.check_integrity().unwrap();
+    for key in 2003..2500 {
+        tree.remove(key, rid_for(key));
+    }
+    assert!(tree.is_empty());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_packs_internal_pages_at_their_min_size() {
+    let db_file = "bulk_load_sparse_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    // An internal max size of 5 keys needs 5 / 2 + 1 = 3 children, a low fill factor
+    // packs every internal page with exactly that many
+    let tree = BplusTree::new(String::from("loaded_index"), &bpm, 4, 5, INVALID_PAGE_ID);
+    tree.bulk_load((0..54).map(|key| (key, rid_for(key))), 0.1).unwrap();
+    assert_eq!(leaf_sizes(&bpm, &tree), vec![2; 27]);
+    let stats = tree.stats();
+    assert_eq!((stats.height, stats.internal_pages, stats.children), (4, 13, 39));
+    tree.check_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_small_inputs() {
+    let db_file = "bulk_load_small_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("loaded_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    tree.bulk_load(Vec::new(), 1.0).unwrap();
+    assert!(tree.is_empty());
+
+    // A single root leaf
+    tree.bulk_load([(2, rid_for(2)), (1, rid_for(1))], 1.0).unwrap();
+    assert_eq!(leaf_sizes(&bpm, &tree), vec![2]);
+    let root: PageId = tree.get_root_page_id();
+    let guard = bpm.read_page(root, AccessType::Index);
+    assert!(BplusTreeLeafPage::<KeyType>::decode(guard.as_ref()).is_ok());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_rejects_bad_input() {
+    let db_file = "bulk_load_errors_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("loaded_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+
+    let err = tree.bulk_load([(1, rid_for(1))], 0.0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = tree.bulk_load([(1, rid_for(1)), (2, rid_for(2)), (1, Rid::new(7, 7))], 1.0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let duplicate = err.get_ref().unwrap().downcast_ref::<DuplicateKey>().unwrap();
+    assert!(duplicate.existing == rid_for(1) || duplicate.existing == Rid::new(7, 7));
+    assert!(tree.is_empty(), "nothing is loaded on an error");
+
+    tree.insert(5, ValueType::Rid(rid_for(5))).unwrap();
+    let err = tree.bulk_load([(1, rid_for(1))], 1.0).unwrap_err();
+    assert!(err.to_string().contains("empty index"), "{}", err);
+    assert_eq!(tree.get_value(1), vec![]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_non_unique_and_restart() {
+    let db_file = "bulk_load_non_unique_test.db";
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 16);
+        let tree = BplusTree::with_mode(String::from("secondary_index"), &bpm, OrdComparator, IndexMode::NonUnique, 4, 4, INVALID_PAGE_ID);
+        let entries = (0..600).map(|slot| (slot as KeyType % 5, Rid::new(1, slot)));
+        tree.bulk_load(entries, 0.75).unwrap();
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    let bpm = BufferPoolManager::new(
+        16,
+        Arc::new(DiskScheduler::new(DiskManager::new(db_file).unwrap())),
+        Arc::new(LRUKReplacerImpl::new(16, 2)),
+    );
+    let tree = BplusTree::with_mode(String::from("secondary_index"), &bpm, OrdComparator, IndexMode::NonUnique, 4, 4, header_page_id);
+    let expected: Vec<Rid> = (0..600).filter(|slot| slot % 5 == 3).map(|slot| Rid::new(1, slot)).collect();
+    assert_eq!(tree.get_value(3), expected);
+    tree.remove(3, Rid::new(1, 298));
+    tree.insert(3, ValueType::Rid(Rid::new(0, 0))).unwrap();
+    assert_eq!(tree.get_value(3)[0], Rid::new(0, 0));
+    assert_eq!(tree.get_value(3).len(), 120);
+
+    std::fs::remove_file(db_file).unwrap();
+}
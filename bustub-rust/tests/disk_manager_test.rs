@@ -0,0 +1,180 @@
+use bustub_rust::include::common::config::{INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::storage::disk::disk_manager::FIRST_DATA_PAGE_ID;
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+
+#[test]
+fn test_fixed_offset_read_write() {
+    let db_file = "disk_manager_rw_test.db";
+    let _ = fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file).unwrap();
+
+    // Pages are written out of order and overwritten in place
+    dm.write_page(3, &[3u8; PAGE_SIZE]).unwrap();
+    dm.write_page(1, &[1u8; PAGE_SIZE]).unwrap();
+    dm.write_page(3, &[33u8; PAGE_SIZE]).unwrap();
+    assert_eq!(dm.file_size().unwrap(), 4 * PAGE_SIZE as u64, "File should not grow on overwrite");
+
+    let mut data = [0u8; PAGE_SIZE];
+    dm.read_page(1, &mut data).unwrap();
+    assert_eq!(data, [1u8; PAGE_SIZE]);
+    dm.read_page(3, &mut data).unwrap();
+    assert_eq!(data, [33u8; PAGE_SIZE]);
+    // A hole in the file reads back as zeroes
+    dm.read_page(2, &mut data).unwrap();
+    assert_eq!(data, [0u8; PAGE_SIZE]);
+
+    let err = dm.read_page(4, &mut data).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = dm.read_page(-1, &mut data).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    // Page 0 belongs to the disk header
+    let err = dm.write_page(0, &[0u8; PAGE_SIZE]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_increase_disk_space() {
+    let db_file = "disk_manager_grow_test.db";
+    let _ = fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file).unwrap();
+
+    dm.increase_disk_space(8).unwrap();
+    assert_eq!(dm.num_pages().unwrap(), 8);
+    // Never shrinks
+    dm.increase_disk_space(2).unwrap();
+    assert_eq!(dm.num_pages().unwrap(), 8);
+
+    let mut data = [1u8; PAGE_SIZE];
+    dm.read_page(7, &mut data).unwrap();
+    assert_eq!(data, [0u8; PAGE_SIZE], "Allocated pages should read back as zeroes");
+
+    fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_migrate_append_log() {
+    let db_file = "disk_manager_migrate_test.db";
+    let _ = fs::remove_file(db_file);
+    {
+        // Old format: a sequence of (page_id, page bytes) records, last one wins
+        let mut file = fs::File::create(db_file).unwrap();
+        for (page_id, byte) in [(2i32, 2u8), (1, 10), (2, 22)] {
+            file.write_all(&page_id.to_le_bytes()).unwrap();
+            file.write_all(&[byte; PAGE_SIZE]).unwrap();
+        }
+    }
+
+    // Page ids move up past the disk header
+    let mapping = DiskManager::migrate_append_log(db_file).unwrap();
+    assert_eq!(mapping, HashMap::from([(1, 2), (2, 3)]));
+
+    let dm = DiskManager::new(db_file).unwrap();
+    assert_eq!(dm.num_pages().unwrap(), 4);
+    assert_eq!(dm.allocate_page().unwrap(), 4, "Migrated pages should count as allocated");
+    let mut data = [0u8; PAGE_SIZE];
+    dm.read_page(2, &mut data).unwrap();
+    assert_eq!(data, [10u8; PAGE_SIZE]);
+    dm.read_page(3, &mut data).unwrap();
+    assert_eq!(data, [22u8; PAGE_SIZE]);
+
+    // A file that is not a whole number of log records is rejected
+    fs::write(db_file, [0u8; 10]).unwrap();
+    let err = DiskManager::migrate_append_log(db_file).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_migrate_append_log_with_page_zero() {
+    let db_file = "disk_manager_migrate_zero_test.db";
+    let _ = fs::remove_file(db_file);
+    {
+        // Legacy logs numbered their pages from 0
+        let mut file = fs::File::create(db_file).unwrap();
+        for (page_id, byte) in [(0i32, 1u8), (1, 2), (0, 3)] {
+            file.write_all(&page_id.to_le_bytes()).unwrap();
+            file.write_all(&[byte; PAGE_SIZE]).unwrap();
+        }
+    }
+
+    let mapping = DiskManager::migrate_append_log(db_file).unwrap();
+    assert_eq!(mapping, HashMap::from([(0, FIRST_DATA_PAGE_ID), (1, FIRST_DATA_PAGE_ID + 1)]));
+
+    // The header survived and page 0 landed on the first data page
+    let dm = DiskManager::new(db_file).unwrap();
+    assert_eq!(dm.header().next_page_id, FIRST_DATA_PAGE_ID + 2);
+    let mut data = [0u8; PAGE_SIZE];
+    dm.read_page(FIRST_DATA_PAGE_ID, &mut data).unwrap();
+    assert_eq!(data, [3u8; PAGE_SIZE]);
+    dm.read_page(FIRST_DATA_PAGE_ID + 1, &mut data).unwrap();
+    assert_eq!(data, [2u8; PAGE_SIZE]);
+
+    fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_allocate_and_reuse_pages() {
+    let db_file = "disk_manager_alloc_test.db";
+    let _ = fs::remove_file(db_file);
+    let dm = DiskManager::new(db_file).unwrap();
+
+    let pages: Vec<_> = (0..4).map(|_| dm.allocate_page().unwrap()).collect();
+    assert_eq!(pages, vec![FIRST_DATA_PAGE_ID, 2, 3, 4]);
+    assert_eq!(dm.num_pages().unwrap(), 5, "File grows with every allocation");
+
+    dm.write_page(2, &[7u8; PAGE_SIZE]).unwrap();
+    dm.deallocate_page(2).unwrap();
+    dm.deallocate_page(4).unwrap();
+    assert_eq!(dm.header().free_page_count, 2);
+    // Double free and unallocated pages are rejected
+    assert_eq!(dm.deallocate_page(2).unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(dm.deallocate_page(10).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    // Most recently freed page is handed out first, and comes back zeroed
+    assert_eq!(dm.allocate_page().unwrap(), 4);
+    assert_eq!(dm.allocate_page().unwrap(), 2);
+    let mut data = [0u8; PAGE_SIZE];
+    dm.read_page(2, &mut data).unwrap();
+    assert_eq!(data, [0u8; PAGE_SIZE]);
+    assert_eq!(dm.allocate_page().unwrap(), 5);
+    assert_eq!(dm.num_pages().unwrap(), 6, "Reusing pages does not grow the file");
+
+    fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_allocation_state_survives_reopen() {
+    let db_file = "disk_manager_reopen_test.db";
+    let _ = fs::remove_file(db_file);
+    {
+        let dm = DiskManager::new(db_file).unwrap();
+        for _ in 0..5 {
+            dm.allocate_page().unwrap();
+        }
+        dm.deallocate_page(3).unwrap();
+        dm.deallocate_page(1).unwrap();
+    }
+
+    let dm = DiskManager::new(db_file).unwrap();
+    let header = dm.header();
+    assert_eq!(header.next_page_id, 6);
+    assert_eq!(header.free_list_head, 1);
+    assert_eq!(header.free_page_count, 2);
+    assert_eq!(dm.allocate_page().unwrap(), 1);
+    assert_eq!(dm.allocate_page().unwrap(), 3);
+    assert_eq!(dm.header().free_list_head, INVALID_PAGE_ID);
+    assert_eq!(dm.allocate_page().unwrap(), 6);
+
+    // A file whose first page is not a disk header is refused
+    drop(dm);
+    fs::write(db_file, [1u8; PAGE_SIZE]).unwrap();
+    assert_eq!(DiskManager::new(db_file).err().unwrap().kind(), ErrorKind::InvalidData);
+
+    fs::remove_file(db_file).unwrap();
+}
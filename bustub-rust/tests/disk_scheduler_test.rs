@@ -1,8 +1,4 @@
-use bustub_rust::{
-    storage::disk::disk_scheduler::DiskScheduler,
-    include::buffer::lru_k_replacer::LRUKReplacer,
-};
-use std::sync::mpsc::channel;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
 #[cfg(test)]
 mod tests {
     use std::sync::mpsc::channel;
@@ -16,15 +12,20 @@ mod tests {
 
     #[test]
     fn test_disk_scheduler_initialization() {
-        let dm = DiskManager::new("test.db");
-        let scheduler = DiskScheduler::new( dm.unwrap());
+        let db_file = "disk_scheduler_init_test.db";
+        let _ = std::fs::remove_file(db_file);
+        let dm = DiskManager::new(db_file);
+        let _scheduler = DiskScheduler::new( dm.unwrap());
         assert_eq!(1,1,"test passed");
+        std::fs::remove_file(db_file).unwrap();
     }
 
     #[test]
     fn test_disk_read_page() {
-        let dm = DiskManager::new("test.db").unwrap();
-        let mut scheduler = DiskScheduler::new(dm);
+        let db_file = "disk_scheduler_read_test.db";
+        let _ = std::fs::remove_file(db_file);
+        let dm = DiskManager::new(db_file).unwrap();
+        let scheduler = DiskScheduler::new(dm);
         let (tx, rx) = channel();
     
         // Write
@@ -44,8 +45,8 @@ mod tests {
         };
         scheduler.schedule(write_req);
         scheduler.schedule(write_req2);
-        rx.recv().unwrap();
-        rx.recv().unwrap();
+        rx.recv().unwrap().unwrap();
+        rx.recv().unwrap().unwrap();
     
         // 10 Parallel Reads
         let read_data = Arc::new(Mutex::new(vec![0; 4096]));
@@ -59,12 +60,14 @@ mod tests {
             scheduler.schedule(read_req);
         }
         for _ in 0..10 {
-            rx.recv().unwrap(); // Wait for all
+            rx.recv().unwrap().unwrap(); // Wait for all
         }
     
         let result = read_data.lock().unwrap();
         assert_eq!(result.len(), 4096, "Read data length matched");
         assert_eq!(&result[..], &[0xFF; 4096], "Content match");
+        drop(scheduler);
+        std::fs::remove_file(db_file).unwrap();
     }
 
     // Add more test cases here as needed
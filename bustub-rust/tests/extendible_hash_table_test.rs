@@ -0,0 +1,291 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{AccessType, PageId, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::DuplicateKey;
+use bustub_rust::include::storage::index::extendible_hash_table::{ExtendibleHashTable, ExtendibleHashTableImpl};
+use bustub_rust::include::storage::index::generic_key::{DefaultKeyHasher, KeyHasher, OrdComparator, VarcharKey};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::KeyType;
+use bustub_rust::include::storage::page::extendible_htable_bucket_page::ExtendibleHTableBucketPage;
+use bustub_rust::include::storage::page::extendible_htable_directory_page::{ExtendibleHTableDirectoryPage, ExtendibleHTableDirectoryPageImpl};
+use bustub_rust::include::storage::page::extendible_htable_header_page::{ExtendibleHTableHeaderPage, ExtendibleHTableHeaderPageImpl};
+use bustub_rust::include::storage::page::page_guard::ReadPageGuardImpl;
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::thread;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn rid(key: KeyType) -> Rid {
+    Rid::new((key >> 32) as i32, key as u32)
+}
+
+// The key is its own hash, so tests pick the bucket of every key
+struct IdentityHasher;
+
+impl KeyHasher<KeyType> for IdentityHasher {
+    fn hash(&self, key: &KeyType) -> u32 {
+        *key as u32
+    }
+}
+
+fn identity_table(bpm: &BufferPoolManager, directory_max_depth: u32, bucket_max_size: u32) -> ExtendibleHashTable<KeyType, OrdComparator, IdentityHasher> {
+    ExtendibleHashTable::with_hasher(String::from("hash_index"), bpm, OrdComparator, IdentityHasher, 0, directory_max_depth, bucket_max_size, INVALID_PAGE_ID).unwrap()
+}
+
+// (global depth, bucket page of every slot) of the directory at directory_index
+fn directory_of<K, C, H>(table: &ExtendibleHashTable<K, C, H>, directory_index: usize) -> (u32, Vec<PageId>) {
+    let header_guard = table.bpm.read_page(table.header_page_id, AccessType::Index);
+    let header = ExtendibleHTableHeaderPage::decode(header_guard.as_ref(), table.header_page_id).unwrap();
+    let directory_page_id = header.get_directory_page_id(directory_index);
+    let guard = table.bpm.read_page(directory_page_id, AccessType::Index);
+    let directory = ExtendibleHTableDirectoryPage::decode(guard.as_ref(), directory_page_id).unwrap();
+    (directory.get_global_depth(), (0..directory.size()).map(|slot| directory.get_bucket_page_id(slot)).collect())
+}
+
+#[test]
+fn test_insert_get_and_remove() {
+    let db_file = "extendible_hash_table_basic_test.db";
+    let bpm = setup_bpm(db_file, 32);
+    let table = ExtendibleHashTable::new(String::from("hash_index"), &bpm, 1, 9, 16, INVALID_PAGE_ID).unwrap();
+    assert_eq!(table.get_value(1), vec![]);
+    assert!(!table.remove(1, rid(1)));
+
+    let mut keys: Vec<KeyType> = (0..2000).map(|key| key * 7919).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    for &key in keys.iter() {
+        table.insert(key, rid(key)).unwrap();
+    }
+    for &key in keys.iter() {
+        assert_eq!(table.get_value(key), vec![rid(key)], "key {}", key);
+    }
+    assert_eq!(table.get_value(1), vec![]);
+    table.verify_integrity().unwrap();
+
+    // Duplicates fail and leave the stored rid alone
+    let err = table.insert(keys[0], Rid::new(9, 9)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(err.get_ref().unwrap().downcast_ref::<DuplicateKey>(), Some(&DuplicateKey { existing: rid(keys[0]) }));
+
+    for &key in keys.iter().step_by(2) {
+        assert!(!table.remove(key, Rid::new(9, 9)), "the rid has to match");
+        assert!(table.remove(key, rid(key)));
+    }
+    for (index, &key) in keys.iter().enumerate() {
+        let expected = if index % 2 == 0 { vec![] } else { vec![rid(key)] };
+        assert_eq!(table.get_value(key), expected, "key {}", key);
+    }
+    table.verify_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_full_buckets_split_and_grow_the_directory() {
+    let db_file = "extendible_hash_table_split_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let table = identity_table(&bpm, 3, 2);
+    table.insert(0, rid(0)).unwrap();
+    table.insert(4, rid(4)).unwrap();
+    assert_eq!(directory_of(&table, 0).0, 0);
+
+    // 0, 4 and 2 share the low bit, so the first split isn't enough
+    table.insert(2, rid(2)).unwrap();
+    let (global_depth, buckets) = directory_of(&table, 0);
+    assert_eq!(global_depth, 2);
+    assert_eq!(buckets[1], buckets[3], "the odd keys still share a bucket");
+    assert_ne!(buckets[0], buckets[2]);
+    table.verify_integrity().unwrap();
+
+    // Splitting a bucket of lower local depth leaves the directory as it is
+    table.insert(1, rid(1)).unwrap();
+    table.insert(3, rid(3)).unwrap();
+    table.insert(5, rid(5)).unwrap();
+    let (global_depth, buckets) = directory_of(&table, 0);
+    assert_eq!(global_depth, 2);
+    assert_ne!(buckets[1], buckets[3]);
+    for key in 0..6 {
+        assert_eq!(table.get_value(key), vec![rid(key)]);
+    }
+    table.verify_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_insert_fails_once_the_directory_is_at_its_max_depth() {
+    let db_file = "extendible_hash_table_full_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let table = identity_table(&bpm, 2, 2);
+    // Every key ends in the same two bits, their bucket can't split
+    table.insert(0, rid(0)).unwrap();
+    table.insert(4, rid(4)).unwrap();
+    let err = table.insert(8, rid(8)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::StorageFull);
+    assert_eq!(table.get_value(8), vec![]);
+
+    // Other buckets still take keys
+    assert_eq!(directory_of(&table, 0).0, 2);
+    table.insert(1, rid(1)).unwrap();
+    assert_eq!(table.get_value(0), vec![rid(0)]);
+    table.verify_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_invalid_arguments_fail_without_allocating_a_header() {
+    let db_file = "extendible_hash_table_invalid_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let slot_cnt = ExtendibleHTableBucketPage::<KeyType>::SLOT_CNT as u32;
+    let last_page_id = bpm.new_page().unwrap();
+    for (header_max_depth, directory_max_depth, bucket_max_size) in [(10, 9, 16), (0, 10, 16), (0, 9, 0), (0, 9, slot_cnt + 1)] {
+        let err = ExtendibleHashTable::new(String::from("hash_index"), &bpm, header_max_depth, directory_max_depth, bucket_max_size, INVALID_PAGE_ID).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    let table = ExtendibleHashTable::new(String::from("hash_index"), &bpm, 9, 9, slot_cnt, INVALID_PAGE_ID).unwrap();
+    assert_eq!(table.header_page_id, last_page_id + 1, "no page was allocated for the rejected tables");
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_empty_buckets_merge_and_the_directory_shrinks() {
+    let db_file = "extendible_hash_table_merge_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let table = identity_table(&bpm, 3, 2);
+    for key in 0..8 {
+        table.insert(key, rid(key)).unwrap();
+    }
+    let (global_depth, buckets) = directory_of(&table, 0);
+    assert_eq!(global_depth, 2);
+    assert_eq!(buckets.len(), 4);
+
+    // The odd buckets merge, the even ones still need both bits
+    for key in [1, 5, 3, 7] {
+        assert!(table.remove(key, rid(key)));
+        table.verify_integrity().unwrap();
+    }
+    let (global_depth, merged) = directory_of(&table, 0);
+    assert_eq!(global_depth, 2);
+    assert_eq!(merged[1], merged[3]);
+
+    // Once the even buckets merge, the result merges with the empty odd bucket too
+    for key in [2, 6] {
+        assert!(table.remove(key, rid(key)));
+    }
+    assert_eq!(directory_of(&table, 0).0, 0);
+    assert_eq!(table.get_value(4), vec![rid(4)]);
+    table.verify_integrity().unwrap();
+    // The freed buckets are gone from the pool
+    let (_, survivor) = directory_of(&table, 0);
+    assert_eq!(buckets.iter().filter(|&&page_id| page_id != survivor[0] && bpm.get_pin_count(page_id).is_some()).count(), 0);
+    assert_eq!((bpm.pending_delete_count(), bpm.leaked_page_count()), (0, 0));
+    for key in [0, 4] {
+        assert!(table.remove(key, rid(key)));
+    }
+
+    // The emptied table still grows
+    for key in 0..8 {
+        table.insert(key, rid(key)).unwrap();
+    }
+    assert_eq!(directory_of(&table, 0).0, 2);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_header_spreads_keys_over_directories_and_reopens() {
+    let db_file = "extendible_hash_table_reopen_test.db";
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 32);
+        let table = ExtendibleHashTable::new(String::from("hash_index"), &bpm, 2, 9, 8, INVALID_PAGE_ID).unwrap();
+        for key in 0..3000 {
+            table.insert(key, rid(key)).unwrap();
+        }
+        for index in 0..4 {
+            assert!(directory_of(&table, index).0 > 0, "directory {} is in use", index);
+        }
+        bpm.flush_all_pages().unwrap();
+        table.header_page_id
+    };
+
+    let bpm = BufferPoolManager::new(
+        32,
+        Arc::new(DiskScheduler::new(DiskManager::new(db_file).unwrap())),
+        Arc::new(LRUKReplacerImpl::new(32, 2)),
+    );
+    let table = ExtendibleHashTable::new(String::from("hash_index"), &bpm, 2, 9, 8, header_page_id).unwrap();
+    table.verify_integrity().unwrap();
+    for key in (0..3000).step_by(3) {
+        assert_eq!(table.get_value(key), vec![rid(key)]);
+        assert!(table.remove(key, rid(key)));
+    }
+    assert_eq!(table.get_value(3), vec![]);
+    assert_eq!(table.get_value(4), vec![rid(4)]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_varchar_keys() {
+    let db_file = "extendible_hash_table_varchar_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let table: ExtendibleHashTable<VarcharKey<32>> =
+        ExtendibleHashTable::with_hasher(String::from("name_index"), &bpm, OrdComparator, DefaultKeyHasher, 0, 9, 4, INVALID_PAGE_ID).unwrap();
+    let names: Vec<String> = (0..300).map(|index| format!("user-{}", index)).collect();
+    for (index, name) in names.iter().enumerate() {
+        table.insert(VarcharKey::new(name).unwrap(), Rid::new(0, index as u32)).unwrap();
+    }
+    assert_eq!(table.get_value(VarcharKey::new("user-42").unwrap()), vec![Rid::new(0, 42)]);
+    assert_eq!(table.get_value(VarcharKey::new("user-4").unwrap()), vec![Rid::new(0, 4)]);
+    assert_eq!(table.get_value(VarcharKey::new("user-").unwrap()), vec![]);
+    table.verify_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_concurrent_inserts_and_removes() {
+    let db_file = "extendible_hash_table_concurrent_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    // Small buckets keep the threads splitting and merging
+    let table = ExtendibleHashTable::new(String::from("hash_index"), &bpm, 1, 9, 16, INVALID_PAGE_ID).unwrap();
+    let threads = 8;
+
+    thread::scope(|scope| {
+        for thread_id in 0..threads {
+            let table = &table;
+            scope.spawn(move || {
+                let keys: Vec<KeyType> = (0..4000).filter(|key| key % threads == thread_id).collect();
+                for &key in keys.iter() {
+                    table.insert(key, rid(key)).unwrap();
+                }
+                // keep the keys that are a multiple of 4
+                for &key in keys.iter().filter(|&&key| key % 4 != 0) {
+                    assert!(table.remove(key, rid(key)));
+                    assert_eq!(table.get_value(key), vec![]);
+                }
+            });
+        }
+    });
+
+    for key in 0..4000 {
+        let expected = if key % 4 == 0 { vec![rid(key)] } else { vec![] };
+        assert_eq!(table.get_value(key), expected, "key {}", key);
+    }
+    table.verify_integrity().unwrap();
+    // Buckets freed while a reader had them pinned are deleted once it let go
+    assert_eq!((bpm.pending_delete_count(), bpm.leaked_page_count()), (0, 0));
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -0,0 +1,162 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{AccessType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::generic_key::{OrdComparator, VarcharKey};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::KeyType;
+use bustub_rust::include::storage::page::extendible_htable_bucket_page::{bucket_page_slot_cnt, ExtendibleHTableBucketPage, ExtendibleHTableBucketPageImpl};
+use bustub_rust::include::storage::page::extendible_htable_directory_page::{ExtendibleHTableDirectoryPage, ExtendibleHTableDirectoryPageImpl};
+use bustub_rust::include::storage::page::extendible_htable_header_page::{ExtendibleHTableHeaderPage, ExtendibleHTableHeaderPageImpl};
+use bustub_rust::include::storage::page::page_guard::{ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+#[test]
+fn test_header_routes_the_high_bits() {
+    let mut header = ExtendibleHTableHeaderPage::new(2);
+    assert_eq!(header.max_size(), 4);
+    assert_eq!(header.hash_to_directory_index(0x0000_ffff), 0);
+    assert_eq!(header.hash_to_directory_index(0x4000_0000), 1);
+    assert_eq!(header.hash_to_directory_index(0xc123_4567), 3);
+    assert_eq!(header.get_directory_page_id(3), INVALID_PAGE_ID);
+    header.set_directory_page_id(3, 9);
+    assert_eq!(header.get_directory_page_id(3), 9);
+
+    // A header of depth 0 has a single directory
+    let header = ExtendibleHTableHeaderPage::new(0);
+    assert_eq!(header.max_size(), 1);
+    assert_eq!(header.hash_to_directory_index(u32::MAX), 0);
+}
+
+#[test]
+fn test_directory_grows_and_shrinks() {
+    let mut directory = ExtendibleHTableDirectoryPage::new(3, 10);
+    assert_eq!((directory.size(), directory.max_size()), (1, 8));
+    assert!(!directory.can_shrink());
+    directory.verify_integrity().unwrap();
+
+    // Split the only bucket: keys with the low bit set move to page 11
+    directory.incr_global_depth();
+    assert_eq!(directory.get_bucket_page_id(1), 10, "the new half mirrors the old one");
+    directory.set_local_depth(0, 1);
+    directory.set_local_depth(1, 1);
+    directory.set_bucket_page_id(1, 11);
+    assert_eq!(directory.get_split_image_index(1), 0);
+    directory.verify_integrity().unwrap();
+
+    // Growing again leaves both buckets at two slots each
+    directory.incr_global_depth();
+    assert_eq!(directory.size(), 4);
+    assert_eq!(directory.get_global_depth_mask(), 0b11);
+    assert_eq!(directory.get_local_depth_mask(2), 0b1);
+    assert_eq!(directory.hash_to_bucket_index(0b1110), 2);
+    assert_eq!((0..4).map(|slot| directory.get_bucket_page_id(slot)).collect::<Vec<_>>(), vec![10, 11, 10, 11]);
+    directory.verify_integrity().unwrap();
+    assert!(directory.can_shrink());
+    directory.decr_global_depth();
+    assert_eq!(directory.size(), 2);
+    assert!(!directory.can_shrink());
+
+    // A slot pointing at the wrong bucket is caught
+    directory.set_bucket_page_id(1, 10);
+    let err = directory.verify_integrity().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+#[should_panic(expected = "max depth")]
+fn test_directory_does_not_grow_past_its_max_depth() {
+    let mut directory = ExtendibleHTableDirectoryPage::new(1, 10);
+    directory.incr_global_depth();
+    directory.incr_global_depth();
+}
+
+#[test]
+fn test_bucket_round_trips_and_fills_up() {
+    let slot_cnt = ExtendibleHTableBucketPage::<KeyType>::SLOT_CNT;
+    assert_eq!(slot_cnt, bucket_page_slot_cnt(8));
+    let mut bucket: ExtendibleHTableBucketPage = ExtendibleHTableBucketPage::new(3, 4);
+    for key in [7, -2, 40] {
+        assert!(bucket.insert(key, Rid::new(1, key as u32)));
+    }
+    assert!(bucket.is_full());
+    assert!(!bucket.insert(5, Rid::new(1, 5)));
+    assert_eq!(bucket.lookup(&-2, &OrdComparator), Some(Rid::new(1, -2i64 as u32)));
+    assert!(!bucket.remove(&7, Rid::new(2, 7), &OrdComparator), "the rid has to match");
+    assert!(bucket.remove(&7, Rid::new(1, 7), &OrdComparator));
+    assert_eq!(bucket.lookup(&7, &OrdComparator), None);
+
+    let mut page = vec![0; PAGE_SIZE];
+    bucket.encode(&mut page);
+    assert_eq!(ExtendibleHTableBucketPage::decode(&page, 4).unwrap(), bucket);
+    // Other key widths and other pages are rejected
+    assert!(ExtendibleHTableBucketPage::<VarcharKey<16>>::decode(&page, 4).is_err());
+    assert_eq!(ExtendibleHTableBucketPage::<KeyType>::decode(&[0; PAGE_SIZE], 4).unwrap_err().kind(), ErrorKind::InvalidData);
+    page[PAGE_SIZE - 1] ^= 1;
+    assert!(ExtendibleHTableBucketPage::<KeyType>::decode(&page, 4).unwrap_err().to_string().contains("checksum mismatch"));
+
+    // Variable-length keys keep their length
+    let mut bucket: ExtendibleHTableBucketPage<VarcharKey<16>> = ExtendibleHTableBucketPage::new(8, 5);
+    for word in ["", "a", "sixteen bytes!!!"] {
+        bucket.insert(VarcharKey::new(word).unwrap(), Rid::new(0, word.len() as u32));
+    }
+    bucket.encode(&mut page);
+    assert_eq!(ExtendibleHTableBucketPage::decode(&page, 5).unwrap(), bucket);
+}
+
+#[test]
+fn test_header_and_directory_round_trip_through_the_pool() {
+    let db_file = "extendible_htable_page_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let (header_page_id, directory_page_id) = (bpm.new_page().unwrap(), bpm.new_page().unwrap());
+    let mut header = ExtendibleHTableHeaderPage::new(1);
+    header.set_directory_page_id(1, directory_page_id);
+    let mut directory = ExtendibleHTableDirectoryPage::new(2, 7);
+    directory.incr_global_depth();
+    directory.set_local_depth(0, 1);
+    directory.set_local_depth(1, 1);
+    directory.set_bucket_page_id(1, 8);
+    header.encode(bpm.write_page(header_page_id, AccessType::Index).as_mut());
+    directory.encode(bpm.write_page(directory_page_id, AccessType::Index).as_mut());
+    bpm.flush_all_pages().unwrap();
+
+    let guard = bpm.read_page(header_page_id, AccessType::Index);
+    assert_eq!(ExtendibleHTableHeaderPage::decode(guard.as_ref(), header_page_id).unwrap(), header);
+    // Integers are little-endian whatever the host
+    assert_eq!(&guard.as_ref()[..8], b"HTBL\x01\x00\x01\x00");
+    assert_eq!(&guard.as_ref()[12..20], &[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(&guard.as_ref()[20..24], &directory_page_id.to_le_bytes());
+    // Each page type only decodes as itself
+    assert_eq!(ExtendibleHTableDirectoryPage::decode(guard.as_ref(), header_page_id).unwrap_err().kind(), ErrorKind::InvalidData);
+    let guard = bpm.read_page(directory_page_id, AccessType::Index);
+    assert_eq!(ExtendibleHTableDirectoryPage::decode(guard.as_ref(), directory_page_id).unwrap(), directory);
+    assert_eq!(&guard.as_ref()[..8], b"HTBL\x01\x00\x02\x00");
+    assert_eq!(&guard.as_ref()[12..22], &[2, 0, 0, 0, 1, 0, 0, 0, 1, 1]);
+    assert_eq!(&guard.as_ref()[532..540], &[7, 0, 0, 0, 8, 0, 0, 0]);
+    assert!(ExtendibleHTableHeaderPage::decode(guard.as_ref(), directory_page_id).is_err());
+    assert!(ExtendibleHTableBucketPage::<KeyType>::decode(guard.as_ref(), directory_page_id).is_err());
+    drop(guard);
+
+    // A flipped byte fails the checksum
+    let mut page = vec![0; PAGE_SIZE];
+    directory.encode(&mut page);
+    page[16] = 3;
+    let err = ExtendibleHTableDirectoryPage::decode(&page, directory_page_id).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    header.encode(&mut page);
+    page[100] ^= 1;
+    assert_eq!(ExtendibleHTableHeaderPage::decode(&page, header_page_id).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(ExtendibleHTableHeaderPage::decode(&page[..8], header_page_id).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -0,0 +1,166 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl};
+use bustub_rust::include::storage::index::generic_key::{
+    GenericComparator, GenericKey, KeyColumn, KeyComparator, KeySchema, KeyValue,
+};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{internal_page_slot_cnt, BplusTreeInternalPage};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{leaf_page_slot_cnt, BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use std::cmp::Ordering;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn varchar(s: &str) -> KeyValue {
+    KeyValue::Varchar(s.to_string())
+}
+
+#[test]
+fn test_slot_counts_follow_the_key_width() {
+    assert_eq!(leaf_page_slot_cnt(8), 254);
+    assert_eq!(internal_page_slot_cnt(8), 338);
+    assert_eq!(BplusTreeLeafPage::<GenericKey<4>>::SLOT_CNT, 338);
+    assert_eq!(BplusTreeLeafPage::<GenericKey<64>>::SLOT_CNT, 56);
+    assert_eq!(BplusTreeInternalPage::<GenericKey<16>>::SLOT_CNT, 203);
+    assert_eq!(BplusTreeInternalPage::<GenericKey<64>>::SLOT_CNT, 59);
+}
+
+#[test]
+fn test_schema_builds_and_reads_keys() {
+    let schema = KeySchema::new(vec![KeyColumn::Varchar(10), KeyColumn::Integer, KeyColumn::BigInt]);
+    assert_eq!(schema.width(), 22);
+    let values = vec![varchar("carol"), KeyValue::Integer(-3), KeyValue::BigInt(1 << 40)];
+    let key: GenericKey<32> = schema.key(&values).unwrap();
+    assert_eq!(&key.as_bytes()[..5], b"carol");
+    assert_eq!(schema.values(&key), values);
+
+    let err = |result: std::io::Result<GenericKey<32>>| result.unwrap_err().kind();
+    assert_eq!(err(schema.key(&values[..2])), ErrorKind::InvalidInput, "one value per column");
+    assert_eq!(err(schema.key(&[KeyValue::Integer(1), KeyValue::Integer(2), KeyValue::BigInt(3)])), ErrorKind::InvalidInput);
+    assert_eq!(err(schema.key(&[varchar("much too long"), KeyValue::Integer(1), KeyValue::BigInt(2)])), ErrorKind::InvalidInput);
+    assert_eq!(err(schema.key(&[varchar("a\0b"), KeyValue::Integer(1), KeyValue::BigInt(2)])), ErrorKind::InvalidInput);
+    assert!(schema.key::<16>(&values).is_err(), "22 bytes don't fit a 16 byte key");
+    assert!(GenericKey::<4>::from_bytes(&[1, 2, 3, 4, 5]).is_err());
+}
+
+#[test]
+fn test_generic_comparator_orders_column_by_column() {
+    let schema = KeySchema::new(vec![KeyColumn::Varchar(6), KeyColumn::Integer]);
+    let comparator = GenericComparator::<16>::new(schema.clone());
+    let key = |name: &str, n: i32| schema.key::<16>(&[varchar(name), KeyValue::Integer(n)]).unwrap();
+
+    assert_eq!(comparator.compare(&key("ab", 1), &key("abc", 0)), Ordering::Less, "a prefix sorts first");
+    assert_eq!(comparator.compare(&key("b", 0), &key("abc", 9)), Ordering::Greater);
+    // Ties on the name fall through to the integer, which compares numerically
+    assert_eq!(comparator.compare(&key("ab", -1), &key("ab", 1)), Ordering::Less);
+    assert_eq!(comparator.compare(&key("ab", 256), &key("ab", 1)), Ordering::Greater);
+    assert_eq!(comparator.compare(&key("ab", 7), &key("ab", 7)), Ordering::Equal);
+}
+
+#[test]
+#[should_panic(expected = "doesn't fit a 4 byte key")]
+fn test_comparator_rejects_a_wider_schema() {
+    GenericComparator::<4>::new(KeySchema::new(vec![KeyColumn::BigInt]));
+}
+
+#[test]
+fn test_tree_over_string_keys() {
+    let db_file = "generic_key_string_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let schema = KeySchema::new(vec![KeyColumn::Varchar(16)]);
+    let tree = BplusTree::with_comparator(
+        String::from("name_index"), &bpm, GenericComparator::<16>::new(schema.clone()), 4, 4, INVALID_PAGE_ID,
+    );
+    let key = |name: &str| schema.key::<16>(&[varchar(name)]).unwrap();
+
+    let mut names: Vec<String> = (0..600).map(|n| format!("user{:04}", n * 7 % 600)).collect();
+    names.shuffle(&mut rand::thread_rng());
+    for (slot, name) in names.iter().enumerate() {
+        assert!(tree.insert(key(name), ValueType::Rid(Rid::new(1, slot as u32))).is_ok());
+    }
+    names.sort();
+    let scanned: Vec<_> = tree.begin().map(|(k, _)| schema.values(&k)).collect();
+    let expected: Vec<_> = names.iter().map(|name| vec![varchar(name)]).collect();
+    assert_eq!(scanned, expected);
+
+    assert_eq!(tree.get_value(key("user0123")).len(), 1);
+    assert!(tree.get_value(key("user")).is_empty());
+    // a unique index removes the key whatever rid it is given
+    tree.remove(key("user0123"), Rid::new(2, 0));
+    assert!(tree.get_value(key("user0123")).is_empty());
+    let range: Vec<_> = tree.range(key("user0120")..key("user0125")).map(|(k, _)| schema.values(&k)).collect();
+    assert_eq!(range, ["user0120", "user0121", "user0122", "user0124"].map(|name| vec![varchar(name)]));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_tree_over_composite_keys_with_full_pages() {
+    let db_file = "generic_key_composite_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    // Widest keys, and pages filled up to their last slot
+    let schema = KeySchema::new(vec![KeyColumn::Integer, KeyColumn::Varchar(52), KeyColumn::BigInt]);
+    let leaf_max_size = BplusTreeLeafPage::<GenericKey<64>>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<GenericKey<64>>::SLOT_CNT as i32 - 1;
+    let tree = BplusTree::with_comparator(
+        String::from("composite_index"), &bpm, GenericComparator::<64>::new(schema.clone()),
+        leaf_max_size, internal_max_size, INVALID_PAGE_ID,
+    );
+    let key = |dept: i32, name: &str, id: i64| {
+        schema.key::<64>(&[KeyValue::Integer(dept), varchar(name), KeyValue::BigInt(id)]).unwrap()
+    };
+
+    let mut entries: Vec<(i32, i64)> = (0..3000).map(|id| ((id % 5) as i32 - 2, id)).collect();
+    entries.shuffle(&mut rand::thread_rng());
+    for &(dept, id) in entries.iter() {
+        assert!(tree.insert(key(dept, "staff", id), ValueType::Rid(Rid::new(dept, id as u32))).is_ok());
+    }
+    for &(dept, id) in entries.iter() {
+        assert_eq!(tree.get_value(key(dept, "staff", id)), vec![Rid::new(dept, id as u32)]);
+    }
+    // Everyone in department -1, ordered by id
+    let ids: Vec<_> = tree
+        .range(key(-1, "", i64::MIN)..key(0, "", i64::MIN))
+        .map(|(_, rid)| rid.get_slot_num() as i64)
+        .collect();
+    assert_eq!(ids, (0..3000).filter(|id| id % 5 == 1).collect::<Vec<_>>());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_pages_remember_their_key_width() {
+    let schema = KeySchema::new(vec![KeyColumn::Varchar(16)]);
+    let mut leaf = BplusTreeLeafPage::<GenericKey<16>>::new(10, 3);
+    leaf.insert(0, schema.key(&[varchar("x")]).unwrap(), ValueType::Rid(Rid::new(1, 1)));
+    let mut page = vec![0u8; PAGE_SIZE];
+    leaf.encode(&mut page);
+    assert_eq!(BplusTreeLeafPage::<GenericKey<16>>::decode(&page).unwrap(), leaf);
+
+    let err = <BplusTreeLeafPage>::decode(&page).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("16 byte keys"), "{}", err);
+}
+
+#[test]
+#[should_panic(expected = "Leaf max size 100")]
+fn test_max_sizes_must_fit_the_key_width() {
+    let bpm = setup_bpm("generic_key_max_size_test.db", 2);
+    // The constructor panics, so clean up first
+    let _ = std::fs::remove_file("generic_key_max_size_test.db");
+    BplusTree::with_comparator(
+        String::from("wide_index"), &bpm, GenericComparator::<64>::new(KeySchema::new(vec![KeyColumn::BigInt])),
+        100, 10, INVALID_PAGE_ID,
+    );
+}
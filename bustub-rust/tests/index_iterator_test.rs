@@ -0,0 +1,133 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::KeyType;
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use std::ops::Bound;
+use std::sync::Arc;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn rid_for(key: KeyType) -> Rid {
+    Rid::new(key as i32, 1)
+}
+
+// Even keys 0, 2, .., 2 * (count - 1) inserted in random order
+fn insert_even_keys(tree: &BplusTree, count: KeyType) {
+    let mut keys: Vec<KeyType> = (0..count).map(|key| key * 2).collect();
+    keys.shuffle(&mut rand::thread_rng());
+    for key in keys {
+        assert!(tree.insert(key, ValueType::Rid(rid_for(key))).is_ok());
+    }
+}
+
+fn keys(iterator: impl Iterator<Item = (KeyType, Rid)>) -> Vec<KeyType> {
+    iterator.map(|(key, _)| key).collect()
+}
+
+fn pinned_pages(bpm: &BufferPoolManager) -> usize {
+    let page_ids: Vec<_> = bpm.page_table.lock().unwrap().keys().copied().collect();
+    page_ids.into_iter().filter(|&page_id| bpm.get_pin_count(page_id).unwrap_or(0) > 0).count()
+}
+
+#[test]
+fn test_empty_tree() {
+    let db_file = "index_iterator_empty_test.db";
+    let bpm = setup_bpm(db_file, 8);
+    let tree = BplusTree::new(String::from("empty_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    assert!(tree.begin().is_end());
+    assert!(tree.begin() == tree.end());
+    assert!(tree.is_empty());
+    assert_eq!(tree.range(0..10).next(), None);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_full_scan_follows_the_leaf_chain() {
+    let db_file = "index_iterator_scan_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("scan_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    insert_even_keys(&tree, 500);
+
+    let entries: Vec<_> = tree.begin().collect();
+    let expected: Vec<_> = (0..500).map(|key| (key * 2, rid_for(key * 2))).collect();
+    assert_eq!(entries, expected);
+
+    // Leaves are only read latched one at a time, and released at the end
+    let mut iterator = tree.begin();
+    for _ in 0..100 {
+        iterator.next();
+        assert_eq!(pinned_pages(&bpm), 1);
+    }
+    assert!(iterator != tree.end());
+    assert_eq!(iterator.by_ref().count(), 400);
+    assert!(iterator.is_end());
+    assert!(iterator == tree.end());
+    assert_eq!(pinned_pages(&bpm), 0);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_begin_at_and_ranges() {
+    let db_file = "index_iterator_range_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("range_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    insert_even_keys(&tree, 100);
+
+    assert_eq!(keys(tree.begin_at(190)), vec![190, 192, 194, 196, 198]);
+    // A missing key starts at the next larger one
+    assert_eq!(keys(tree.begin_at(191)), vec![192, 194, 196, 198]);
+    assert_eq!(keys(tree.begin_at(-5).take(2)), vec![0, 2]);
+    assert!(tree.begin_at(199).is_end());
+
+    assert_eq!(keys(tree.range(10..16)), vec![10, 12, 14]);
+    assert_eq!(keys(tree.range(10..=16)), vec![10, 12, 14, 16]);
+    assert_eq!(keys(tree.range(9..15)), vec![10, 12, 14]);
+    assert_eq!(keys(tree.range(..6)), vec![0, 2, 4]);
+    assert_eq!(keys(tree.range(194..)), vec![194, 196, 198]);
+    assert_eq!(keys(tree.range((Bound::Excluded(10), Bound::Included(16)))), vec![12, 14, 16]);
+    assert_eq!(keys(tree.range((Bound::Excluded(11), Bound::Excluded(16)))), vec![12, 14]);
+    assert_eq!(tree.range(..).count(), 100);
+    assert!(tree.range(20..20).is_end());
+    assert!(tree.range(21..22).is_end());
+    assert_eq!(pinned_pages(&bpm), 0);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_scan_after_removes_and_restart() {
+    let db_file = "index_iterator_restart_test.db";
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 8);
+        let tree = BplusTree::new(String::from("restart_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+        insert_even_keys(&tree, 300);
+        // Merges have to keep the leaf chain intact
+        for key in (0..600).filter(|key| key % 6 == 0) {
+            tree.remove(key, rid_for(key));
+        }
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    // The leaf chain is read back from disk
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    let bpm = BufferPoolManager::new(8, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(8, 2)));
+    let tree = BplusTree::new(String::from("restart_index"), &bpm, 4, 4, header_page_id);
+    let expected: Vec<KeyType> = (0..600).filter(|key| key % 2 == 0 && key % 6 != 0).collect();
+    assert_eq!(keys(tree.begin()), expected);
+
+    std::fs::remove_file(db_file).unwrap();
+}
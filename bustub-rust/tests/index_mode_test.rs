@@ -0,0 +1,212 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, DuplicateKey, IndexMode};
+use bustub_rust::include::storage::index::generic_key::OrdComparator;
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, KeyType};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::thread;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn non_unique_tree(bpm: &BufferPoolManager, header_page_id: i32) -> BplusTree {
+    BplusTree::with_mode(String::from("secondary_index"), bpm, OrdComparator, IndexMode::NonUnique, 4, 4, header_page_id)
+}
+
+fn value(rid: Rid) -> ValueType {
+    ValueType::Rid(rid)
+}
+
+#[test]
+fn test_unique_index_rejects_duplicates() {
+    let db_file = "index_mode_unique_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("primary_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    assert_eq!(tree.mode, IndexMode::Unique);
+    for key in 0..100 {
+        tree.insert(key, value(Rid::new(1, key as u32))).unwrap();
+    }
+
+    let err = tree.insert(42, value(Rid::new(9, 9))).unwrap_err();
+    assert_eq!(err, DuplicateKey { existing: Rid::new(1, 42) });
+    assert!(err.to_string().contains("(1, 42)"), "{}", err);
+    assert_eq!(tree.get_value(42), vec![Rid::new(1, 42)], "the first insert wins");
+    assert_eq!(tree.begin().count(), 100);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_unique_index_removes_by_key_whatever_the_rid() {
+    let db_file = "index_mode_unique_remove_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    let tree = BplusTree::new(String::from("primary_index"), &bpm, 4, 4, INVALID_PAGE_ID);
+    for key in 0..100 {
+        tree.insert(key, value(Rid::new(1, key as u32))).unwrap();
+    }
+    // A stale rid still removes the key, enough of them to merge pages
+    for key in (0..100).step_by(2) {
+        tree.remove(key, Rid::new(7, 7));
+    }
+    for key in 0..100 {
+        let expected = if key % 2 == 0 { vec![] } else { vec![Rid::new(1, key as u32)] };
+        assert_eq!(tree.get_value(key), expected, "key {}", key);
+    }
+    tree.check_integrity().unwrap();
+    tree.remove(200, Rid::new(1, 200));
+    assert_eq!(tree.begin().count(), 50);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_non_unique_index_keeps_every_rid_in_order() {
+    let db_file = "index_mode_non_unique_test.db";
+    let bpm = setup_bpm(db_file, 32);
+    let tree = non_unique_tree(&bpm, INVALID_PAGE_ID);
+    // 40 entries per key, far more than a leaf holds
+    let mut entries: Vec<(KeyType, Rid)> = (0..5)
+        .flat_map(|key| (0..40).map(move |slot| (key, Rid::new(slot % 3, slot as u32))))
+        .collect();
+    entries.shuffle(&mut rand::thread_rng());
+    for &(key, rid) in entries.iter() {
+        tree.insert(key, value(rid)).unwrap();
+    }
+
+    let mut expected: Vec<Rid> = (0..40).map(|slot| Rid::new(slot % 3, slot as u32)).collect();
+    expected.sort();
+    for key in 0..5 {
+        assert_eq!(tree.get_value(key), expected, "rids of key {}", key);
+    }
+    assert!(tree.get_value(5).is_empty());
+    entries.sort();
+    assert_eq!(tree.begin().collect::<Vec<_>>(), entries, "scans order equal keys by rid");
+
+    // The exact entry is a duplicate, the same key with another rid is not
+    let err = tree.insert(3, value(Rid::new(1, 7))).unwrap_err();
+    assert_eq!(err.existing, Rid::new(1, 7));
+    tree.insert(3, value(Rid::new(5, 0))).unwrap();
+    assert_eq!(tree.get_value(3).len(), 41);
+
+    // remove takes out just the entry with that rid
+    tree.remove(3, Rid::new(1, 7));
+    tree.remove(3, Rid::new(8, 8));
+    let rids = tree.get_value(3);
+    assert_eq!(rids.len(), 40);
+    assert!(!rids.contains(&Rid::new(1, 7)));
+    assert_eq!(tree.get_value(2), expected);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_non_unique_index_matches_a_model() {
+    let db_file = "index_mode_model_test.db";
+    let bpm = setup_bpm(db_file, 32);
+    let tree = non_unique_tree(&bpm, INVALID_PAGE_ID);
+    let mut model = BTreeSet::new();
+    let mut rng = rand::thread_rng();
+    for _ in 0..5000 {
+        let entry: (KeyType, Rid) = (rng.gen_range(0..20), Rid::new(rng.gen_range(0..4), rng.gen_range(0..30)));
+        if rng.gen_bool(0.6) {
+            assert_eq!(tree.insert(entry.0, value(entry.1)).is_ok(), model.insert(entry));
+        } else {
+            tree.remove(entry.0, entry.1);
+            model.remove(&entry);
+        }
+    }
+    assert_eq!(tree.begin().collect::<Vec<_>>(), model.iter().copied().collect::<Vec<_>>());
+    for key in 0..20 {
+        let rids: Vec<Rid> = model.range((key, Rid::new(i32::MIN, 0))..=(key, Rid::new(i32::MAX, u32::MAX))).map(|&(_, rid)| rid).collect();
+        assert_eq!(tree.get_value(key), rids, "rids of key {}", key);
+    }
+    for (key, rid) in model.iter().copied() {
+        tree.remove(key, rid);
+    }
+    assert!(tree.is_empty());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_separator_rids_survive_a_restart() {
+    let db_file = "index_mode_restart_test.db";
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 16);
+        let tree = non_unique_tree(&bpm, INVALID_PAGE_ID);
+        for slot in 0..300 {
+            tree.insert(slot as KeyType % 2, value(Rid::new(0, slot))).unwrap();
+        }
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    let bpm = BufferPoolManager::new(
+        16,
+        Arc::new(DiskScheduler::new(DiskManager::new(db_file).unwrap())),
+        Arc::new(LRUKReplacerImpl::new(16, 2)),
+    );
+    let tree = non_unique_tree(&bpm, header_page_id);
+    assert_eq!(tree.get_value(1), (0..300).filter(|slot| slot % 2 == 1).map(|slot| Rid::new(0, slot)).collect::<Vec<_>>());
+    tree.remove(0, Rid::new(0, 150));
+    assert_eq!(tree.get_value(0).len(), 149);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_internal_page_encodes_separator_rids() {
+    let mut internal = BplusTreeInternalPage::<KeyType>::new(100, 3);
+    internal.separator_rids = true;
+    internal.page_id_array[..3].copy_from_slice(&[10, 11, 12]);
+    internal.key_array[..2].copy_from_slice(&[5, 5]);
+    internal.rid_array[..2].copy_from_slice(&[Rid::new(1, 2), Rid::new(3, 4)]);
+    internal.base_page.size_ = 2;
+    let mut page = vec![0u8; PAGE_SIZE];
+    internal.encode(&mut page);
+    let decoded = BplusTreeInternalPage::<KeyType>::decode(&page).unwrap();
+    assert!(decoded.separator_rids);
+    assert_eq!(decoded.slot_cnt(), BplusTreeInternalPage::<KeyType>::NON_UNIQUE_SLOT_CNT);
+    assert_eq!(decoded.rid_array[..2], internal.rid_array[..2]);
+
+    // Equal keys route by rid, no rid routes before all of them
+    assert_eq!(decoded.lookup_entry_index(5, None, &OrdComparator), 0);
+    assert_eq!(decoded.lookup_entry_index(5, Some(Rid::new(2, 0)), &OrdComparator), 1);
+    assert_eq!(decoded.lookup_entry_index(5, Some(Rid::new(3, 4)), &OrdComparator), 2);
+    assert_eq!(decoded.lookup_entry_index(6, None, &OrdComparator), 2);
+}
+
+#[test]
+fn test_concurrent_inserts_of_equal_keys() {
+    let db_file = "index_mode_concurrent_test.db";
+    let bpm = setup_bpm(db_file, 128);
+    let tree = non_unique_tree(&bpm, INVALID_PAGE_ID);
+    thread::scope(|scope| {
+        for thread_id in 0..4 {
+            let tree = &tree;
+            scope.spawn(move || {
+                for slot in 0..200 {
+                    tree.insert(slot as KeyType % 3, value(Rid::new(thread_id, slot))).unwrap();
+                }
+            });
+        }
+    });
+    let all: usize = (0..3).map(|key| tree.get_value(key).len()).sum();
+    assert_eq!(all, 800);
+    let entries: Vec<_> = tree.begin().collect();
+    assert!(entries.windows(2).all(|pair| pair[0] < pair[1]));
+
+    std::fs::remove_file(db_file).unwrap();
+}
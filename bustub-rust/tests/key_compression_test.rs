@@ -0,0 +1,257 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, IndexMode, KeyCompression, TreeStats};
+use bustub_rust::include::storage::index::generic_key::{IndexKey, OrdComparator, VarcharKey};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::BplusTreeInternalPage;
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePageTrait, BPLUS_TREE_PAGE_HEADER_SIZE};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::sync::Arc;
+
+type UrlKey = VarcharKey<200>;
+type UrlTree = BplusTree<UrlKey, OrdComparator>;
+
+const URL_PREFIX: &str = "https://example.com/users/";
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn key(s: &str) -> UrlKey {
+    VarcharKey::new(s).unwrap()
+}
+
+// A tree with the largest max sizes slotted pages allow, so bytes decide every split
+fn url_tree(bpm: &BufferPoolManager, mode: IndexMode, compression: KeyCompression, header_page_id: i32) -> UrlTree {
+    let leaf_max_size = BplusTreeLeafPage::<UrlKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<UrlKey>::NON_UNIQUE_SLOT_CNT as i32 - 1;
+    BplusTree::with_mode(String::from("url_index"), bpm, OrdComparator, mode, leaf_max_size, internal_max_size, header_page_id)
+        .with_compression(compression)
+}
+
+// Distinct urls sharing URL_PREFIX, each with a long random tail
+fn random_urls(count: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut urls: Vec<String> = (0..count)
+        .map(|index| {
+            let tail: String = (0..100).map(|_| rng.gen_range(b'a'..=b'z') as char).collect();
+            format!("{}{}{:06}", URL_PREFIX, tail, index)
+        })
+        .collect();
+    urls.shuffle(&mut rng);
+    urls
+}
+
+#[test]
+fn test_varchar_separators_and_prefixes() {
+    assert_eq!(key("apple").common_prefix_len(&key("apricot")), 2);
+    assert_eq!(key("app").common_prefix_len(&key("apple")), 3);
+    assert_eq!(key("").common_prefix_len(&key("apple")), 0);
+
+    // The shortest key after lower and up to upper
+    assert_eq!(UrlKey::shortest_separator(&key("apple"), &key("apricot")), key("apr"));
+    assert_eq!(UrlKey::shortest_separator(&key("app"), &key("apple")), key("appl"));
+    assert_eq!(UrlKey::shortest_separator(&key("azure"), &key("b")), key("b"));
+    assert_eq!(UrlKey::shortest_separator(&key("same"), &key("same")), key("same"));
+    // Fixed width keys are never cut
+    assert_eq!(i64::shortest_separator(&3, &700), 700);
+}
+
+#[test]
+fn test_prefix_compressed_leaf_round_trip() {
+    let mut leaf: BplusTreeLeafPage<UrlKey> = BplusTreeLeafPage::new(100, 4);
+    leaf.prefix_compression = true;
+    let mut page = vec![0u8; PAGE_SIZE];
+    leaf.encode(&mut page);
+    assert_eq!(BplusTreeLeafPage::decode(&page).unwrap(), leaf);
+
+    let suffixes = ["alice", "bob", "carol/settings"];
+    for (index, suffix) in suffixes.iter().enumerate() {
+        leaf.insert(index as i32, key(&format!("{}{}", URL_PREFIX, suffix)), ValueType::Rid(Rid::new(3, index as u32)));
+    }
+    leaf.encode(&mut page);
+    let decoded = BplusTreeLeafPage::<UrlKey>::decode(&page).unwrap();
+    assert_eq!(decoded, leaf);
+    assert!(decoded.prefix_compression);
+    assert_eq!(decoded.common_prefix_len(), URL_PREFIX.len());
+
+    // Flag bit 2, then the prefix once right after the header
+    assert_ne!(u16::from_le_bytes([page[30], page[31]]) & 4, 0);
+    let header = BPLUS_TREE_PAGE_HEADER_SIZE;
+    assert_eq!(u16::from_le_bytes([page[header], page[header + 1]]) as usize, URL_PREFIX.len());
+    assert_eq!(&page[header + 2..header + 2 + URL_PREFIX.len()], URL_PREFIX.as_bytes());
+
+    // Every key but one leaves the prefix out, the prefix length takes two bytes
+    leaf.prefix_compression = false;
+    let uncompressed = leaf.used_bytes();
+    leaf.prefix_compression = true;
+    assert_eq!(leaf.used_bytes(), uncompressed + 2 - 2 * URL_PREFIX.len());
+    // A key sharing none of the prefix makes the others store it again
+    assert_eq!(leaf.used_bytes_with(&key("ftp://")), uncompressed + 2 + BplusTreeLeafPage::slotted_entry_size(&key("ftp://")));
+}
+
+#[test]
+fn test_compression_raises_fan_out() {
+    let db_file = "key_compression_fan_out_test.db";
+    let bpm = setup_bpm(db_file, 128);
+    let urls = random_urls(5000);
+    let plain = url_tree(&bpm, IndexMode::Unique, KeyCompression::Off, INVALID_PAGE_ID);
+    let compressed = url_tree(&bpm, IndexMode::Unique, KeyCompression::PrefixSuffix, INVALID_PAGE_ID);
+    for (slot, url) in urls.iter().enumerate() {
+        plain.insert(key(url), ValueType::Rid(Rid::new(1, slot as u32))).unwrap();
+        compressed.insert(key(url), ValueType::Rid(Rid::new(1, slot as u32))).unwrap();
+    }
+    plain.check_integrity().unwrap();
+    compressed.check_integrity().unwrap();
+    assert!(compressed.begin().eq(plain.begin()));
+    for (slot, url) in urls.iter().enumerate().step_by(11) {
+        assert_eq!(compressed.get_value(key(url)), vec![Rid::new(1, slot as u32)]);
+    }
+
+    let (plain_stats, stats) = (plain.stats(), compressed.stats());
+    assert_eq!((plain_stats.entries, stats.entries), (5000, 5000));
+    assert_eq!(plain_stats.prefix_bytes_saved, 0);
+    assert!(stats.prefix_bytes_saved > 0);
+    assert!(stats.leaf_pages < plain_stats.leaf_pages, "{:?} {:?}", stats, plain_stats);
+    // Truncated separators only keep the shared prefix and a byte or two more
+    let separators = stats.children - stats.internal_pages;
+    assert!(stats.separator_key_bytes < separators * (URL_PREFIX.len() + 6), "{:?}", stats);
+    assert!(stats.fan_out() > 2.0 * plain_stats.fan_out(), "{:?} {:?}", stats, plain_stats);
+    assert!(stats.height <= plain_stats.height);
+
+    for (slot, url) in urls.iter().enumerate() {
+        compressed.remove(key(url), Rid::new(1, slot as u32));
+        if slot % 500 == 0 {
+            assert_eq!(compressed.check_integrity(), Ok(()), "after {} removes", slot);
+        }
+    }
+    assert!(compressed.is_empty());
+    assert_eq!(compressed.stats(), TreeStats::default());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_keys_breaking_a_shared_prefix() {
+    let db_file = "key_compression_prefix_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let tree = url_tree(&bpm, IndexMode::Unique, KeyCompression::PrefixSuffix, INVALID_PAGE_ID);
+    // Leaves of long keys that only differ at the end, stored in a few bytes each
+    let shared = "x".repeat(180);
+    let mut keys: Vec<String> = (0..2000).map(|index| format!("{}{:05}", shared, index)).collect();
+    for (slot, name) in keys.iter().enumerate() {
+        tree.insert(key(name), ValueType::Rid(Rid::new(2, slot as u32))).unwrap();
+    }
+    assert!(tree.stats().entries_per_leaf() > 100.0, "{:?}", tree.stats());
+    // Keys before and after all of them share none of the prefix
+    for index in 0..50 {
+        for name in [format!("a{}", index), format!("z{}", index), format!("{}{:05}m", shared, index * 40)] {
+            tree.insert(key(&name), ValueType::Rid(Rid::new(3, index))).unwrap();
+            keys.push(name);
+        }
+    }
+    tree.check_integrity().unwrap();
+    keys.sort();
+    assert!(tree.begin().map(|(key, _)| key).eq(keys.iter().map(|name| key(name))));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_non_unique_compressed_index() {
+    let db_file = "key_compression_non_unique_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let tree = url_tree(&bpm, IndexMode::NonUnique, KeyCompression::PrefixSuffix, INVALID_PAGE_ID);
+    let pages: Vec<String> = (0..5).map(|index| format!("{}{}/{}", URL_PREFIX, "profile".repeat(20), index)).collect();
+    for slot in 0..3000 {
+        tree.insert(key(&pages[slot as usize % 5]), ValueType::Rid(Rid::new(4, slot))).unwrap();
+    }
+    tree.check_integrity().unwrap();
+    let expected: Vec<Rid> = (0..3000).filter(|slot| slot % 5 == 2).map(|slot| Rid::new(4, slot)).collect();
+    assert_eq!(tree.get_value(key(&pages[2])), expected);
+    for slot in (0..3000).filter(|slot| slot % 5 != 2) {
+        tree.remove(key(&pages[slot as usize % 5]), Rid::new(4, slot));
+    }
+    tree.check_integrity().unwrap();
+    assert_eq!(tree.get_value(key(&pages[2])), expected);
+    assert_eq!(tree.stats().entries, 600);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_compressed_and_reopen() {
+    let db_file = "key_compression_bulk_test.db";
+    let urls = random_urls(4000);
+    let entries = || urls.iter().enumerate().map(|(slot, url)| (key(url), Rid::new(5, slot as u32)));
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 64);
+        let plain = url_tree(&bpm, IndexMode::Unique, KeyCompression::Off, INVALID_PAGE_ID);
+        plain.bulk_load(entries(), 1.0).unwrap();
+        let tree = url_tree(&bpm, IndexMode::Unique, KeyCompression::PrefixSuffix, INVALID_PAGE_ID);
+        tree.bulk_load(entries(), 1.0).unwrap();
+        tree.check_integrity().unwrap();
+        let (plain_stats, stats) = (plain.stats(), tree.stats());
+        assert!(stats.leaf_pages < plain_stats.leaf_pages, "{:?} {:?}", stats, plain_stats);
+        assert!(stats.fan_out() > plain_stats.fan_out());
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    let bpm = BufferPoolManager::new(
+        32,
+        Arc::new(DiskScheduler::new(DiskManager::new(db_file).unwrap())),
+        Arc::new(LRUKReplacerImpl::new(32, 2)),
+    );
+    let tree = url_tree(&bpm, IndexMode::Unique, KeyCompression::PrefixSuffix, header_page_id);
+    for (slot, url) in urls.iter().enumerate().step_by(13) {
+        assert_eq!(tree.get_value(key(url)), vec![Rid::new(5, slot as u32)]);
+    }
+    // Full leaves split again, keeping their prefix compression
+    for (slot, url) in random_urls(500).iter().enumerate() {
+        tree.insert(key(&format!("{}-new", url)), ValueType::Rid(Rid::new(6, slot as u32))).unwrap();
+    }
+    tree.check_integrity().unwrap();
+    assert_eq!(tree.stats().entries, 4500);
+    assert!(tree.stats().prefix_bytes_saved > 0);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_stats_of_a_fixed_width_tree() {
+    let db_file = "key_compression_stats_test.db";
+    let bpm = setup_bpm(db_file, 16);
+    // Fixed width keys ignore the compression
+    let tree = BplusTree::new(String::from("stats_index"), &bpm, 4, 4, INVALID_PAGE_ID).with_compression(KeyCompression::PrefixSuffix);
+    assert_eq!(tree.stats(), TreeStats::default());
+    assert_eq!(tree.stats().fan_out(), 0.0);
+
+    for key in 1..=10 {
+        tree.insert(key, ValueType::Rid(Rid::new(key as i32, 0))).unwrap();
+    }
+    let stats = tree.stats();
+    assert_eq!(stats, TreeStats {
+        height: 2,
+        leaf_pages: 4,
+        internal_pages: 1,
+        entries: 10,
+        children: 4,
+        leaf_key_bytes: 80,
+        separator_key_bytes: 24,
+        prefix_bytes_saved: 0,
+    });
+    assert_eq!(stats.fan_out(), 4.0);
+    assert_eq!(stats.entries_per_leaf(), 2.5);
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -1,8 +1,11 @@
 use bustub_rust::include::common::config::AccessType;
 use bustub_rust::{
-    buffer::lru_k_replacer::{LRUKNode, LRUKReplacerImpl},
-    include::buffer::lru_k_replacer::LRUKReplacer,
+    buffer::lru_k_replacer::LRUKReplacerImpl,
+    include::buffer::lru_k_replacer::{LRUKReplacer, ReplacerPolicy},
 };
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
 //use crate::buffer
 //mod buffer;
@@ -15,35 +18,196 @@ fn test_basic() {
 #[test]
 // Checking if the evictable size is working as expected.
 fn test_lru_k_impl_evictable_size() {
-    let mut lru_k_replacer_impl: LRUKReplacerImpl = LRUKReplacerImpl::new(100, 3);
+    let lru_k_replacer_impl: LRUKReplacerImpl = LRUKReplacerImpl::new(100, 3);
     // check if a node_store a hashmap which maps the frameid with LRUKnode is created
     // Check if a frameId is being inserted into the replacer?
-    lru_k_replacer_impl.record_access(123, AccessType::Lookup);
-    lru_k_replacer_impl.record_access(234, AccessType::Lookup);
-    lru_k_replacer_impl.record_access(345, AccessType::Lookup);
-    lru_k_replacer_impl.set_evictable(123, true);
-    lru_k_replacer_impl.set_evictable(234, true);
-    lru_k_replacer_impl.set_evictable(345, true);
-    lru_k_replacer_impl.set_evictable(123, false);
+    lru_k_replacer_impl.record_access(12, AccessType::Lookup);
+    lru_k_replacer_impl.record_access(23, AccessType::Lookup);
+    lru_k_replacer_impl.record_access(34, AccessType::Lookup);
+    lru_k_replacer_impl.set_evictable(12, true);
+    lru_k_replacer_impl.set_evictable(23, true);
+    lru_k_replacer_impl.set_evictable(34, true);
+    lru_k_replacer_impl.set_evictable(12, false);
     assert_eq!(lru_k_replacer_impl.size(), 2, "Curr evictable size matches")
 }
 
 #[test]
 // Checking if the basic evict algorithm is working as expected.
-// Frame with earliest timestamp accessed is evicted.
+// Frames with fewer than k accesses are evicted before frames with a full history.
 fn test_lru_k_impl_evictable_evict() {
-    let mut lru_k_replacer_impl: LRUKReplacerImpl = LRUKReplacerImpl::new(100, 3);
+    let lru_k_replacer_impl: LRUKReplacerImpl = LRUKReplacerImpl::new(100, 3);
     // check if a node_store a hashmap which maps the frameid with LRUKnode is created
     // Check if a frameId is being inserted into the replacer?
-    lru_k_replacer_impl.record_access(123, AccessType::Lookup);
-    lru_k_replacer_impl.record_access(123, AccessType::Lookup);
-    lru_k_replacer_impl.record_access(123, AccessType::Lookup);
-    lru_k_replacer_impl.record_access(123, AccessType::Lookup);
-    lru_k_replacer_impl.set_evictable(123, true);
-    lru_k_replacer_impl.record_access(234, AccessType::Lookup);
-    lru_k_replacer_impl.set_evictable(234, true);
-    assert_eq!(lru_k_replacer_impl.evict(), Some(123), "matches");
-    lru_k_replacer_impl.remove(123);
-    assert_eq!(lru_k_replacer_impl.evict(), Some(234), "matches");
-    assert_eq!(lru_k_replacer_impl.size(), 1, "Matches")
+    lru_k_replacer_impl.record_access(12, AccessType::Lookup);
+    lru_k_replacer_impl.record_access(12, AccessType::Lookup);
+    lru_k_replacer_impl.record_access(12, AccessType::Lookup);
+    lru_k_replacer_impl.record_access(12, AccessType::Lookup);
+    lru_k_replacer_impl.set_evictable(12, true);
+    lru_k_replacer_impl.record_access(23, AccessType::Lookup);
+    lru_k_replacer_impl.set_evictable(23, true);
+    // 23 has fewer than k accesses, so its k-distance is +inf
+    assert_eq!(lru_k_replacer_impl.evict(), Some(23), "matches");
+    assert_eq!(lru_k_replacer_impl.size(), 1, "Evicted frames leave the replacer");
+    assert_eq!(lru_k_replacer_impl.evict(), Some(12), "matches");
+    assert_eq!(lru_k_replacer_impl.evict(), None, "matches");
+    assert_eq!(lru_k_replacer_impl.size(), 0, "Matches")
+}
+
+#[test]
+// Mirrors the BusTub sample test: +inf frames by earliest access, then by kth most recent access.
+fn test_lru_k_impl_backward_k_distance() {
+    let replacer = LRUKReplacerImpl::new(7, 2);
+    for frame_id in 1..=6 {
+        replacer.record_access(frame_id, AccessType::Lookup);
+    }
+    for frame_id in 1..=5 {
+        replacer.set_evictable(frame_id, true);
+    }
+    replacer.set_evictable(6, false);
+    assert_eq!(replacer.size(), 5);
+
+    // Frame 1 now has two accesses, every other frame still has +inf distance
+    replacer.record_access(1, AccessType::Lookup);
+    assert_eq!(replacer.evict(), Some(2));
+    assert_eq!(replacer.evict(), Some(3));
+    assert_eq!(replacer.evict(), Some(4));
+    assert_eq!(replacer.size(), 2);
+
+    replacer.record_access(3, AccessType::Lookup);
+    replacer.record_access(4, AccessType::Lookup);
+    replacer.record_access(5, AccessType::Lookup);
+    replacer.record_access(4, AccessType::Lookup);
+    replacer.set_evictable(3, true);
+    replacer.set_evictable(4, true);
+    assert_eq!(replacer.size(), 4);
+
+    // 3 is back with a single access
+    assert_eq!(replacer.evict(), Some(3));
+    replacer.set_evictable(6, true);
+    assert_eq!(replacer.evict(), Some(6));
+    // 5 and 4 both have two accesses, 5 has the older second most recent access
+    replacer.set_evictable(1, false);
+    assert_eq!(replacer.evict(), Some(5));
+    replacer.record_access(1, AccessType::Lookup);
+    replacer.record_access(1, AccessType::Lookup);
+    replacer.set_evictable(1, true);
+    assert_eq!(replacer.evict(), Some(4));
+    assert_eq!(replacer.evict(), Some(1));
+    assert_eq!(replacer.evict(), None);
+    assert_eq!(replacer.size(), 0);
+}
+
+#[test]
+// A scan touching a hot frame must not refresh its history.
+fn test_lru_k_impl_scan_does_not_pollute_history() {
+    let replacer = LRUKReplacerImpl::new(10, 2);
+    replacer.record_access(1, AccessType::Lookup);
+    replacer.record_access(1, AccessType::Lookup);
+    replacer.record_access(2, AccessType::Lookup);
+    replacer.record_access(2, AccessType::Lookup);
+    for _ in 0..5 {
+        replacer.record_access(1, AccessType::Scan);
+    }
+    // Scanned-only frames are registered with a single access
+    replacer.record_access(3, AccessType::Scan);
+    replacer.record_access(3, AccessType::Scan);
+    for frame_id in 1..=3 {
+        replacer.set_evictable(frame_id, true);
+    }
+    assert_eq!(replacer.evict(), Some(3));
+    assert_eq!(replacer.evict(), Some(1), "Scans should not have promoted frame 1");
+    assert_eq!(replacer.evict(), Some(2));
+}
+
+#[test]
+#[should_panic(expected = "Invalid frame id")]
+fn test_lru_k_impl_rejects_out_of_range_frame_id() {
+    let replacer = LRUKReplacerImpl::new(10, 2);
+    replacer.record_access(10, AccessType::Lookup);
+}
+
+#[test]
+#[should_panic(expected = "k of at least 1")]
+fn test_lru_k_impl_rejects_k_of_zero() {
+    LRUKReplacerImpl::new(10, 0);
+}
+
+#[test]
+#[should_panic(expected = "k of at least 1")]
+fn test_lru_k_policy_rejects_k_of_zero() {
+    ReplacerPolicy::LruK(0).build(10);
+}
+
+#[test]
+// Tens of thousands of frames, evicted in LRU order without rescanning the node store.
+fn test_lru_k_impl_many_frames() {
+    let num_frames = 50_000;
+    let replacer = LRUKReplacerImpl::new(num_frames, 2);
+    for frame_id in 0..num_frames as i32 {
+        replacer.record_access(frame_id, AccessType::Lookup);
+        replacer.set_evictable(frame_id, true);
+    }
+    // Odd frames get a second access and move behind every +inf frame
+    for frame_id in (1..num_frames as i32).step_by(2) {
+        replacer.record_access(frame_id, AccessType::Lookup);
+    }
+    assert_eq!(replacer.size(), num_frames);
+    let evicted: Vec<_> = std::iter::from_fn(|| replacer.evict()).collect();
+    let expected: Vec<_> = (0..num_frames as i32).step_by(2).chain((1..num_frames as i32).step_by(2)).collect();
+    assert_eq!(evicted, expected);
+    assert_eq!(replacer.size(), 0);
+}
+
+#[test]
+// Threads pin, unpin and evict concurrently, no frame may be handed out twice.
+fn test_lru_k_impl_concurrent_stress() {
+    let num_threads = 8;
+    let frames_per_thread = 500;
+    let replacer = Arc::new(LRUKReplacerImpl::new(num_threads * frames_per_thread, 3));
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..num_threads)
+        .map(|t| {
+            let replacer = Arc::clone(&replacer);
+            let evicted = Arc::clone(&evicted);
+            thread::spawn(move || {
+                let first = (t * frames_per_thread) as i32;
+                for round in 0..4 {
+                    for frame_id in first..first + frames_per_thread as i32 {
+                        let access_type = if round % 2 == 0 { AccessType::Lookup } else { AccessType::Scan };
+                        replacer.record_access(frame_id, access_type);
+                        replacer.set_evictable(frame_id, false);
+                        replacer.set_evictable(frame_id, true);
+                    }
+                    for _ in 0..frames_per_thread / 10 {
+                        if let Some(frame_id) = replacer.evict() {
+                            evicted.lock().unwrap().push(frame_id);
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Whatever is left is still evictable
+    let mut evicted = evicted.lock().unwrap().clone();
+    let remaining = replacer.size();
+    evicted.extend(std::iter::from_fn(|| replacer.evict()));
+    assert_eq!(replacer.size(), 0);
+    assert!(remaining > 0);
+    // A frame is registered at most once per round, so it can't be evicted more often than that
+    let mut evictions: HashMap<i32, usize> = HashMap::new();
+    for &frame_id in evicted.iter() {
+        *evictions.entry(frame_id).or_default() += 1;
+    }
+    assert!(evictions.values().all(|&count| count <= 4), "A frame was evicted twice without being accessed again");
+    let unique: HashSet<_> = evicted.iter().collect();
+    assert_eq!(
+        unique.len(),
+        num_threads * frames_per_thread,
+        "Every frame should have been evicted at least once"
+    );
 }
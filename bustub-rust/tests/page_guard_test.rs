@@ -0,0 +1,157 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::AccessType::Index;
+use bustub_rust::include::common::config::PAGE_SIZE;
+use bustub_rust::include::storage::page::page_guard::{PageguardImpl, ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+#[test]
+fn test_dirty_on_first_mutable_access() {
+    let db_file = "page_guard_dirty_test.db";
+    let bpm = setup_bpm(db_file, 2);
+    let page_id = bpm.new_page().unwrap();
+
+    let mut guard = bpm.write_page(page_id, Index);
+    assert_eq!(guard.as_ref()[0], 0);
+    assert!(!guard.is_dirty(), "Reading through a write guard doesn't dirty the page");
+    guard.as_mut();
+    assert!(guard.is_dirty());
+    guard.flush().unwrap();
+    assert!(!guard.is_dirty());
+    drop(guard);
+
+    let guard = bpm.read_page(page_id, Index);
+    assert!(!guard.is_dirty());
+    drop(guard);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_readers_share_and_writers_wait() {
+    let db_file = "page_guard_latch_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let page_id = bpm.new_page().unwrap();
+
+    // Two readers can hold the page at once
+    let reader = bpm.read_page(page_id, Index);
+    let second_reader = bpm.read_page(page_id, Index);
+    assert_eq!(bpm.get_pin_count(page_id), Some(2));
+    drop(second_reader);
+
+    let acquired = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let bpm = bpm.clone();
+        let acquired = Arc::clone(&acquired);
+        thread::spawn(move || {
+            let mut guard = bpm.write_page(page_id, Index);
+            acquired.store(true, Ordering::SeqCst);
+            guard.as_mut()[0] = 7;
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert!(!acquired.load(Ordering::SeqCst), "Writer got the page while a reader held it");
+    assert_eq!(reader.as_ref()[0], 0);
+    drop(reader);
+    writer.join().unwrap();
+    assert!(acquired.load(Ordering::SeqCst));
+    assert_eq!(bpm.read_page(page_id, Index).as_ref()[0], 7);
+    assert_eq!(bpm.get_pin_count(page_id), Some(0));
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_writers_never_alias() {
+    let db_file = "page_guard_writers_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let page_id = bpm.new_page().unwrap();
+    let num_threads = 8;
+    let iterations = 200;
+
+    let handles: Vec<_> = (0..num_threads)
+        .map(|t| {
+            let bpm = bpm.clone();
+            thread::spawn(move || {
+                let marker = t as u8 + 1;
+                for _ in 0..iterations {
+                    let mut guard = bpm.write_page(page_id, Index);
+                    let data = guard.as_mut();
+                    // The body is always filled by exactly one writer
+                    assert!(data[8..].iter().all(|&b| b == data[8]), "Saw a half written page");
+                    data[8..].fill(marker);
+                    thread::yield_now();
+                    assert!(data[8..].iter().all(|&b| b == marker), "Another writer touched the page");
+                    let count = u64::from_le_bytes(data[..8].try_into().unwrap());
+                    data[..8].copy_from_slice(&(count + 1).to_le_bytes());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let guard = bpm.read_page(page_id, Index);
+    let count = u64::from_le_bytes(guard.as_ref()[..8].try_into().unwrap());
+    assert_eq!(count, num_threads * iterations, "Lost an update");
+    drop(guard);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_concurrent_guards_under_eviction() {
+    let db_file = "page_guard_eviction_test.db";
+    let bpm = setup_bpm(db_file, 4);
+    let num_threads = 4;
+    let pages_per_thread = 6;
+    let page_ids: Vec<_> = (0..num_threads * pages_per_thread).map(|_| bpm.new_page().unwrap()).collect();
+
+    let handles: Vec<_> = page_ids
+        .chunks(pages_per_thread)
+        .map(|pages| {
+            let bpm = bpm.clone();
+            let pages = pages.to_vec();
+            thread::spawn(move || {
+                for round in 0..20u8 {
+                    for &page_id in pages.iter() {
+                        let expected = if round == 0 { 0 } else { page_id as u8 ^ (round - 1) };
+                        let mut guard = loop {
+                            // Every frame may be pinned by the other threads for a moment
+                            if let Some(guard) = bpm.checked_write_page(page_id, Index) {
+                                break guard;
+                            }
+                            thread::yield_now();
+                        };
+                        assert_eq!(guard.get_page_id(), page_id);
+                        assert!(guard.as_ref().iter().all(|&b| b == expected), "Page {} has stale contents", page_id);
+                        guard.as_mut().fill(page_id as u8 ^ round);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    for &page_id in page_ids.iter() {
+        assert_eq!(bpm.read_page(page_id, Index).as_ref(), &[page_id as u8 ^ 19; PAGE_SIZE]);
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
@@ -0,0 +1,244 @@
+use bustub_rust::buffer::arc_replacer::ArcReplacerImpl;
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::clock_replacer::ClockReplacerImpl;
+use bustub_rust::buffer::lfu_replacer::LfuReplacerImpl;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::buffer::two_q_replacer::TwoQReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::{LRUKReplacer, ReplacerPolicy};
+use bustub_rust::include::common::config::{AccessType, PAGE_SIZE};
+use bustub_rust::include::storage::page::page_guard::{ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+
+// Every replacement policy has to pass the same contract tests.
+// Policy specific behaviour is tested at the bottom of the file.
+macro_rules! replacer_conformance_tests {
+    ($name:ident, $replacer:ty, $policy:expr) => {
+        mod $name {
+            use super::*;
+
+            fn replacer(num_frames: usize) -> $replacer {
+                <$replacer>::new(num_frames, 2)
+            }
+
+            #[test]
+            fn evict_empty() {
+                let replacer = replacer(10);
+                assert_eq!(replacer.evict(), None);
+                assert_eq!(replacer.size(), 0);
+            }
+
+            #[test]
+            fn new_frames_are_not_evictable() {
+                let replacer = replacer(10);
+                replacer.record_access(1, AccessType::Lookup);
+                replacer.record_access(2, AccessType::Lookup);
+                assert_eq!(replacer.size(), 0);
+                assert_eq!(replacer.evict(), None);
+            }
+
+            #[test]
+            fn size_tracks_evictable_frames() {
+                let replacer = replacer(10);
+                for frame_id in 0..5 {
+                    replacer.record_access(frame_id, AccessType::Lookup);
+                    replacer.set_evictable(frame_id, true);
+                }
+                // Repeated calls don't change the count
+                replacer.set_evictable(0, true);
+                assert_eq!(replacer.size(), 5);
+                replacer.set_evictable(0, false);
+                replacer.set_evictable(0, false);
+                assert_eq!(replacer.size(), 4);
+                // Frames that were never accessed are ignored
+                replacer.set_evictable(9, true);
+                assert_eq!(replacer.size(), 4);
+            }
+
+            #[test]
+            fn pinned_frames_are_never_evicted() {
+                let replacer = replacer(10);
+                for frame_id in 0..4 {
+                    replacer.record_access(frame_id, AccessType::Lookup);
+                    replacer.set_evictable(frame_id, frame_id % 2 == 0);
+                }
+                let evicted: HashSet<_> = std::iter::from_fn(|| replacer.evict()).collect();
+                assert_eq!(evicted, HashSet::from([0, 2]));
+                assert_eq!(replacer.size(), 0);
+            }
+
+            #[test]
+            fn single_access_frames_evict_in_access_order() {
+                let replacer = replacer(10);
+                for frame_id in 0..6 {
+                    replacer.record_access(frame_id, AccessType::Lookup);
+                    replacer.set_evictable(frame_id, true);
+                }
+                let evicted: Vec<_> = std::iter::from_fn(|| replacer.evict()).collect();
+                assert_eq!(evicted, vec![0, 1, 2, 3, 4, 5]);
+            }
+
+            #[test]
+            fn evict_forgets_the_victim() {
+                let replacer = replacer(10);
+                replacer.record_access(3, AccessType::Lookup);
+                replacer.set_evictable(3, true);
+                assert_eq!(replacer.evict(), Some(3));
+                assert_eq!(replacer.size(), 0);
+                // The frame is untracked until it is accessed again
+                replacer.set_evictable(3, true);
+                assert_eq!(replacer.evict(), None);
+                replacer.record_access(3, AccessType::Lookup);
+                assert_eq!(replacer.size(), 0, "A reused frame starts out pinned");
+                replacer.set_evictable(3, true);
+                assert_eq!(replacer.evict(), Some(3));
+            }
+
+            #[test]
+            fn remove_drops_the_frame() {
+                let replacer = replacer(10);
+                for frame_id in 0..3 {
+                    replacer.record_access(frame_id, AccessType::Lookup);
+                    replacer.set_evictable(frame_id, true);
+                }
+                replacer.remove(1);
+                replacer.remove(7); // untracked, no-op
+                assert_eq!(replacer.size(), 2);
+                let evicted: HashSet<_> = std::iter::from_fn(|| replacer.evict()).collect();
+                assert_eq!(evicted, HashSet::from([0, 2]));
+            }
+
+            #[test]
+            #[should_panic(expected = "Invalid frame id")]
+            fn rejects_out_of_range_frame_id() {
+                replacer(4).record_access(4, AccessType::Lookup);
+            }
+
+            #[test]
+            fn concurrent_access() {
+                let replacer: Arc<dyn LRUKReplacer> = Arc::new(replacer(800));
+                let handles: Vec<_> = (0..8)
+                    .map(|t| {
+                        let replacer = Arc::clone(&replacer);
+                        thread::spawn(move || {
+                            for frame_id in t * 100..(t + 1) * 100 {
+                                replacer.record_access(frame_id, AccessType::Lookup);
+                                replacer.record_access(frame_id, AccessType::Scan);
+                                replacer.set_evictable(frame_id, true);
+                            }
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+                assert_eq!(replacer.size(), 800);
+                let evicted: HashSet<_> = std::iter::from_fn(|| replacer.evict()).collect();
+                assert_eq!(evicted.len(), 800);
+            }
+
+            #[test]
+            fn drives_buffer_pool_eviction() {
+                let db_file = concat!("replacer_conformance_", stringify!($name), ".db");
+                let _ = std::fs::remove_file(db_file);
+                let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+                let num_frames = 4;
+                let bpm = BufferPoolManager::new(num_frames, Arc::new(scheduler), $policy.build(num_frames));
+
+                let page_ids: Vec<_> = (0..12).map(|_| bpm.new_page().unwrap()).collect();
+                for (i, &page_id) in page_ids.iter().enumerate() {
+                    let mut guard = bpm.write_page(page_id, AccessType::Lookup);
+                    guard.as_mut().copy_from_slice(&[i as u8 + 1; PAGE_SIZE]);
+                }
+                for (i, &page_id) in page_ids.iter().enumerate().rev() {
+                    let guard = bpm.read_page(page_id, AccessType::Lookup);
+                    assert_eq!(guard.as_ref(), &[i as u8 + 1; PAGE_SIZE]);
+                }
+
+                std::fs::remove_file(db_file).unwrap();
+            }
+        }
+    };
+}
+
+replacer_conformance_tests!(lru_k, LRUKReplacerImpl, ReplacerPolicy::LruK(2));
+replacer_conformance_tests!(clock, ClockReplacerImpl, ReplacerPolicy::Clock);
+replacer_conformance_tests!(two_q, TwoQReplacerImpl, ReplacerPolicy::TwoQ);
+replacer_conformance_tests!(arc, ArcReplacerImpl, ReplacerPolicy::Arc);
+replacer_conformance_tests!(lfu, LfuReplacerImpl, ReplacerPolicy::Lfu);
+
+// Helper for the policy specific tests: registers frames in order and unpins them
+fn register<R: LRUKReplacer>(replacer: &R, frame_ids: &[i32]) {
+    for &frame_id in frame_ids {
+        replacer.record_access(frame_id, AccessType::Lookup);
+        replacer.set_evictable(frame_id, true);
+    }
+}
+
+#[test]
+fn test_clock_gives_referenced_frames_a_second_chance() {
+    let replacer = ClockReplacerImpl::new(4, 0);
+    register(&replacer, &[0, 1, 2, 3]);
+    // First sweep clears every bit and evicts 0, the hand stops at 1
+    assert_eq!(replacer.evict(), Some(0));
+    replacer.record_access(1, AccessType::Lookup);
+    replacer.record_access(2, AccessType::Scan);
+    assert_eq!(replacer.evict(), Some(2), "1 was referenced again, a scan doesn't count");
+    assert_eq!(replacer.evict(), Some(3));
+    assert_eq!(replacer.evict(), Some(1));
+}
+
+#[test]
+fn test_two_q_keeps_hot_frames_over_one_off_frames() {
+    let replacer = TwoQReplacerImpl::new(8, 2);
+    register(&replacer, &[0, 1]);
+    // 0 and 1 become hot, then a burst of one-off frames arrives
+    replacer.record_access(0, AccessType::Lookup);
+    replacer.record_access(1, AccessType::Lookup);
+    register(&replacer, &[2, 3, 4, 5]);
+    // A1 is only trimmed down to a quarter of the pool
+    assert_eq!(replacer.evict(), Some(2));
+    assert_eq!(replacer.evict(), Some(3));
+    // then Am gives up its least recently used frame
+    assert_eq!(replacer.evict(), Some(0));
+    assert_eq!(replacer.evict(), Some(1));
+    assert_eq!(replacer.evict(), Some(4));
+    assert_eq!(replacer.evict(), Some(5));
+}
+
+#[test]
+fn test_arc_adapts_between_recency_and_frequency() {
+    let replacer = ArcReplacerImpl::new(8, 0);
+    register(&replacer, &[0, 1, 2, 3]);
+    // Two T1 hits raise the target for T1 to 2
+    replacer.record_access(0, AccessType::Lookup);
+    replacer.record_access(1, AccessType::Lookup);
+    // T1 = {2, 3} is not above its target, so T2 gives up its LRU frame
+    assert_eq!(replacer.evict(), Some(0));
+    register(&replacer, &[4]);
+    // T1 = {2, 3, 4} is now above the target
+    assert_eq!(replacer.evict(), Some(2));
+    // A T2 hit lowers the target again
+    replacer.record_access(1, AccessType::Lookup);
+    assert_eq!(replacer.evict(), Some(3));
+}
+
+#[test]
+fn test_lfu_evicts_least_frequently_used() {
+    let replacer = LfuReplacerImpl::new(8, 0);
+    register(&replacer, &[0, 1, 2]);
+    for _ in 0..3 {
+        replacer.record_access(0, AccessType::Lookup);
+    }
+    replacer.record_access(2, AccessType::Lookup);
+    // Scans don't add to the count
+    for _ in 0..5 {
+        replacer.record_access(1, AccessType::Scan);
+    }
+    let evicted: Vec<_> = std::iter::from_fn(|| replacer.evict()).collect();
+    assert_eq!(evicted, vec![1, 2, 0]);
+}
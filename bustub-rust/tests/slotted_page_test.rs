@@ -0,0 +1,370 @@
+use bustub_rust::buffer::bufferpool_manager::BufferPoolManager;
+use bustub_rust::buffer::lru_k_replacer::LRUKReplacerImpl;
+use bustub_rust::include::buffer::bufferpool_manager::BufferPoolManagerImpl;
+use bustub_rust::include::buffer::lru_k_replacer::LRUKReplacer;
+use bustub_rust::include::common::config::{AccessType, ValueType, INVALID_PAGE_ID, PAGE_SIZE};
+use bustub_rust::include::common::rid::Rid;
+use bustub_rust::include::storage::index::b_plus_tree::{BplusTree, BplusTreeImpl, IndexMode, IntegrityViolation};
+use bustub_rust::include::storage::index::generic_key::{OrdComparator, VarcharKey};
+use bustub_rust::include::storage::page::b_plus_tree_internal_page::{BplusTreeInternalPage, BplusTreeInternalPageImpl, KeyType};
+use bustub_rust::include::storage::page::b_plus_tree_leaf_page::{BplusTreeLeafPage, BplusTreeLeafPageImpl};
+use bustub_rust::include::storage::page::b_plus_tree_page::{BplusTreePageTrait, BPLUS_TREE_PAGE_HEADER_SIZE};
+use bustub_rust::include::storage::page::page_guard::{ReadPageGuardImpl, WritePageGuardImpl};
+use bustub_rust::storage::disk::disk_manager::DiskManager;
+use bustub_rust::storage::disk::disk_scheduler::DiskScheduler;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+type NameKey = VarcharKey<200>;
+type NameTree = BplusTree<NameKey, OrdComparator>;
+
+fn setup_bpm(db_file: &str, num_frames: usize) -> BufferPoolManager {
+    let _ = std::fs::remove_file(db_file);
+    let scheduler = DiskScheduler::new(DiskManager::new(db_file).unwrap());
+    BufferPoolManager::new(num_frames, Arc::new(scheduler), Arc::new(LRUKReplacerImpl::new(num_frames, 2)))
+}
+
+fn key(s: &str) -> NameKey {
+    VarcharKey::new(s).unwrap()
+}
+
+// Lowercase strings of 1 to max_len bytes, all different
+fn random_names(count: usize, max_len: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut names: Vec<String> = (0..count)
+        .map(|index| {
+            let len = rng.gen_range(1..=max_len);
+            let mut name: String = (0..len).map(|_| rng.gen_range(b'a'..=b'z') as char).collect();
+            // a unique suffix keeps the names apart
+            name.truncate(max_len.saturating_sub(6).max(1));
+            format!("{}{:06}", name, index)
+        })
+        .collect();
+    names.shuffle(&mut rng);
+    names
+}
+
+// Leaf sizes along the leaf chain, starting at the leftmost leaf
+fn leaf_sizes(bpm: &BufferPoolManager, tree: &NameTree) -> Vec<i32> {
+    let mut page_id = tree.get_root_page_id();
+    loop {
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        match BplusTreeInternalPage::<NameKey>::decode(guard.as_ref()) {
+            Ok(internal) => page_id = internal.page_id_value_at(0),
+            Err(_) => break,
+        }
+    }
+    let mut sizes = Vec::new();
+    while page_id != INVALID_PAGE_ID {
+        let guard = bpm.read_page(page_id, AccessType::Index);
+        let leaf = BplusTreeLeafPage::<NameKey>::decode(guard.as_ref()).unwrap();
+        sizes.push(leaf.get_size());
+        page_id = leaf.get_next_page_id();
+    }
+    sizes
+}
+
+#[test]
+fn test_varchar_keys_order_by_bytes() {
+    assert!(key("apple") < key("apples"));
+    assert!(key("") < key("a"));
+    assert!(key("b") > key("abc"));
+    assert_eq!(key("pear"), VarcharKey::from_bytes(b"pear").unwrap());
+    assert_eq!(key("pear").as_bytes(), b"pear");
+    assert_eq!(format!("{:?}", key("pear")), "\"pear\"");
+
+    let err = VarcharKey::<4>::new("too long").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_slotted_leaf_round_trip() {
+    let mut leaf: BplusTreeLeafPage<NameKey> = BplusTreeLeafPage::new(100, 7);
+    leaf.next_page_id = 9;
+    for (index, name) in ["", "a", "pear", &"z".repeat(200)].into_iter().enumerate() {
+        assert!(leaf.insert(index as i32, key(name), ValueType::Rid(Rid::new(1, index as u32))));
+    }
+    let mut page = vec![0u8; PAGE_SIZE];
+    leaf.encode(&mut page);
+    assert_eq!(BplusTreeLeafPage::decode(&page).unwrap(), leaf);
+    assert_eq!(leaf.used_bytes(), BPLUS_TREE_PAGE_HEADER_SIZE + 4 * 12 + 1 + 4 + 200);
+
+    // The first slot sits right after the header and points at the last entry bytes
+    let slot = &page[BPLUS_TREE_PAGE_HEADER_SIZE..BPLUS_TREE_PAGE_HEADER_SIZE + 4];
+    let (pos, len) = (u16::from_le_bytes([slot[0], slot[1]]) as usize, u16::from_le_bytes([slot[2], slot[3]]) as usize);
+    assert_eq!((pos, len), (PAGE_SIZE - 8, 0));
+    // A fixed width key type can't read the page
+    let err = BplusTreeLeafPage::<KeyType>::decode(&page).unwrap_err();
+    assert!(err.to_string().contains("variable-length keys"), "{}", err);
+}
+
+#[test]
+fn test_slotted_internal_round_trip() {
+    for separator_rids in [false, true] {
+        let mut internal: BplusTreeInternalPage<NameKey> = BplusTreeInternalPage::new(50, 3);
+        internal.separator_rids = separator_rids;
+        internal.page_id_array[0] = 10;
+        for (index, name) in ["cherry", "kiwi", &"m".repeat(200)].into_iter().enumerate() {
+            internal.key_array[index] = key(name);
+            internal.rid_array[index] = if separator_rids { Rid::new(2, index as u32) } else { Rid::new(INVALID_PAGE_ID, 0) };
+            internal.page_id_array[index + 1] = 11 + index as i32;
+        }
+        internal.base_page.size_ = 3;
+        let mut page = vec![0u8; PAGE_SIZE];
+        internal.encode(&mut page);
+        let decoded = BplusTreeInternalPage::<NameKey>::decode(&page).unwrap();
+        assert_eq!(decoded.separator_rids, separator_rids);
+        for index in 0..3 {
+            assert_eq!(decoded.key_at(index), internal.key_at(index));
+            assert_eq!(decoded.page_id_value_at(index + 1), 11 + index);
+        }
+        assert_eq!(decoded.page_id_value_at(0), 10);
+        let rid_size = if separator_rids { 8 } else { 0 };
+        assert_eq!(internal.used_bytes(), BPLUS_TREE_PAGE_HEADER_SIZE + 4 + 3 * (4 + rid_size + 4) + 6 + 4 + 200);
+    }
+}
+
+#[test]
+fn test_tree_of_varchar_keys() {
+    let db_file = "slotted_tree_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let tree: NameTree = BplusTree::with_comparator(String::from("name_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+
+    let names = random_names(3000, 200);
+    let mut model = BTreeMap::new();
+    for (slot, name) in names.iter().enumerate() {
+        let rid = Rid::new(1, slot as u32);
+        tree.insert(key(name), ValueType::Rid(rid)).unwrap();
+        model.insert(name.clone(), rid);
+        if slot % 500 == 0 {
+            tree.check_integrity().unwrap();
+        }
+    }
+    tree.check_integrity().unwrap();
+    assert!(tree.insert(key(&names[0]), ValueType::Rid(Rid::new(9, 9))).is_err());
+    assert!(tree.begin().map(|(key, rid)| (String::from_utf8(key.as_bytes().to_vec()).unwrap(), rid)).eq(model.clone()));
+    for name in names.iter().step_by(13) {
+        assert_eq!(tree.get_value(key(name)), vec![model[name]]);
+    }
+
+    // Remove all but a few, checking the tree as it shrinks
+    for (step, name) in names.iter().enumerate().skip(10) {
+        tree.remove(key(name), model[name]);
+        if step % 300 == 0 {
+            assert_eq!(tree.check_integrity(), Ok(()), "after {} removes", step);
+        }
+    }
+    let mut rest: Vec<NameKey> = names[..10].iter().map(|name| key(name)).collect();
+    rest.sort();
+    assert!(tree.begin().map(|(key, _)| key).eq(rest));
+    for name in names[..10].iter() {
+        tree.remove(key(name), model[name]);
+    }
+    assert!(tree.is_empty());
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_removes_keep_slotted_pages_at_min_bytes() {
+    let db_file = "slotted_remove_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let mut rng = rand::thread_rng();
+    for round in 0..3 {
+        let tree: NameTree = BplusTree::with_comparator(String::from("name_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+        // Short and long keys mixed, so moving an entry changes a page by very different amounts
+        let mut names: Vec<String> = (0..6000)
+            .map(|index| {
+                let len = if rng.gen_bool(0.3) { 190 } else { 2 };
+                let fill = (b'a' + rng.gen_range(0..26)) as char;
+                format!("{}{:06}", fill.to_string().repeat(len), index)
+            })
+            .collect();
+        for name in names.iter() {
+            tree.insert(key(name), ValueType::Rid(Rid::new(round, 0))).unwrap();
+        }
+        tree.check_integrity().unwrap();
+        names.shuffle(&mut rng);
+        for (step, name) in names.iter().enumerate() {
+            tree.remove(key(name), Rid::new(round, 0));
+            if step % 100 == 0 {
+                assert_eq!(tree.check_integrity(), Ok(()), "after {} removes", step);
+            }
+        }
+        assert!(tree.is_empty());
+    }
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_short_keys_pack_more_entries_per_leaf() {
+    let db_file = "slotted_packing_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let mut leaves_by_len = Vec::new();
+    for len in [8, 190] {
+        let tree: NameTree = BplusTree::with_comparator(String::from("packed_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+        for index in 0..2000 {
+            let name = format!("{:0width$}", index, width = len);
+            tree.insert(key(&name), ValueType::Rid(Rid::new(0, index))).unwrap();
+        }
+        tree.check_integrity().unwrap();
+        leaves_by_len.push(leaf_sizes(&bpm, &tree).len());
+    }
+    // A leaf holds about 4064 / (12 + len) entries
+    assert!(leaves_by_len[0] * 8 < leaves_by_len[1], "{:?}", leaves_by_len);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_non_unique_varchar_index() {
+    let db_file = "slotted_non_unique_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::NON_UNIQUE_SLOT_CNT as i32 - 1;
+    let tree: NameTree = BplusTree::with_mode(String::from("city_index"), &bpm, OrdComparator, IndexMode::NonUnique, 300, internal_max_size, INVALID_PAGE_ID);
+    let cities = ["Amsterdam", &"Llanfairpwllgwyngyll".repeat(8), "Oslo", &"x".repeat(150)];
+    for slot in 0..2000 {
+        let city = cities[slot as usize % cities.len()];
+        tree.insert(key(city), ValueType::Rid(Rid::new(2, slot))).unwrap();
+    }
+    tree.check_integrity().unwrap();
+    let expected: Vec<Rid> = (0..2000).filter(|slot| slot % 4 == 1).map(|slot| Rid::new(2, slot)).collect();
+    assert_eq!(tree.get_value(key(cities[1])), expected);
+    for slot in (0..2000).filter(|slot| slot % 4 == 1) {
+        tree.remove(key(cities[1]), Rid::new(2, slot));
+    }
+    tree.check_integrity().unwrap();
+    assert_eq!(tree.get_value(key(cities[1])), vec![]);
+    assert_eq!(tree.get_value(key("Oslo")).len(), 500);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_bulk_load_varchar_keys_and_restart() {
+    let db_file = "slotted_bulk_load_test.db";
+    let names = random_names(4000, 120);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let header_page_id = {
+        let bpm = setup_bpm(db_file, 64);
+        for fill_factor in [0.3, 1.0] {
+            let tree: NameTree = BplusTree::with_comparator(String::from("loaded_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+            tree.bulk_load(names.iter().enumerate().map(|(slot, name)| (key(name), Rid::new(3, slot as u32))), fill_factor).unwrap();
+            tree.check_integrity().unwrap();
+            assert_eq!(leaf_sizes(&bpm, &tree).iter().sum::<i32>(), 4000);
+        }
+        let tree: NameTree = BplusTree::with_comparator(String::from("loaded_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+        tree.bulk_load(names.iter().enumerate().map(|(slot, name)| (key(name), Rid::new(3, slot as u32))), 0.8).unwrap();
+        bpm.flush_all_pages().unwrap();
+        tree.header_page_id
+    };
+
+    let bpm = BufferPoolManager::new(
+        16,
+        Arc::new(DiskScheduler::new(DiskManager::new(db_file).unwrap())),
+        Arc::new(LRUKReplacerImpl::new(16, 2)),
+    );
+    let tree: NameTree = BplusTree::with_comparator(String::from("loaded_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, header_page_id);
+    for (slot, name) in names.iter().enumerate().step_by(17) {
+        assert_eq!(tree.get_value(key(name)), vec![Rid::new(3, slot as u32)]);
+    }
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert!(tree.begin().map(|(key, _)| key).eq(sorted.iter().map(|name| key(name))));
+    tree.insert(key("a new name"), ValueType::Rid(Rid::new(4, 0))).unwrap();
+    tree.check_integrity().unwrap();
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+// The children of an internal page
+fn children(bpm: &BufferPoolManager, page_id: i32) -> Vec<i32> {
+    let guard = bpm.read_page(page_id, AccessType::Index);
+    let internal = BplusTreeInternalPage::<NameKey>::decode(guard.as_ref()).unwrap();
+    (0..=internal.get_size()).map(|index| internal.page_id_value_at(index)).collect()
+}
+
+fn leaf_keys(bpm: &BufferPoolManager, page_id: i32) -> Vec<NameKey> {
+    let guard = bpm.read_page(page_id, AccessType::Index);
+    let leaf = BplusTreeLeafPage::<NameKey>::decode(guard.as_ref()).unwrap();
+    leaf.key_array[..leaf.get_size() as usize].to_vec()
+}
+
+#[test]
+fn test_borrow_splits_a_parent_its_longer_separator_overflows() {
+    let db_file = "slotted_borrow_split_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let new_tree = || -> NameTree { BplusTree::with_comparator(String::from("full_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID) };
+    // Short keys fill two levels of internal pages with short separators, long enough
+    // for the internal pages to run out of bytes before they run out of slots
+    let mut names: Vec<String> = (0..40_000).map(|index| format!("key-{:010}", index)).collect();
+    let load = |tree: &NameTree, names: &[String]| tree.bulk_load(names.iter().map(|name| (key(name), Rid::new(0, 0))), 1.0).unwrap();
+    let probe = new_tree();
+    load(&probe, &names);
+    let first_internal = children(&bpm, probe.get_root_page_id())[0];
+    let right_leaf = children(&bpm, first_internal)[1];
+    let right_first = leaf_keys(&bpm, right_leaf)[0];
+
+    // A long key right after the first key of the second leaf becomes the separator
+    // once the first leaf, the only one that borrows from its right sibling, takes that key
+    let long_name = format!("{}{}", String::from_utf8(right_first.as_bytes().to_vec()).unwrap(), "x".repeat(180));
+    names.push(long_name.clone());
+    names.sort();
+    let tree = new_tree();
+    load(&tree, &names);
+    let first_internal = children(&bpm, tree.get_root_page_id())[0];
+    let leaves = children(&bpm, first_internal);
+    assert_eq!(leaf_keys(&bpm, leaves[1])[..2], [right_first, key(&long_name)]);
+    let root_size = children(&bpm, tree.get_root_page_id()).len();
+
+    for left_key in leaf_keys(&bpm, leaves[0]) {
+        tree.remove(left_key, Rid::new(0, 0));
+        if children(&bpm, tree.get_root_page_id()).len() > root_size {
+            break;
+        }
+    }
+    assert_eq!(children(&bpm, tree.get_root_page_id()).len(), root_size + 1, "The parent didn't split");
+    assert_eq!(leaf_keys(&bpm, leaves[1])[0], key(&long_name));
+    assert_eq!(tree.check_integrity(), Ok(()));
+    assert_eq!(tree.get_value(key(&long_name)), vec![Rid::new(0, 0)]);
+
+    std::fs::remove_file(db_file).unwrap();
+}
+
+#[test]
+fn test_integrity_reports_slotted_pages_under_their_min_bytes() {
+    let db_file = "slotted_underflow_test.db";
+    let bpm = setup_bpm(db_file, 64);
+    let leaf_max_size = BplusTreeLeafPage::<NameKey>::SLOT_CNT as i32;
+    let internal_max_size = BplusTreeInternalPage::<NameKey>::SLOT_CNT as i32 - 1;
+    let tree: NameTree = BplusTree::with_comparator(String::from("name_index"), &bpm, OrdComparator, leaf_max_size, internal_max_size, INVALID_PAGE_ID);
+    for index in 0..2000 {
+        tree.insert(key(&format!("key-{:010}", index)), ValueType::Rid(Rid::new(0, index))).unwrap();
+    }
+    tree.check_integrity().unwrap();
+
+    // Cut a leaf down to its first entry, the keys left stay in order
+    let leaf_id = children(&bpm, tree.get_root_page_id())[1];
+    let mut guard = bpm.write_page(leaf_id, AccessType::Index);
+    let mut leaf = BplusTreeLeafPage::<NameKey>::decode(guard.as_ref()).unwrap();
+    leaf.base_page.size_ = 1;
+    leaf.encode(guard.as_mut());
+    drop(guard);
+    let min_used = BPLUS_TREE_PAGE_HEADER_SIZE + (PAGE_SIZE - BPLUS_TREE_PAGE_HEADER_SIZE) / 4;
+    assert_eq!(tree.check_integrity(), Err(vec![IntegrityViolation::ByteUnderflow { page_id: leaf_id, used_bytes: leaf.used_bytes(), min_used }]));
+
+    std::fs::remove_file(db_file).unwrap();
+}